@@ -0,0 +1,103 @@
+//! Times the serial and parallel obj parsers on each bundled model and checks they agree.
+//! Run it with `cargo bench`, as the parsers are far slower without optimisations.
+extern crate rust_game_engine;
+
+use rust_game_engine::{
+    graphics::MeshDescriptions,
+    io::Vfs,
+    parser::{FromReaderWithMode, ParseError, ParseMode, Parsed},
+};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MODELS: [&str; 3] = [
+    "content/Millenium Falcon/millenium-falcon.obj",
+    "content/Earth/earth.obj",
+    "content/IronMan/IronMan.obj",
+];
+
+// how many times each parser is timed on each model
+const RUNS: u32 = 5;
+
+fn main() {
+    let mut vfs = Vfs::new();
+    vfs.mount_directory("content", "content");
+
+    let available = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let mut thread_counts = vec![1, 2, 4, available];
+    thread_counts.sort();
+    thread_counts.dedup();
+
+    for path in MODELS.iter() {
+        let source = match vfs.read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                println!("Skipping {}: {}", path, e);
+                continue;
+            }
+        };
+        let resolver = vfs.resolver(path);
+        let mode = ParseMode::Lenient;
+
+        let (serial, serial_time) = fastest(|| {
+            MeshDescriptions::from_reader_with_mode(source.as_bytes(), path, &resolver, mode)
+        });
+        let serial = describe(&serial);
+        println!(
+            "{} ({} KiB): serial {:?}, fastest of {} runs",
+            path,
+            source.len() / 1024,
+            serial_time,
+            RUNS
+        );
+
+        for &threads in thread_counts.iter() {
+            let (parallel, parallel_time) = fastest(|| {
+                MeshDescriptions::from_source_parallel(&source, path, &resolver, mode, threads)
+            });
+
+            println!(
+                "    parallel on {} threads {:?} ({:.2}x), output {}",
+                threads,
+                parallel_time,
+                serial_time.as_secs_f64() / parallel_time.as_secs_f64(),
+                if describe(&parallel) == serial {
+                    "identical"
+                } else {
+                    "DIFFERS"
+                }
+            );
+        }
+        if available == 1 {
+            println!("    only one core is available so there is no speed-up to measure");
+        }
+    }
+}
+
+// runs `work` once to warm up and then `RUNS` more times,
+// returning the result of the last run along with the fastest time
+fn fastest<T, F>(mut work: F) -> (T, Duration)
+where
+    F: FnMut() -> T,
+{
+    let mut result = work();
+    let mut best = Duration::MAX;
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        result = work();
+        best = best.min(start.elapsed());
+    }
+
+    (result, best)
+}
+
+fn describe(parsed: &Result<Parsed<MeshDescriptions>, ParseError>) -> String {
+    match parsed {
+        Ok(parsed) => format!("{}\n{} warnings", parsed.value.describe(), parsed.warnings.len()),
+        Err(e) => e.to_string(),
+    }
+}
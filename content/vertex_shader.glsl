@@ -1,17 +1,19 @@
 #version 150
 in vec4 position;
 in vec3 normal;
+in vec4 tangent;
 in vec3 texture;
 out vec3 v_normal;
+out vec4 v_tangent;
 out vec3 v_position;
 out vec2 v_tex_coords;
 uniform mat4 perspective;
-uniform mat4 view;
-uniform mat4 model;
+uniform mat4 modelview;
+uniform mat3 normal_matrix;
 void main() {
     v_tex_coords = texture.xy;
-    mat4 modelview = view * model;
-    v_normal = transpose(inverse(mat3(modelview))) * normal;
+    v_normal = normal_matrix * normal;
+    v_tangent = vec4(mat3(modelview) * tangent.xyz, tangent.w);
     gl_Position = perspective * modelview * position;
     v_position = gl_Position.xyz / gl_Position.w;
 }
\ No newline at end of file
@@ -0,0 +1,350 @@
+//! A module containing typed asset handles and the cache that hands them out.
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A reference to an asset held by an [`AssetCache`]
+///
+/// # Remarks
+///
+/// Cloning a handle adds a reference to the asset and dropping one removes it. An asset
+/// is never unloaded while a handle to it exists.
+pub struct Handle<T> {
+    id: usize,
+    refs: Arc<()>,
+    asset: PhantomData<fn() -> T>,
+}
+
+/// A collection of assets keyed by their canonical path
+///
+/// # Remarks
+///
+/// Loading the same path twice returns a handle to the same asset, and assets that no
+/// longer have any handles can be unloaded with [`AssetCache::unload_unused`].
+/// A path can be reserved before its asset is ready, handles to it refer to nothing until
+/// the asset is inserted.
+pub struct AssetCache<T> {
+    slots: Vec<Option<Slot<T>>>,
+    paths: HashMap<String, usize>,
+    free: Vec<usize>,
+}
+
+struct Slot<T> {
+    path: String,
+    asset: Option<T>,
+    refs: Arc<()>,
+}
+
+impl<T> Handle<T> {
+    /// returns the id of the asset within its cache
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Handle<T> {
+        Handle {
+            id: self.id,
+            refs: self.refs.clone(),
+            asset: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Handle<T>) -> bool {
+        self.id == other.id && Arc::ptr_eq(&self.refs, &other.refs)
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.id)
+    }
+}
+
+impl<T> Slot<T> {
+    fn handle(&self, id: usize) -> Handle<T> {
+        Handle {
+            id: id,
+            refs: self.refs.clone(),
+            asset: PhantomData,
+        }
+    }
+}
+
+impl<T> AssetCache<T> {
+    /// Creates an empty cache
+    pub fn new() -> AssetCache<T> {
+        AssetCache {
+            slots: Vec::new(),
+            paths: HashMap::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// returns a handle to the asset loaded from a path, if it has been loaded or reserved
+    ///
+    /// # Arguments
+    /// `path` - the canonical path of the asset
+    pub fn find(&self, path: &str) -> Option<Handle<T>> {
+        self.paths
+            .get(path)
+            .and_then(|&id| self.slots[id].as_ref().map(|slot| slot.handle(id)))
+    }
+
+    /// Adds an asset to the cache, replacing any asset already loaded from the same path
+    ///
+    /// # Arguments
+    /// `path` - the canonical path of the asset
+    /// `asset` - the asset
+    ///
+    /// # Remarks
+    ///
+    /// When an asset is replaced the existing handles refer to the new asset.
+    pub fn insert(&mut self, path: &str, asset: T) -> Handle<T> {
+        let handle = self.reserve(path);
+        if let Some(ref mut slot) = self.slots[handle.id] {
+            slot.asset = Some(asset);
+        }
+
+        handle
+    }
+
+    /// Reserves a path for an asset that isn't ready yet
+    ///
+    /// # Arguments
+    /// `path` - the canonical path of the asset
+    ///
+    /// # Remarks
+    ///
+    /// When the path is already loaded or reserved a handle to it is returned.
+    pub fn reserve(&mut self, path: &str) -> Handle<T> {
+        if let Some(handle) = self.find(path) {
+            return handle;
+        }
+
+        let id = match self.free.pop() {
+            Some(id) => id,
+            None => {
+                self.slots.push(None);
+                self.slots.len() - 1
+            }
+        };
+
+        let slot = Slot {
+            path: path.to_owned(),
+            asset: None,
+            refs: Arc::new(()),
+        };
+        let handle = slot.handle(id);
+        self.slots[id] = Some(slot);
+        self.paths.insert(path.to_owned(), id);
+
+        handle
+    }
+
+    /// Removes the asset loaded from a path, even if it still has handles
+    /// the handles that refer to it will refer to nothing
+    ///
+    /// # Arguments
+    /// `path` - the canonical path of the asset
+    pub fn remove(&mut self, path: &str) -> Option<T> {
+        let id = self.paths.remove(path)?;
+        self.free.push(id);
+        self.slots[id].take().and_then(|slot| slot.asset)
+    }
+
+    /// returns the asset that a handle refers to, or None when it isn't ready yet
+    ///
+    /// # Arguments
+    /// `handle` - the handle, this should come from this cache
+    pub fn get(&self, handle: &Handle<T>) -> Option<&T> {
+        self.slot(handle).and_then(|slot| slot.asset.as_ref())
+    }
+
+    /// returns true when the asset that a handle refers to is ready
+    ///
+    /// # Arguments
+    /// `handle` - the handle, this should come from this cache
+    pub fn is_loaded(&self, handle: &Handle<T>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    /// returns the canonical path of the asset that a handle refers to
+    ///
+    /// # Arguments
+    /// `handle` - the handle, this should come from this cache
+    pub fn path(&self, handle: &Handle<T>) -> Option<&str> {
+        self.slot(handle).map(|slot| &slot.path[..])
+    }
+
+    /// returns the number of handles to an asset, not counting the cache itself
+    ///
+    /// # Arguments
+    /// `handle` - the handle, this should come from this cache
+    pub fn ref_count(&self, handle: &Handle<T>) -> usize {
+        self.slot(handle)
+            .map_or(0, |slot| Arc::strong_count(&slot.refs) - 1)
+    }
+
+    /// returns the paths of every loaded asset
+    pub fn paths(&self) -> Vec<&str> {
+        self.paths.keys().map(|p| &p[..]).collect()
+    }
+
+    /// returns the number of loaded assets
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// returns true when no assets are loaded
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Unloads every asset that no longer has any handles
+    ///
+    /// returns the number of assets unloaded
+    pub fn unload_unused(&mut self) -> usize {
+        let mut count = 0;
+
+        for id in 0..self.slots.len() {
+            let unused = match self.slots[id] {
+                Some(ref slot) => Arc::strong_count(&slot.refs) == 1,
+                None => false,
+            };
+
+            if unused {
+                if let Some(slot) = self.slots[id].take() {
+                    self.paths.remove(&slot.path);
+                }
+                self.free.push(id);
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    fn slot(&self, handle: &Handle<T>) -> Option<&Slot<T>> {
+        match self.slots.get(handle.id) {
+            Some(&Some(ref slot)) if Arc::ptr_eq(&slot.refs, &handle.refs) => Some(slot),
+            _ => None,
+        }
+    }
+}
+
+impl<T> Default for AssetCache<T> {
+    fn default() -> AssetCache<T> {
+        AssetCache::new()
+    }
+}
+
+impl<T> fmt::Debug for AssetCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AssetCache")
+            .field("paths", &self.paths.keys().collect::<Vec<&String>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_path_gives_the_same_asset() {
+        let mut cache = AssetCache::new();
+        let a = cache.insert("content/a.obj", 1);
+        let b = cache.insert("content/b.obj", 2);
+
+        assert_eq!(cache.find("content/a.obj"), Some(a.clone()));
+        assert_eq!(cache.reserve("content/a.obj"), a);
+        assert_ne!(a, b);
+        assert_eq!(cache.get(&a), Some(&1));
+        assert_eq!(cache.path(&b), Some("content/b.obj"));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.find("content/c.obj"), None);
+    }
+
+    #[test]
+    fn inserting_again_replaces_the_asset_behind_existing_handles() {
+        let mut cache = AssetCache::new();
+        let handle = cache.insert("a", 1);
+        let again = cache.insert("a", 2);
+
+        assert_eq!(handle, again);
+        assert_eq!(cache.get(&handle), Some(&2));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn reserved_paths_refer_to_nothing_until_inserted() {
+        let mut cache = AssetCache::new();
+        let handle = cache.reserve("a");
+
+        assert!(!cache.is_loaded(&handle));
+        assert_eq!(cache.get(&handle), None);
+
+        cache.insert("a", 1);
+        assert!(cache.is_loaded(&handle));
+    }
+
+    #[test]
+    fn handles_are_counted() {
+        let mut cache = AssetCache::new();
+        let handle = cache.insert("a", 1);
+        assert_eq!(cache.ref_count(&handle), 1);
+
+        let clone = handle.clone();
+        let found = cache.find("a").unwrap();
+        assert_eq!(cache.ref_count(&handle), 3);
+
+        drop(clone);
+        drop(found);
+        assert_eq!(cache.ref_count(&handle), 1);
+    }
+
+    #[test]
+    fn only_assets_without_handles_are_unloaded() {
+        let mut cache = AssetCache::new();
+        let kept = cache.insert("kept", 1);
+        let dropped = cache.insert("dropped", 2);
+        let cloned = dropped.clone();
+
+        drop(dropped);
+        assert_eq!(cache.unload_unused(), 0);
+
+        drop(cloned);
+        assert_eq!(cache.unload_unused(), 1);
+        assert_eq!(cache.paths(), vec!["kept"]);
+        assert_eq!(cache.get(&kept), Some(&1));
+        assert_eq!(cache.find("dropped"), None);
+    }
+
+    #[test]
+    fn a_stale_handle_does_not_see_a_reused_slot() {
+        let mut cache = AssetCache::new();
+        let old = cache.insert("old", 1);
+        assert_eq!(cache.remove("old"), Some(1));
+
+        let new = cache.insert("new", 2);
+        assert_eq!(old.id(), new.id());
+        assert_ne!(old, new);
+        assert_eq!(cache.get(&old), None);
+        assert_eq!(cache.ref_count(&old), 0);
+        assert_eq!(cache.get(&new), Some(&2));
+    }
+}
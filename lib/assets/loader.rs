@@ -0,0 +1,409 @@
+//! A module containing the worker threads that load assets in the background.
+//! Workers only do the work that doesn't need the GPU, such as parsing and decoding,
+//! anything that needs the GPU is finished on the main thread by the asset manager.
+use assets::AssetError;
+use glium::texture::RawImage2d;
+use graphics::{decode_texture, Material, MeshDescriptions, NormalGeneration};
+use io::Vfs;
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// The kinds of asset that can be loaded
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    /// The meshes and materials of an obj file
+    Mesh,
+    /// A material from an mtl file
+    Material,
+    /// A texture that holds data, such as a normal map
+    Texture,
+    /// A texture that holds colors, such as a diffuse map
+    SrgbTexture,
+    /// A shader program
+    Program,
+}
+
+/// How far an asset has got through loading
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadStatus {
+    /// The asset is waiting for a worker
+    Queued,
+    /// A worker is loading the asset
+    Loading,
+    /// The asset has loaded
+    Loaded,
+    /// The asset failed to load, with a description of the problem
+    Failed(String),
+}
+
+/// The progress of a single asset that is loading in the background
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetProgress {
+    /// The kind of asset
+    pub kind: AssetKind,
+    /// The canonical path of the asset
+    pub path: String,
+    /// How far the asset has got through loading
+    pub status: LoadStatus,
+}
+
+/// The progress of every asset requested since the last time everything finished loading
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadProgress {
+    /// The assets that have been requested
+    pub assets: Vec<AssetProgress>,
+}
+
+/// Reports that an asset loading in the background has finished
+#[derive(Debug)]
+pub struct LoadEvent {
+    /// The kind of asset
+    pub kind: AssetKind,
+    /// The canonical path of the asset
+    pub path: String,
+    /// Whether the asset loaded, the asset can be fetched with its handle when it did
+    pub result: Result<(), AssetError>,
+    /// True when an asset that was already loaded has been reloaded because its files changed,
+    /// when a reload fails the last version that loaded is kept
+    pub reloaded: bool,
+}
+
+impl LoadStatus {
+    /// returns true when the asset has either loaded or failed to load
+    pub fn is_finished(&self) -> bool {
+        match self {
+            LoadStatus::Loaded | LoadStatus::Failed(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl LoadProgress {
+    /// returns the number of assets that have been requested
+    pub fn total(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// returns the number of assets that have either loaded or failed to load
+    pub fn finished(&self) -> usize {
+        self.assets.iter().filter(|a| a.status.is_finished()).count()
+    }
+
+    /// returns the number of assets that failed to load
+    pub fn failed(&self) -> usize {
+        self.assets
+            .iter()
+            .filter(|a| match a.status {
+                LoadStatus::Failed(_) => true,
+                _ => false,
+            })
+            .count()
+    }
+
+    /// returns how far through loading the assets are, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.assets.is_empty() {
+            1.0
+        } else {
+            self.finished() as f32 / self.total() as f32
+        }
+    }
+
+    /// returns true when every asset has either loaded or failed to load
+    pub fn is_complete(&self) -> bool {
+        self.assets.iter().all(|a| a.status.is_finished())
+    }
+}
+
+// the work a worker should do
+pub enum Request {
+    Mesh(String, MeshOptions),
+    Materials(String),
+    Image(String),
+    Sources(String, String),
+}
+
+// what is generated for a mesh once it has been parsed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeshOptions {
+    pub normals: Option<NormalGeneration>,
+    pub tangents: bool,
+}
+
+impl MeshOptions {
+    pub fn apply(&self, mesh: &mut MeshDescriptions) {
+        if let Some(generation) = self.normals {
+            mesh.generate_normals(generation);
+        }
+        if self.tangents {
+            mesh.generate_tangents();
+        }
+    }
+}
+
+// the result of a worker's work, ready to be finished on the main thread
+pub enum Loaded {
+    // the mesh along with every file that was read to load it
+    Mesh(MeshDescriptions, Vec<String>),
+    Materials(Vec<Material>),
+    Image(RawImage2d<'static, u8>),
+    Sources(String, String),
+}
+
+pub struct Job {
+    pub kind: AssetKind,
+    pub key: String,
+    pub request: Request,
+    pub reload: bool,
+}
+
+pub enum LoadMessage {
+    Started(AssetKind, String),
+    Finished(Job, Result<Loaded, AssetError>),
+}
+
+// a pool of worker threads, the workers stop when the loader is dropped
+pub struct Loader {
+    jobs: Sender<Job>,
+    results: Receiver<LoadMessage>,
+    workers: usize,
+}
+
+impl Loader {
+    pub fn new(vfs: Arc<Vfs>, workers: usize) -> Loader {
+        let (jobs, job_receiver) = channel::<Job>();
+        let (result_sender, results) = channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for _ in 0..workers {
+            let vfs = vfs.clone();
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+
+            thread::spawn(move || loop {
+                let job = match job_receiver.lock() {
+                    Ok(receiver) => match receiver.recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    },
+                    Err(_) => break,
+                };
+
+                let started = LoadMessage::Started(job.kind, job.key.clone());
+                if result_sender.send(started).is_err() {
+                    break;
+                }
+
+                // a panic is reported like any other failure so the asset doesn't stay loading
+                let result = catch_panic(|| load(&vfs, &job.request));
+                if result_sender.send(LoadMessage::Finished(job, result)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        Loader {
+            jobs: jobs,
+            results: results,
+            workers: workers,
+        }
+    }
+
+    pub fn submit(&self, job: Job) {
+        // the workers only stop once the loader is dropped so this can't fail
+        self.jobs.send(job).ok();
+    }
+
+    pub fn messages(&self) -> Vec<LoadMessage> {
+        self.results.try_iter().collect()
+    }
+}
+
+impl fmt::Debug for Loader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Loader ({} workers)", self.workers)
+    }
+}
+
+fn catch_panic<F>(load: F) -> Result<Loaded, AssetError>
+where
+    F: FnOnce() -> Result<Loaded, AssetError>,
+{
+    panic::catch_unwind(AssertUnwindSafe(load))
+        .unwrap_or_else(|payload| Err(AssetError::Panicked(panic_message(payload))))
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_owned(),
+        },
+    }
+}
+
+fn load(vfs: &Vfs, request: &Request) -> Result<Loaded, AssetError> {
+    match request {
+        Request::Mesh(path, options) => {
+            let (mut mesh, files) = vfs.parse_tracked::<MeshDescriptions, _>(path)?;
+            options.apply(&mut mesh);
+            Ok(Loaded::Mesh(mesh, files))
+        }
+        Request::Materials(path) => Ok(Loaded::Materials(vfs.parse::<Material, _>(path)?)),
+        Request::Image(path) => Ok(Loaded::Image(decode_texture(&vfs.read(path)?, path)?)),
+        Request::Sources(vertex, fragment) => Ok(Loaded::Sources(
+            vfs.read_to_string(vertex)?,
+            vfs.read_to_string(fragment)?,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use io::DirectoryMount;
+    use parser::ParseErrorKind;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use std::time::{Duration, Instant};
+
+    // starts a loader with one worker over a directory holding a single triangle,
+    // the directory should be removed once the test is done with it
+    fn loader(name: &str) -> (Loader, PathBuf) {
+        let root = env::temp_dir().join(format!("loader-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.obj"), "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.mount("content", DirectoryMount::new(&root));
+        (Loader::new(Arc::new(vfs), 1), root)
+    }
+
+    // waits for a job to finish, returning every message that was sent for it
+    fn wait(loader: &Loader) -> Vec<LoadMessage> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut messages = Vec::new();
+        loop {
+            messages.extend(loader.messages());
+            if let Some(&LoadMessage::Finished(..)) = messages.last() {
+                return messages;
+            }
+            assert!(Instant::now() < deadline, "timed out waiting for the job");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn mesh_job(path: &str) -> Job {
+        Job {
+            kind: AssetKind::Mesh,
+            key: path.to_owned(),
+            request: Request::Mesh(path.to_owned(), MeshOptions::default()),
+            reload: false,
+        }
+    }
+
+    #[test]
+    fn a_mesh_is_started_then_finished() {
+        let (loader, root) = loader("mesh");
+        loader.submit(mesh_job("content/a.obj"));
+
+        let mut messages = wait(&loader).into_iter();
+        match messages.next() {
+            Some(LoadMessage::Started(AssetKind::Mesh, key)) => assert_eq!(key, "content/a.obj"),
+            _ => panic!("expected the job to start first"),
+        }
+        match messages.next() {
+            Some(LoadMessage::Finished(job, Ok(Loaded::Mesh(mesh, files)))) => {
+                assert_eq!(job.key, "content/a.obj");
+                assert_eq!(mesh.meshes[0].indices.len(), 3);
+                assert_eq!(files, vec!["content/a.obj".to_owned()]);
+            }
+            _ => panic!("expected the mesh to load"),
+        }
+        assert!(messages.next().is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn a_missing_file_fails_to_load() {
+        let (loader, root) = loader("missing");
+        loader.submit(mesh_job("content/missing.obj"));
+
+        match wait(&loader).pop() {
+            Some(LoadMessage::Finished(job, Err(AssetError::Parse(e)))) => {
+                assert_eq!(job.key, "content/missing.obj");
+                match e.kind {
+                    ParseErrorKind::MissingFile => (),
+                    ref kind => panic!("expected a missing file, not {:?}", kind),
+                }
+            }
+            _ => panic!("expected the mesh to fail to load"),
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn progress_counts_finished_and_failed_assets() {
+        let asset = |path: &str, status: LoadStatus| AssetProgress {
+            kind: AssetKind::Mesh,
+            path: path.to_owned(),
+            status: status,
+        };
+        let mut progress = LoadProgress {
+            assets: vec![
+                asset("a.obj", LoadStatus::Queued),
+                asset("b.obj", LoadStatus::Loading),
+                asset("c.obj", LoadStatus::Loaded),
+                asset("d.obj", LoadStatus::Failed("d.obj was not found".to_owned())),
+            ],
+        };
+
+        assert_eq!(progress.total(), 4);
+        assert_eq!(progress.finished(), 2);
+        assert_eq!(progress.failed(), 1);
+        assert_eq!(progress.fraction(), 0.5);
+        assert!(!progress.is_complete());
+
+        progress.assets[0].status = LoadStatus::Loaded;
+        progress.assets[1].status = LoadStatus::Failed("bad mesh".to_owned());
+        assert_eq!(progress.failed(), 2);
+        assert_eq!(progress.fraction(), 1.0);
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn nothing_requested_is_complete() {
+        let progress = LoadProgress::default();
+        assert_eq!(progress.total(), 0);
+        assert_eq!(progress.fraction(), 1.0);
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn a_panic_is_reported_as_an_error() {
+        match catch_panic(|| panic!("bad {}", "mesh")) {
+            Err(AssetError::Panicked(message)) => assert_eq!(message, "bad mesh"),
+            _ => panic!("expected the panic to be reported"),
+        }
+        match catch_panic(|| panic!("bad mesh")) {
+            Err(AssetError::Panicked(message)) => assert_eq!(message, "bad mesh"),
+            _ => panic!("expected the panic to be reported"),
+        }
+    }
+
+    #[test]
+    fn errors_are_passed_through() {
+        match catch_panic(|| Err(AssetError::NotFound("mesh.obj".to_owned()))) {
+            Err(AssetError::NotFound(path)) => assert_eq!(path, "mesh.obj"),
+            _ => panic!("expected the error to be passed through"),
+        }
+    }
+}
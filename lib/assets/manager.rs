@@ -0,0 +1,1085 @@
+//! A module containing the asset manager, which loads and owns the content used by a game.
+use assets::handle::{AssetCache, Handle};
+use assets::loader::{Job, LoadMessage, Loaded, Loader, MeshOptions, Request};
+use assets::{AssetError, AssetKind, AssetProgress, LoadEvent, LoadProgress, LoadStatus};
+use glium::texture::{SrgbTexture2d, Texture2d};
+use glium::{Display, Program};
+use graphics::{decode_texture, Material, MeshDescriptions, NormalGeneration};
+use io::Vfs;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Loads meshes, materials, textures and shader programs from a [`Vfs`] and owns them
+///
+/// # Remarks
+///
+/// Every asset is cached by its canonical path, so loading the same file twice, even through
+/// a differently written path, returns a handle to the asset that is already loaded.
+///
+/// Assets loaded with the `_async` methods are parsed and decoded on worker threads, their
+/// handles refer to nothing until [`AssetManager::update`] has finished them on the main thread.
+/// Meshes and materials can also be finished by [`AssetManager::poll`], which doesn't need a
+/// display.
+///
+/// With hot reloading turned on, the files of every loaded asset are watched and an asset is
+/// loaded again when any of its files change. The new version is swapped in behind the
+/// existing handles, or the last good version is kept when the new one fails to load.
+pub struct AssetManager {
+    vfs: Arc<Vfs>,
+    meshes: AssetCache<MeshDescriptions>,
+    materials: AssetCache<Material>,
+    textures: AssetCache<Texture2d>,
+    srgb_textures: AssetCache<SrgbTexture2d>,
+    programs: AssetCache<Program>,
+    libraries: HashMap<String, Vec<String>>,
+    mesh_sources: HashMap<String, (String, MeshOptions)>,
+    loader: Option<Loader>,
+    // assets that have loaded but need the display to finish
+    gpu_work: Vec<(Job, Loaded)>,
+    workers: usize,
+    progress: LoadProgress,
+    mesh_options: MeshOptions,
+    hot_reload: bool,
+    poll_interval: Duration,
+    last_poll: Option<Instant>,
+    watches: HashMap<(AssetKind, String), Vec<(String, Option<SystemTime>)>>,
+}
+
+/// The textures used by a material
+/// color maps are loaded as SRGB textures and data maps, such as bump maps, are not
+#[derive(Clone, Debug, Default)]
+pub struct MaterialTextures {
+    /// Ambient texture map
+    pub map_ka: Option<Handle<SrgbTexture2d>>,
+    /// Diffuse texture map
+    pub map_kd: Option<Handle<SrgbTexture2d>>,
+    /// Reflection map
+    pub map_refl: Option<Handle<SrgbTexture2d>>,
+    /// Emissive map
+    pub map_ke: Option<Handle<SrgbTexture2d>>,
+    /// Bump map
+    pub map_bump: Option<Handle<Texture2d>>,
+    /// Opacity map
+    pub map_d: Option<Handle<Texture2d>>,
+}
+
+impl AssetManager {
+    /// Creates an asset manager with nothing loaded
+    ///
+    /// # Arguments
+    /// `vfs` - the file system to load assets from
+    pub fn new(vfs: Arc<Vfs>) -> AssetManager {
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        AssetManager::with_workers(vfs, workers)
+    }
+
+    /// Creates an asset manager with nothing loaded
+    ///
+    /// # Arguments
+    /// `vfs` - the file system to load assets from
+    /// `workers` - the number of threads used to load assets in the background,
+    /// they aren't started until something is loaded in the background
+    pub fn with_workers(vfs: Arc<Vfs>, workers: usize) -> AssetManager {
+        AssetManager {
+            vfs: vfs,
+            meshes: AssetCache::new(),
+            materials: AssetCache::new(),
+            textures: AssetCache::new(),
+            srgb_textures: AssetCache::new(),
+            programs: AssetCache::new(),
+            libraries: HashMap::new(),
+            mesh_sources: HashMap::new(),
+            loader: None,
+            gpu_work: Vec::new(),
+            workers: workers.max(1),
+            progress: LoadProgress::default(),
+            mesh_options: MeshOptions::default(),
+            hot_reload: false,
+            poll_interval: Duration::from_secs(1),
+            last_poll: None,
+            watches: HashMap::new(),
+        }
+    }
+
+    /// returns the file system that assets are loaded from
+    pub fn vfs(&self) -> &Arc<Vfs> {
+        &self.vfs
+    }
+
+    /// Turns hot reloading on or off
+    ///
+    /// # Arguments
+    /// `enabled` - true to watch the files of assets and reload them when they change
+    ///
+    /// # Remarks
+    ///
+    /// Only assets loaded while hot reloading is on are watched.
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        self.hot_reload = enabled;
+        if !enabled {
+            self.watches.clear();
+        }
+    }
+
+    /// Sets whether normals are generated for meshes that don't have them
+    ///
+    /// # Arguments
+    /// `generation` - how normals are generated, or none to leave meshes as they are loaded
+    ///
+    /// # Remarks
+    ///
+    /// Only meshes loaded after this is set are affected, this is none by default. A mesh that
+    /// is loaded again with different options is a separate asset with its own handle.
+    pub fn set_normal_generation(&mut self, generation: Option<NormalGeneration>) {
+        self.mesh_options.normals = generation;
+    }
+
+    /// Sets whether tangents are generated for meshes, which normal maps need
+    ///
+    /// # Arguments
+    /// `enabled` - true to generate a tangent for every vertex with a normal and a texture
+    /// coordinate, after any normals are generated
+    ///
+    /// # Remarks
+    ///
+    /// Only meshes loaded after this is set are affected, this is off by default. A mesh that
+    /// is loaded again with different options is a separate asset with its own handle.
+    pub fn set_tangent_generation(&mut self, enabled: bool) {
+        self.mesh_options.tangents = enabled;
+    }
+
+    /// Sets how often [`AssetManager::update`] and [`AssetManager::poll`] check watched files
+    /// for changes
+    ///
+    /// # Arguments
+    /// `interval` - the time between checks, this is one second by default
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    /// Loads the meshes and materials from an obj file
+    ///
+    /// # Arguments
+    /// `path` - the path of the obj file
+    pub fn load_mesh(&mut self, path: &str) -> Result<Handle<MeshDescriptions>, AssetError> {
+        let path = self.canonical_path(path)?;
+        let options = self.mesh_options;
+        let key = mesh_key(&path, &options);
+        let vfs = &self.vfs;
+        let mut files = Vec::new();
+
+        let handle = load_cached(&mut self.meshes, &key, |_| {
+            let (mut mesh, read) = vfs.parse_tracked::<MeshDescriptions, _>(&path)?;
+            options.apply(&mut mesh);
+            files = read;
+            Ok(mesh)
+        })?;
+
+        self.mesh_sources.insert(key.clone(), (path, options));
+        if !files.is_empty() {
+            self.watch(AssetKind::Mesh, &key, files);
+        }
+        Ok(handle)
+    }
+
+    /// Loads every material from an mtl file
+    ///
+    /// # Arguments
+    /// `path` - the path of the mtl file
+    pub fn load_materials(&mut self, path: &str) -> Result<Vec<Handle<Material>>, AssetError> {
+        let path = self.canonical_path(path)?;
+
+        if let Some(keys) = self.libraries.get(&path) {
+            let handles = keys
+                .iter()
+                .filter_map(|key| self.materials.find(key))
+                .collect::<Vec<Handle<Material>>>();
+
+            if handles.len() == keys.len() {
+                return Ok(handles);
+            }
+        }
+
+        let mut handles = Vec::new();
+        let mut keys = Vec::new();
+        for material in self.vfs.parse::<Material, _>(&path)? {
+            let key = material_key(&path, &material.name);
+            let handle = match self.materials.find(&key) {
+                Some(handle) => handle,
+                None => self.materials.insert(&key, material),
+            };
+
+            handles.push(handle);
+            keys.push(key);
+        }
+        self.libraries.insert(path.clone(), keys);
+        self.watch(AssetKind::Material, &path, vec![path.clone()]);
+
+        Ok(handles)
+    }
+
+    /// Loads a single material from an mtl file
+    ///
+    /// # Arguments
+    /// `path` - the path of the mtl file
+    /// `name` - the name of the material
+    pub fn load_material(&mut self, path: &str, name: &str) -> Result<Handle<Material>, AssetError> {
+        let handles = self.load_materials(path)?;
+        let found = handles.into_iter().find(|h| {
+            self.materials
+                .get(h)
+                .map_or(false, |material| material.name == name)
+        });
+
+        match found {
+            Some(handle) => Ok(handle),
+            None => Err(AssetError::NotFound(material_key(path, name))),
+        }
+    }
+
+    /// Loads a texture that holds data, such as a normal map
+    ///
+    /// # Arguments
+    /// `path` - the path of the image file
+    /// `display` - the glium display
+    pub fn load_texture(
+        &mut self,
+        path: &str,
+        display: &Display,
+    ) -> Result<Handle<Texture2d>, AssetError> {
+        let path = self.canonical_path(path)?;
+        let vfs = &self.vfs;
+
+        let handle = load_cached(&mut self.textures, &path, |path| {
+            let image = decode_texture(&vfs.read(path)?, path)?;
+            Ok(Texture2d::new(display, image)?)
+        })?;
+
+        self.watch(AssetKind::Texture, &path, vec![path.clone()]);
+        Ok(handle)
+    }
+
+    /// Loads a texture that holds colors, such as a diffuse map
+    ///
+    /// # Arguments
+    /// `path` - the path of the image file
+    /// `display` - the glium display
+    pub fn load_srgb_texture(
+        &mut self,
+        path: &str,
+        display: &Display,
+    ) -> Result<Handle<SrgbTexture2d>, AssetError> {
+        let path = self.canonical_path(path)?;
+        let vfs = &self.vfs;
+
+        let handle = load_cached(&mut self.srgb_textures, &path, |path| {
+            let image = decode_texture(&vfs.read(path)?, path)?;
+            Ok(SrgbTexture2d::new(display, image)?)
+        })?;
+
+        self.watch(AssetKind::SrgbTexture, &path, vec![path.clone()]);
+        Ok(handle)
+    }
+
+    /// Loads the textures used by a material
+    /// a texture shared by several materials is only loaded once
+    ///
+    /// # Arguments
+    /// `material` - the material
+    /// `display` - the glium display
+    pub fn load_material_textures(
+        &mut self,
+        material: &Material,
+        display: &Display,
+    ) -> Result<MaterialTextures, AssetError> {
+        let mut srgb = |map: &Option<String>| -> Result<Option<Handle<SrgbTexture2d>>, AssetError> {
+            match map {
+                Some(path) => self.load_srgb_texture(path, display).map(Some),
+                None => Ok(None),
+            }
+        };
+
+        let map_ka = srgb(&material.map_ka)?;
+        let map_kd = srgb(&material.map_kd)?;
+        let map_refl = srgb(&material.map_refl)?;
+        let map_ke = srgb(&material.map_ke)?;
+
+        let mut linear = |map: &Option<String>| -> Result<Option<Handle<Texture2d>>, AssetError> {
+            match map {
+                Some(path) => self.load_texture(path, display).map(Some),
+                None => Ok(None),
+            }
+        };
+
+        let map_bump = linear(&material.map_bump)?;
+        let map_d = linear(&material.map_d)?;
+
+        Ok(MaterialTextures {
+            map_ka: map_ka,
+            map_kd: map_kd,
+            map_refl: map_refl,
+            map_ke: map_ke,
+            map_bump: map_bump,
+            map_d: map_d,
+        })
+    }
+
+    /// Loads and compiles a shader program
+    ///
+    /// # Arguments
+    /// `vertex` - the path of the vertex shader
+    /// `fragment` - the path of the fragment shader
+    /// `display` - the glium display
+    pub fn load_program(
+        &mut self,
+        vertex: &str,
+        fragment: &str,
+        display: &Display,
+    ) -> Result<Handle<Program>, AssetError> {
+        let vertex = self.canonical_path(vertex)?;
+        let fragment = self.canonical_path(fragment)?;
+        let vfs = &self.vfs;
+
+        let key = program_key(&vertex, &fragment);
+        let handle = load_cached(&mut self.programs, &key, |_| {
+            let vertex_src = vfs.read_to_string(&vertex)?;
+            let fragment_src = vfs.read_to_string(&fragment)?;
+            Ok(Program::from_source(
+                display,
+                &vertex_src[..],
+                &fragment_src[..],
+                None,
+            )?)
+        })?;
+
+        self.watch(AssetKind::Program, &key, vec![vertex, fragment]);
+        Ok(handle)
+    }
+
+    /// Starts loading the meshes and materials from an obj file in the background
+    ///
+    /// # Arguments
+    /// `path` - the path of the obj file
+    pub fn load_mesh_async(&mut self, path: &str) -> Result<Handle<MeshDescriptions>, AssetError> {
+        let path = self.canonical_path(path)?;
+        let options = self.mesh_options;
+        let key = mesh_key(&path, &options);
+        let request = Request::Mesh(path.clone(), options);
+
+        self.mesh_sources.insert(key.clone(), (path, options));
+        Ok(self.queue(AssetKind::Mesh, key, request, |m| &mut m.meshes))
+    }
+
+    /// Starts loading a texture that holds data in the background
+    ///
+    /// # Arguments
+    /// `path` - the path of the image file
+    pub fn load_texture_async(&mut self, path: &str) -> Result<Handle<Texture2d>, AssetError> {
+        let path = self.canonical_path(path)?;
+        let request = Request::Image(path.clone());
+
+        Ok(self.queue(AssetKind::Texture, path, request, |m| &mut m.textures))
+    }
+
+    /// Starts loading a texture that holds colors in the background
+    ///
+    /// # Arguments
+    /// `path` - the path of the image file
+    pub fn load_srgb_texture_async(
+        &mut self,
+        path: &str,
+    ) -> Result<Handle<SrgbTexture2d>, AssetError> {
+        let path = self.canonical_path(path)?;
+        let request = Request::Image(path.clone());
+
+        Ok(self.queue(AssetKind::SrgbTexture, path, request, |m| {
+            &mut m.srgb_textures
+        }))
+    }
+
+    /// Starts loading a shader program in the background
+    /// the program is compiled on the main thread once its source has been read
+    ///
+    /// # Arguments
+    /// `vertex` - the path of the vertex shader
+    /// `fragment` - the path of the fragment shader
+    pub fn load_program_async(
+        &mut self,
+        vertex: &str,
+        fragment: &str,
+    ) -> Result<Handle<Program>, AssetError> {
+        let vertex = self.canonical_path(vertex)?;
+        let fragment = self.canonical_path(fragment)?;
+        let key = program_key(&vertex, &fragment);
+
+        Ok(self.queue(
+            AssetKind::Program,
+            key,
+            Request::Sources(vertex, fragment),
+            |m| &mut m.programs,
+        ))
+    }
+
+    /// Finishes any assets that have loaded in the background, this should be called every frame
+    /// when hot reloading is on this also checks watched files for changes
+    ///
+    /// # Arguments
+    /// `display` - the glium display, used to upload textures and compile programs
+    ///
+    /// returns an event for each asset that finished loading since the last update
+    pub fn update(&mut self, display: &Display) -> Vec<LoadEvent> {
+        let mut events = self.poll();
+
+        for (job, loaded) in mem::replace(&mut self.gpu_work, Vec::new()) {
+            let result = self.finish_on_gpu(&job, loaded, display);
+            events.push(self.complete(job, result));
+        }
+
+        events
+    }
+
+    /// Finishes the assets that have loaded in the background without needing a display
+    /// when hot reloading is on this also checks watched files for changes
+    ///
+    /// # Remarks
+    ///
+    /// Meshes and materials are finished straight away. Textures and programs need the GPU,
+    /// so they keep loading until the next call to [`AssetManager::update`].
+    ///
+    /// returns an event for each asset that finished loading since the last update
+    pub fn poll(&mut self) -> Vec<LoadEvent> {
+        if self.hot_reload {
+            let due = self
+                .last_poll
+                .map_or(true, |last| last.elapsed() >= self.poll_interval);
+
+            if due {
+                self.last_poll = Some(Instant::now());
+                self.check_for_changes();
+            }
+        }
+
+        let messages = match self.loader {
+            Some(ref loader) => loader.messages(),
+            None => return Vec::new(),
+        };
+
+        let mut events = Vec::new();
+        for message in messages {
+            match message {
+                LoadMessage::Started(kind, key) => {
+                    self.set_status(kind, &key, LoadStatus::Loading)
+                }
+                LoadMessage::Finished(job, Ok(loaded @ Loaded::Image(_)))
+                | LoadMessage::Finished(job, Ok(loaded @ Loaded::Sources(..))) => {
+                    self.gpu_work.push((job, loaded))
+                }
+                LoadMessage::Finished(job, result) => {
+                    let result = result.and_then(|loaded| self.finish(&job, loaded));
+                    events.push(self.complete(job, result));
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Checks every watched file and starts reloading the assets whose files have changed
+    /// the reloaded assets are swapped in by [`AssetManager::update`] or [`AssetManager::poll`]
+    /// once they have loaded
+    ///
+    /// # Remarks
+    ///
+    /// The new modification times are kept as soon as a reload starts, so a reload that fails
+    /// isn't retried until one of the asset's files changes again, such as when it is fixed.
+    pub fn check_for_changes(&mut self) {
+        let mut changed = Vec::new();
+
+        for (&(kind, ref key), files) in self.watches.iter_mut() {
+            let mut dirty = false;
+            for &mut (ref path, ref mut modified) in files.iter_mut() {
+                let current = self.vfs.modified(path);
+                if current != *modified {
+                    *modified = current;
+                    dirty = true;
+                }
+            }
+
+            if dirty {
+                changed.push((kind, key.clone()));
+            }
+        }
+
+        for (kind, key) in changed {
+            let request = match kind {
+                // a mesh is reloaded with the options it was first loaded with
+                AssetKind::Mesh => match self.mesh_sources.get(&key) {
+                    Some(&(ref path, options)) => Request::Mesh(path.clone(), options),
+                    None => continue,
+                },
+                AssetKind::Material => Request::Materials(key.clone()),
+                AssetKind::Texture | AssetKind::SrgbTexture => Request::Image(key.clone()),
+                AssetKind::Program => {
+                    let mut files = key.splitn(2, '|').map(|f| f.to_owned());
+                    match (files.next(), files.next()) {
+                        (Some(vertex), Some(fragment)) => Request::Sources(vertex, fragment),
+                        _ => continue,
+                    }
+                }
+            };
+
+            self.loader().submit(Job {
+                kind: kind,
+                key: key,
+                request: request,
+                reload: true,
+            });
+        }
+    }
+
+    /// returns the progress of the assets loading in the background
+    pub fn progress(&self) -> &LoadProgress {
+        &self.progress
+    }
+
+    /// returns true while any asset is loading in the background
+    pub fn is_loading(&self) -> bool {
+        !self.progress.is_complete()
+    }
+
+    /// returns a loaded mesh
+    ///
+    /// # Arguments
+    /// `handle` - the handle returned when the mesh was loaded
+    pub fn mesh(&self, handle: &Handle<MeshDescriptions>) -> Option<&MeshDescriptions> {
+        self.meshes.get(handle)
+    }
+
+    /// returns a loaded material
+    ///
+    /// # Arguments
+    /// `handle` - the handle returned when the material was loaded
+    pub fn material(&self, handle: &Handle<Material>) -> Option<&Material> {
+        self.materials.get(handle)
+    }
+
+    /// returns a loaded data texture
+    ///
+    /// # Arguments
+    /// `handle` - the handle returned when the texture was loaded
+    pub fn texture(&self, handle: &Handle<Texture2d>) -> Option<&Texture2d> {
+        self.textures.get(handle)
+    }
+
+    /// returns a loaded color texture
+    ///
+    /// # Arguments
+    /// `handle` - the handle returned when the texture was loaded
+    pub fn srgb_texture(&self, handle: &Handle<SrgbTexture2d>) -> Option<&SrgbTexture2d> {
+        self.srgb_textures.get(handle)
+    }
+
+    /// returns a loaded shader program
+    ///
+    /// # Arguments
+    /// `handle` - the handle returned when the program was loaded
+    pub fn program(&self, handle: &Handle<Program>) -> Option<&Program> {
+        self.programs.get(handle)
+    }
+
+    /// returns the cache of loaded meshes
+    pub fn meshes(&self) -> &AssetCache<MeshDescriptions> {
+        &self.meshes
+    }
+
+    /// returns the cache of loaded materials
+    pub fn materials(&self) -> &AssetCache<Material> {
+        &self.materials
+    }
+
+    /// returns the cache of loaded data textures
+    pub fn textures(&self) -> &AssetCache<Texture2d> {
+        &self.textures
+    }
+
+    /// returns the cache of loaded color textures
+    pub fn srgb_textures(&self) -> &AssetCache<SrgbTexture2d> {
+        &self.srgb_textures
+    }
+
+    /// returns the cache of loaded shader programs
+    pub fn programs(&self) -> &AssetCache<Program> {
+        &self.programs
+    }
+
+    /// Unloads every asset that no longer has any handles, freeing its GPU resources
+    ///
+    /// returns the number of assets unloaded
+    pub fn unload_unused(&mut self) -> usize {
+        let count = self.meshes.unload_unused()
+            + self.materials.unload_unused()
+            + self.textures.unload_unused()
+            + self.srgb_textures.unload_unused()
+            + self.programs.unload_unused();
+
+        let meshes = &self.meshes;
+        self.mesh_sources.retain(|key, _| meshes.find(key).is_some());
+
+        let materials = &self.materials;
+        self.libraries
+            .retain(|_, keys| keys.iter().any(|key| materials.find(key).is_some()));
+
+        let meshes = &self.meshes;
+        let libraries = &self.libraries;
+        let textures = &self.textures;
+        let srgb_textures = &self.srgb_textures;
+        let programs = &self.programs;
+        self.watches.retain(|&(kind, ref key), _| match kind {
+            AssetKind::Mesh => meshes.find(key).is_some(),
+            AssetKind::Material => libraries.contains_key(key),
+            AssetKind::Texture => textures.find(key).is_some(),
+            AssetKind::SrgbTexture => srgb_textures.find(key).is_some(),
+            AssetKind::Program => programs.find(key).is_some(),
+        });
+
+        count
+    }
+
+    // reserves a handle for an asset and hands the work of loading it to a worker
+    fn queue<T>(
+        &mut self,
+        kind: AssetKind,
+        key: String,
+        request: Request,
+        cache: fn(&mut AssetManager) -> &mut AssetCache<T>,
+    ) -> Handle<T> {
+        if let Some(handle) = cache(self).find(&key) {
+            return handle;
+        }
+        let handle = cache(self).reserve(&key);
+
+        // a new batch of loads starts once everything from the last one has finished
+        if self.progress.is_complete() {
+            self.progress.assets.clear();
+        }
+        self.progress.assets.push(AssetProgress {
+            kind: kind,
+            path: key.clone(),
+            status: LoadStatus::Queued,
+        });
+
+        self.loader().submit(Job {
+            kind: kind,
+            key: key,
+            request: request,
+            reload: false,
+        });
+
+        handle
+    }
+
+    // returns the background loader, starting its workers the first time it is needed
+    fn loader(&mut self) -> &Loader {
+        if self.loader.is_none() {
+            self.loader = Some(Loader::new(self.vfs.clone(), self.workers));
+        }
+
+        match self.loader {
+            Some(ref loader) => loader,
+            None => unreachable!(),
+        }
+    }
+
+    // starts watching the files of an asset when hot reloading is on
+    fn watch(&mut self, kind: AssetKind, key: &str, files: Vec<String>) {
+        if !self.hot_reload {
+            return;
+        }
+
+        let vfs = &self.vfs;
+        self.watches
+            .entry((kind, key.to_owned()))
+            .or_insert_with(|| {
+                files
+                    .into_iter()
+                    .map(|file| {
+                        let modified = vfs.modified(&file);
+                        (file, modified)
+                    })
+                    .collect()
+            });
+    }
+
+    // finishes an asset that a worker has loaded and that doesn't need the GPU
+    fn finish(&mut self, job: &Job, loaded: Loaded) -> Result<(), AssetError> {
+        match (job.kind, loaded) {
+            (AssetKind::Mesh, Loaded::Mesh(mesh, files)) => {
+                fill(&mut self.meshes, &job.key, mesh);
+
+                // the files a mesh reads can change between reloads, such as a new mtllib
+                if job.reload {
+                    self.watches.remove(&(AssetKind::Mesh, job.key.clone()));
+                }
+                self.watch(AssetKind::Mesh, &job.key, files);
+            }
+            (AssetKind::Material, Loaded::Materials(materials)) => {
+                // the library may have been unloaded while it was reloading
+                let old_keys = match self.libraries.remove(&job.key) {
+                    Some(keys) => keys,
+                    None => return Ok(()),
+                };
+
+                let mut keys = Vec::new();
+                for material in materials {
+                    let key = material_key(&job.key, &material.name);
+                    self.materials.insert(&key, material);
+                    keys.push(key);
+                }
+
+                // materials that were taken out of the library are removed with it
+                for key in old_keys.iter().filter(|key| !keys.contains(key)) {
+                    self.materials.remove(key);
+                }
+                self.libraries.insert(job.key.clone(), keys);
+            }
+            _ => unreachable!("a worker returned the wrong kind of asset"),
+        }
+
+        Ok(())
+    }
+
+    // finishes an asset that a worker has loaded by uploading it to the GPU
+    fn finish_on_gpu(
+        &mut self,
+        job: &Job,
+        loaded: Loaded,
+        display: &Display,
+    ) -> Result<(), AssetError> {
+        match (job.kind, loaded) {
+            (AssetKind::Texture, Loaded::Image(image)) => {
+                let texture = Texture2d::new(display, image)?;
+                fill(&mut self.textures, &job.key, texture);
+                self.watch(AssetKind::Texture, &job.key, vec![job.key.clone()]);
+            }
+            (AssetKind::SrgbTexture, Loaded::Image(image)) => {
+                let texture = SrgbTexture2d::new(display, image)?;
+                fill(&mut self.srgb_textures, &job.key, texture);
+                self.watch(AssetKind::SrgbTexture, &job.key, vec![job.key.clone()]);
+            }
+            (AssetKind::Program, Loaded::Sources(vertex, fragment)) => {
+                let program = Program::from_source(display, &vertex[..], &fragment[..], None)?;
+                fill(&mut self.programs, &job.key, program);
+
+                let files = job.key.splitn(2, '|').map(|f| f.to_owned()).collect();
+                self.watch(AssetKind::Program, &job.key, files);
+            }
+            _ => unreachable!("a worker returned the wrong kind of asset"),
+        }
+
+        Ok(())
+    }
+
+    // records how loading an asset went and returns the event reporting it
+    fn complete(&mut self, job: Job, result: Result<(), AssetError>) -> LoadEvent {
+        match result {
+            Ok(()) => self.set_status(job.kind, &job.key, LoadStatus::Loaded),
+            Err(ref e) => {
+                self.set_status(job.kind, &job.key, LoadStatus::Failed(e.to_string()));
+                // a failed reload keeps the last good version
+                if !job.reload {
+                    self.forget(job.kind, &job.key);
+                }
+            }
+        }
+
+        LoadEvent {
+            kind: job.kind,
+            path: job.key,
+            result: result,
+            reloaded: job.reload,
+        }
+    }
+
+    fn set_status(&mut self, kind: AssetKind, key: &str, status: LoadStatus) {
+        let asset = self
+            .progress
+            .assets
+            .iter_mut()
+            .rev()
+            .find(|a| a.kind == kind && a.path == key);
+
+        if let Some(asset) = asset {
+            asset.status = status;
+        }
+    }
+
+    // drops the reservation for an asset that failed to load so it can be requested again
+    fn forget(&mut self, kind: AssetKind, key: &str) {
+        match kind {
+            AssetKind::Mesh => forget_pending(&mut self.meshes, key),
+            AssetKind::Material => forget_pending(&mut self.materials, key),
+            AssetKind::Texture => forget_pending(&mut self.textures, key),
+            AssetKind::SrgbTexture => forget_pending(&mut self.srgb_textures, key),
+            AssetKind::Program => forget_pending(&mut self.programs, key),
+        }
+    }
+
+    fn canonical_path(&self, path: &str) -> Result<String, AssetError> {
+        self.vfs
+            .canonical_path(path)
+            .ok_or_else(|| AssetError::NotFound(path.to_owned()))
+    }
+}
+
+impl fmt::Debug for AssetManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AssetManager")
+            .field("vfs", &self.vfs)
+            .field("meshes", &self.meshes)
+            .field("materials", &self.materials)
+            .field("textures", &self.textures)
+            .field("srgb_textures", &self.srgb_textures)
+            .field("programs", &self.programs)
+            .field("loader", &self.loader)
+            .field("progress", &self.progress)
+            .field("mesh_options", &self.mesh_options)
+            .field("hot_reload", &self.hot_reload)
+            .finish()
+    }
+}
+
+// returns the cached asset for a path, loading it when it isn't cached or is still loading
+fn load_cached<T, F>(cache: &mut AssetCache<T>, path: &str, load: F) -> Result<Handle<T>, AssetError>
+where
+    F: FnOnce(&str) -> Result<T, AssetError>,
+{
+    if let Some(handle) = cache.find(path) {
+        if cache.is_loaded(&handle) {
+            return Ok(handle);
+        }
+    }
+
+    let asset = load(path)?;
+    Ok(cache.insert(path, asset))
+}
+
+// stores an asset that finished loading, unless its reservation was unloaded while it loaded
+fn fill<T>(cache: &mut AssetCache<T>, key: &str, asset: T) {
+    if cache.find(key).is_some() {
+        cache.insert(key, asset);
+    }
+}
+
+fn forget_pending<T>(cache: &mut AssetCache<T>, key: &str) {
+    let loaded = cache.find(key).map(|handle| cache.is_loaded(&handle));
+    if loaded == Some(false) {
+        cache.remove(key);
+    }
+}
+
+// meshes loaded with different options are different assets, so the options are part of the key
+fn mesh_key(path: &str, options: &MeshOptions) -> String {
+    if *options == MeshOptions::default() {
+        path.to_owned()
+    } else {
+        format!("{}?{:?}", path, options)
+    }
+}
+
+fn material_key(library: &str, name: &str) -> String {
+    format!("{}#{}", library, name)
+}
+
+fn program_key(vertex: &str, fragment: &str) -> String {
+    format!("{}|{}", vertex, fragment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::NormalGeneration;
+    use io::DirectoryMount;
+    use std::env;
+    use std::fs::{self, File};
+    use std::path::PathBuf;
+    use std::process;
+
+    // a directory of content that is removed once the test is done with it
+    struct Content(PathBuf);
+
+    impl Content {
+        fn new(name: &str, files: &[(&str, &str)]) -> Content {
+            let root = env::temp_dir().join(format!("manager-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            for &(path, contents) in files {
+                fs::write(root.join(path), contents).unwrap();
+            }
+            Content(root)
+        }
+
+        fn assets(&self) -> AssetManager {
+            let mut vfs = Vfs::new();
+            vfs.mount("content", DirectoryMount::new(&self.0));
+            AssetManager::with_workers(Arc::new(vfs), 1)
+        }
+    }
+
+    impl Content {
+        // rewrites a file and moves its modification time forward so it is seen as changed
+        fn rewrite(&self, path: &str, contents: &str, seconds: u64) {
+            let path = self.0.join(path);
+            fs::write(&path, contents).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+                .unwrap();
+        }
+    }
+
+    impl Drop for Content {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+
+    // polls until the given number of assets have finished loading in the background
+    fn poll_until(assets: &mut AssetManager, count: usize) -> Vec<LoadEvent> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut events = Vec::new();
+        while events.len() < count {
+            assert!(Instant::now() < deadline, "timed out waiting for assets to load");
+            events.extend(assets.poll());
+            thread::sleep(Duration::from_millis(1));
+        }
+        events
+    }
+
+    #[test]
+    fn meshes_are_cached_by_path() {
+        let content = Content::new("cached", &[("a.obj", TRIANGLE)]);
+        let mut assets = content.assets();
+
+        let first = assets.load_mesh("content/a.obj").unwrap();
+        let second = assets.load_mesh("content/./a.obj").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(assets.meshes().len(), 1);
+    }
+
+    #[test]
+    fn meshes_loaded_with_different_options_are_different_assets() {
+        let content = Content::new("options", &[("a.obj", TRIANGLE)]);
+        let mut assets = content.assets();
+
+        let plain = assets.load_mesh("content/a.obj").unwrap();
+        assets.set_normal_generation(Some(NormalGeneration::Flat));
+        let flat = assets.load_mesh("content/a.obj").unwrap();
+        assert!(plain != flat);
+        assert_eq!(flat, assets.load_mesh("content/a.obj").unwrap());
+
+        let normal = |handle: &Handle<MeshDescriptions>| {
+            assets.mesh(handle).unwrap().meshes[0].vertices[0].normal()
+        };
+        assert!(normal(&plain).is_none());
+        assert!(normal(&flat).is_some());
+
+        // a reload uses the options the mesh was loaded with, not the current ones
+        assets.set_normal_generation(None);
+        let flat_key = mesh_key(
+            "content/a.obj",
+            &MeshOptions {
+                normals: Some(NormalGeneration::Flat),
+                tangents: false,
+            },
+        );
+        assert_eq!(
+            assets.mesh_sources.get(&flat_key).map(|s| s.1.normals),
+            Some(Some(NormalGeneration::Flat))
+        );
+    }
+
+    #[test]
+    fn meshes_loaded_in_the_background_finish_without_a_display() {
+        let content = Content::new("background", &[("a.obj", TRIANGLE)]);
+        let mut assets = content.assets();
+
+        let handle = assets.load_mesh_async("content/a.obj").unwrap();
+        let missing = assets.load_mesh_async("content/b.obj");
+        assert!(missing.is_err());
+        assert!(assets.is_loading());
+        assert!(assets.mesh(&handle).is_none());
+
+        let events = poll_until(&mut assets, 1);
+        assert_eq!(events[0].kind, AssetKind::Mesh);
+        assert_eq!(events[0].path, "content/a.obj");
+        assert!(events[0].result.is_ok());
+        assert!(!events[0].reloaded);
+
+        assert!(!assets.is_loading());
+        assert_eq!(assets.progress().fraction(), 1.0);
+        assert_eq!(assets.mesh(&handle).unwrap().meshes[0].indices.len(), 3);
+    }
+
+    #[test]
+    fn changed_meshes_are_reloaded_behind_their_handles() {
+        let content = Content::new("reload", &[("a.obj", TRIANGLE)]);
+        let mut assets = content.assets();
+        assets.set_hot_reload(true);
+
+        let handle = assets.load_mesh("content/a.obj").unwrap();
+        assets.check_for_changes();
+        assert!(assets.loader.is_none());
+
+        content.rewrite("a.obj", &format!("{}f 1 3 2\n", TRIANGLE), 10);
+        assets.check_for_changes();
+        assert!(assets.loader.is_some());
+
+        let events = poll_until(&mut assets, 1);
+        assert_eq!(events[0].path, "content/a.obj");
+        assert!(events[0].result.is_ok());
+        assert!(events[0].reloaded);
+        assert_eq!(assets.mesh(&handle).unwrap().meshes[0].indices.len(), 6);
+
+        // a broken file keeps the last version that loaded
+        content.rewrite("a.obj", "v 0 0 0\nf 1 2 3\n", 20);
+        assets.check_for_changes();
+
+        let events = poll_until(&mut assets, 1);
+        assert!(events[0].result.is_err());
+        assert!(events[0].reloaded);
+        assert_eq!(assets.mesh(&handle).unwrap().meshes[0].indices.len(), 6);
+    }
+
+    #[test]
+    fn reloaded_libraries_add_and_remove_materials() {
+        let library = "newmtl red\nKd 1 0 0\nnewmtl green\nKd 0 1 0\n";
+        let content = Content::new("library", &[("a.mtl", library)]);
+        let mut assets = content.assets();
+        assets.set_hot_reload(true);
+
+        let red = assets.load_material("content/a.mtl", "red").unwrap();
+        let green = assets.load_material("content/a.mtl", "green").unwrap();
+
+        content.rewrite("a.mtl", "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n", 10);
+        assets.check_for_changes();
+        let events = poll_until(&mut assets, 1);
+        assert!(events[0].result.is_ok());
+        assert!(events[0].reloaded);
+
+        assert!(assets.material(&red).is_some());
+        assert!(assets.material(&green).is_none());
+        assert!(assets.load_material("content/a.mtl", "green").is_err());
+
+        let blue = assets.load_material("content/a.mtl", "blue").unwrap();
+        assert_eq!(assets.material(&blue).unwrap().name, "blue");
+        assert_eq!(assets.load_materials("content/a.mtl").unwrap(), vec![red, blue]);
+    }
+}
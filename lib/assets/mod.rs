@@ -0,0 +1,94 @@
+//! A module for loading and owning the content used by a game.
+//! Assets are referred to by typed handles that keep them loaded, and each asset is only
+//! loaded once no matter how many times it is asked for. Assets can also be loaded in the
+//! background so that big files don't stall the game loop.
+mod handle;
+mod loader;
+mod manager;
+
+pub use self::handle::*;
+pub use self::loader::{AssetKind, AssetProgress, LoadEvent, LoadProgress, LoadStatus};
+pub use self::manager::*;
+
+use glium::texture::TextureCreationError;
+use glium::ProgramCreationError;
+use image::ImageError;
+use parser::ParseError;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// An error encountered while loading an asset
+#[derive(Debug)]
+pub enum AssetError {
+    /// The asset's file could not be found in any mount
+    NotFound(String),
+    /// Reading the asset's file failed
+    Io(io::Error),
+    /// The asset's file could not be parsed
+    Parse(ParseError),
+    /// The image could not be decoded
+    Image(ImageError),
+    /// The texture could not be created
+    Texture(TextureCreationError),
+    /// The shader program could not be compiled or linked
+    Shader(ProgramCreationError),
+    /// Loading the asset panicked, this holds the panic message
+    Panicked(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::NotFound(path) => write!(f, "{} was not found", path),
+            AssetError::Io(e) => write!(f, "io error ({})", e),
+            AssetError::Parse(e) => write!(f, "{}", e),
+            AssetError::Image(e) => write!(f, "failed to decode image ({})", e),
+            AssetError::Texture(e) => write!(f, "failed to create texture ({:?})", e),
+            AssetError::Shader(e) => write!(f, "failed to create shader program ({})", e),
+            AssetError::Panicked(message) => write!(f, "loading panicked ({})", message),
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetError::Io(e) => Some(e),
+            AssetError::Parse(e) => Some(e),
+            AssetError::Image(e) => Some(e),
+            AssetError::Shader(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AssetError {
+    fn from(e: io::Error) -> AssetError {
+        AssetError::Io(e)
+    }
+}
+
+impl From<ParseError> for AssetError {
+    fn from(e: ParseError) -> AssetError {
+        AssetError::Parse(e)
+    }
+}
+
+impl From<ImageError> for AssetError {
+    fn from(e: ImageError) -> AssetError {
+        AssetError::Image(e)
+    }
+}
+
+impl From<TextureCreationError> for AssetError {
+    fn from(e: TextureCreationError) -> AssetError {
+        AssetError::Texture(e)
+    }
+}
+
+impl From<ProgramCreationError> for AssetError {
+    fn from(e: ProgramCreationError) -> AssetError {
+        AssetError::Shader(e)
+    }
+}
@@ -24,6 +24,13 @@ pub fn create_engine(events_loop: &EventsLoop, title: &str) -> Display {
 
 /// Runs the main game loop
 /// This will run until the events loop recieves a close request.
+///
+/// # Remarks
+///
+/// When the game has an asset manager, assets loading in the background are finished at the
+/// start of each frame and `Game::on_loading` is run instead of `Game::on_frame` until they
+/// have all loaded. Every asset that finishes loading or reloading, or fails to, is handed to
+/// `Game::on_asset_event`.
 pub fn run<T: Game>(display: &Display, events_loop: &mut EventsLoop, game: T) {
     let mut running = true;
     let mut game = game;
@@ -34,7 +41,25 @@ pub fn run<T: Game>(display: &Display, events_loop: &mut EventsLoop, game: T) {
         let mut target = display.draw();
         target.clear_color_and_depth((0.0, 0.0, 1.0, 1.0), 1.0);
 
-        game = game.on_frame(&mut target);
+        let events = game
+            .assets()
+            .map_or_else(Vec::new, |assets| assets.update(display));
+        for event in &events {
+            game.on_asset_event(event);
+        }
+
+        let progress = game.assets().and_then(|assets| {
+            if assets.is_loading() {
+                Some(assets.progress().clone())
+            } else {
+                None
+            }
+        });
+
+        game = match progress {
+            Some(progress) => game.on_loading(&mut target, &progress),
+            None => game.on_frame(&mut target),
+        };
 
         events_loop.poll_events(|event| match event {
             glutin::Event::WindowEvent { event, .. } => match event {
@@ -1,6 +1,7 @@
 //! This module contains a set of types and traits that represent a game
 //! The methods here are used within a game loop and control the flow of the 
 //! game.
+use assets::{AssetManager, LoadEvent, LoadProgress};
 use glium::Frame;
 
 /// A collection of methods that a game should implement 
@@ -13,4 +14,32 @@ pub trait Game {
     /// `self` - the instance of game
     /// `frame` - (mutable reference) - The frame object that the frame renders to
     fn on_frame(self, frame: &mut Frame) -> Self;
+
+    /// Gets the asset manager of the game, if it has one.
+    /// The engine uses it to finish loading assets in the background each frame.
+    fn assets(&mut self) -> Option<&mut AssetManager> {
+        None
+    }
+
+    /// Method run for each asset that finished loading, or failed to, since the last frame
+    ///
+    /// # Arguments
+    ///
+    /// `self` - (mutable reference) the instance of game
+    /// `event` - the asset and whether it loaded
+    fn on_asset_event(&mut self, _event: &LoadEvent) {}
+
+    /// Method run once per frame instead of `on_frame` while assets are loading in the background
+    ///
+    /// # Arguments
+    ///
+    /// `self` - the instance of game
+    /// `frame` - (mutable reference) - The frame object that the frame renders to
+    /// `progress` - how far through loading the assets are
+    fn on_loading(self, _frame: &mut Frame, _progress: &LoadProgress) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
 }
@@ -1,10 +1,11 @@
 //! Module contains a collection of types and functions representing a shader material
 //! primary usage of this comes from an obj file parser
-use parser::{FromFile, ParseError};
+use parser::{
+    first_arg, parse_f32, parse_floats, tokenize, FromFile, FromReader, FromReaderWithMode,
+    ParseError, ParseErrorKind, ParseMode, Parsed, Resolver, Token,
+};
 use std::fmt;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::BufRead;
 use std::default::Default;
 
 /// A type that represents a Material
@@ -57,319 +58,200 @@ impl FromFile for Material {
     type ParseResult = Result<Vec<Material>, ParseError>;
 
     fn from_file(file: &str) -> Self::ParseResult {
-        let directory = Path::new(&file)
-            .parent()
-            .expect("Failed to get parent directory")
-            .to_str()
-            .expect("Failed to get parent directory string");
-
-        let file = File::open(&file).expect(format!("{} not found!", &file).as_str());
-        let reader = BufReader::new(&file);
-        let mut materials: Vec<Material> = Vec::new();
-        let mut material_name: Option<String> = None;
-        let mut specular_exponent = 0f32;
-        let mut optical_density = 0f32;
-        let mut d_factor = 1.0f32;
-        let mut transparency = 0f32;
-        let mut illum = IlluminationModel::ColorOnAmbientOn;
-        let mut ka = MaterialColor::None;
-        let mut kd = MaterialColor::None;
-        let mut ks = MaterialColor::None;
-        let mut ke = MaterialColor::None;
-        let mut tf = MaterialColor::None;
-        let mut map_ka: Option<String> = None;
-        let mut map_kd: Option<String> = None;
-        let mut map_refl: Option<String> = None;
-        let mut map_ke: Option<String> = None;
-        let mut map_bump: Option<String> = None;
-        let mut map_d: Option<String> = None;
-
-        for line in reader.lines() {
-            let parts = match line {
-                Ok(ref line) => line[..].split_whitespace().collect::<Vec<&str>>(),
-                Err(e) => return Err(ParseError::GeneralError(String::from(format!("{:?}", e)))),
-            };
+        Material::from_file_with_mode(file, ParseMode::Strict).map(|parsed| parsed.value)
+    }
+}
 
-            if parts.len() == 0 {
-                continue;
-            }
+impl FromReader for Material {
+    type ParseResult = Result<Vec<Material>, ParseError>;
 
-            let (token, rest) = (parts[0], &parts[1..]);
-
-            match token {
-            "newmtl" => {
-                match material_name {
-                    None => (),
-                    Some(s) => {
-                        materials.push(Material {
-                            name: s,
-                            ns: specular_exponent,
-                            ni: optical_density,
-                            d: d_factor,
-                            tr: transparency,
-                            tf: tf.clone(),
-                            illum: illum.clone(),
-                            ka: ka.clone(),
-                            kd: kd.clone(),
-                            ks: ks.clone(),
-                            ke: ke.clone(),
-                            map_ka: match map_ka.clone() {
-                                None => None,
-                                Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                            },
-                            map_kd: match map_kd.clone() {
-                                None => None,
-                                Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                            },
-                            map_refl: match map_refl.clone() {
-                                None => None,
-                                Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                            },
-                             map_ke: match map_ke.clone() {
-                                None => None,
-                                Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                            },
-                               map_bump: match map_bump.clone() {
-                                None => None,
-                                Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                            },
-                             map_d: match map_d.clone() {
-                                None => None,
-                                Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                            },
-                        });
-                        specular_exponent = 0f32;
-                        optical_density = 0f32;
-                        d_factor = 1.0f32;
-                        transparency = 0f32;
-                        illum = IlluminationModel::ColorOnAmbientOn;
-                        ka = MaterialColor::None;
-                        kd = MaterialColor::None;
-                        ks = MaterialColor::None;
-                        ke = MaterialColor::None;
-                        tf = MaterialColor::None;
-                        map_ka = None;
-                        map_kd = None;
-                        map_refl = None;
-                        map_ke = None;
-                        map_bump = None;
-                        map_d = None;
-                    }
-                }
-                material_name = Some(String::from(rest[0]));
-            }
-            "Ns" => {
-                specular_exponent = match rest[0].parse::<f32>() {
-                    Ok(exponent) => exponent,
-                    Err(_) => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse specular exponent".to_owned(),
-                        ))
-                    }
-                }
-            }
-            "Ni" => {
-                optical_density = match rest[0].parse::<f32>() {
-                    Ok(density) => density,
-                    Err(_) => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse optical density".to_owned(),
-                        ))
-                    }
-                }
-            }
-            "d" => {
-                d_factor = match rest[0].parse::<f32>() {
-                    Ok(factor) => factor,
-                    Err(_) => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse d factor".to_owned(),
-                        ))
-                    }
-                }
-            }
-            "Tr" => {
-                transparency = match rest[0].parse::<f32>() {
-                    Ok(t) => t,
-                    Err(_) => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse transparency".to_owned(),
-                        ))
-                    }
-                }
-            }
-            "illum" => illum = match rest[0].parse::<u32>() {
-                Ok(i) => match i {
-                    0 => IlluminationModel::ColorOnAmbientOff,
-                    1 => IlluminationModel::ColorOnAmbientOn,
-                    2 => IlluminationModel::HighlightOn,
-                    3 => IlluminationModel::ReflectionAndRaytraceOn,
-                    4 => IlluminationModel::TransparencyGlassOnReflectionRaytraceOn,
-                    5 => IlluminationModel::ReflectionFresnelOnRaytraceOn,
-                    6 => IlluminationModel::TransparencyRefractionOnReflectionFresnelOffRaytraceOn,
-                    7 => IlluminationModel::TransparencyRefractionOnReflectionFresnelOnRaytraceOn,
-                    8 => IlluminationModel::ReflectionOnRaytraceOff,
-                    9 => IlluminationModel::TransparencyGlassOnReflectionRaytraceOff,
-                    10 => IlluminationModel::CastsShadowsOntoInvisibleSurfacess,
-                    _ => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse illumination model".to_owned(),
-                        ))
-                    }
-                },
-                Err(_) => {
-                    return Err(ParseError::GeneralError(
-                        "Failed to parse illumination model".to_owned(),
-                    ))
-                }
-            },
-            "Ka" => {
-                ka = match parse_color(&rest) {
-                    MaterialColor::None => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse Ka".to_owned(),
-                        ))
-                    }
-                    x => x,
-                }
-            }
-            "Kd" => {
-                kd = match parse_color(&rest) {
-                    MaterialColor::None => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse Kd".to_owned(),
-                        ))
-                    }
-                    x => x,
-                }
-            }
-            "Ks" => {
-                ks = match parse_color(&rest) {
-                    MaterialColor::None => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse Ks".to_owned(),
-                        ))
-                    }
-                    x => x,
-                }
-            }
-            "Ke" => {
-                ke = match parse_color(&rest) {
-                    MaterialColor::None => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse Ke".to_owned(),
-                        ))
-                    }
-                    x => x,
-                }
-            }
-            "Tf" => {
-                tf = match parse_color(&rest) {
-                    MaterialColor::None => {
-                        return Err(ParseError::GeneralError(
-                            "Failed to parse Tf".to_owned(),
-                        ))
-                    }
-                    x => x,
-                }
-            }
-            "map_Ka" => map_ka = Some(String::from(rest[0])),
-            "map_Kd" => map_kd = Some(String::from(rest[0])),
-            "map_refl" => map_refl = Some(String::from(rest[0])),
-            "map_Ke" => map_ke = Some(String::from(rest[0])),
-            "map_d" => map_d = Some(String::from(rest[0])),
-            "map_bump" | "bump" => map_ke = Some(String::from(rest[0])),
-            "#" => continue,
-            x => {
-                return Err(ParseError::UnknownToken(
-                    format!("Material parse: unknown token {}", x).to_owned(),
-                ))
-            }
+    fn from_reader<R: BufRead>(reader: R, name: &str, resolver: &dyn Resolver) -> Self::ParseResult {
+        Material::from_reader_with_mode(reader, name, resolver, ParseMode::Strict)
+            .map(|parsed| parsed.value)
+    }
+}
+
+impl FromReaderWithMode for Material {
+    type Output = Vec<Material>;
+
+    fn from_reader_with_mode<R: BufRead>(
+        reader: R,
+        name: &str,
+        resolver: &dyn Resolver,
+        mode: ParseMode,
+    ) -> Result<Parsed<Vec<Material>>, ParseError> {
+        let mut parser = MtlParser::new();
+        let mut warnings = Vec::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(|e| ParseError::from(e).at(name, line_number))?;
+
+            let result = parser
+                .parse_line(&line, resolver)
+                .map_err(|e| e.at(name, line_number));
+            mode.recover(result, &mut warnings)?;
         }
+
+        Ok(Parsed {
+            value: parser.finish(),
+            warnings: warnings,
+        })
+    }
+}
+
+// The state built up while reading an mtl file line by line
+struct MtlParser {
+    materials: Vec<Material>,
+    current: Option<Material>,
+}
+
+impl MtlParser {
+    fn new() -> MtlParser {
+        MtlParser {
+            materials: Vec::new(),
+            current: None,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str, resolver: &dyn Resolver) -> Result<(), ParseError> {
+        let parts = tokenize(line).collect::<Vec<Token>>();
+
+        if parts.is_empty() {
+            return Ok(());
         }
 
-        match material_name {
-            None => (),
-            Some(s) => {
-                materials.push(Material {
-                    name: s,
-                    ns: specular_exponent,
-                    ni: optical_density,
-                    d: d_factor,
-                    tr: transparency,
-                    tf: tf.clone(),
-                    illum: illum.clone(),
-                    ka: ka.clone(),
-                    kd: kd.clone(),
-                    ks: ks.clone(),
-                    ke: ke.clone(),
-                    map_ka: match map_ka.clone() {
-                        None => None,
-                        Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                    },
-                    map_kd: match map_kd.clone() {
-                        None => None,
-                        Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                    },
-                    map_refl: match map_refl.clone() {
-                        None => None,
-                        Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                    },
-                    map_ke: match map_ke.clone() {
-                        None => None,
-                        Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                    },
-                    map_bump: match map_bump.clone() {
-                        None => None,
-                        Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                    },
-                    map_d: match map_d.clone() {
-                        None => None,
-                        Some(m) => Some(format!("{}/{}", &directory, m).to_owned()),
-                    },
-                });
+        let (token, rest) = (parts[0], &parts[1..]);
+
+        if token.text == "#" {
+            return Ok(());
+        }
+
+        if token.text == "newmtl" {
+            let name = first_arg(token, rest)?;
+            self.finish_material();
+            self.current = Some(Material {
+                name: String::from(name.text),
+                d: 1.0f32,
+                illum: IlluminationModel::ColorOnAmbientOn,
+                ..Default::default()
+            });
+            return Ok(());
+        }
+
+        let material = match self.current {
+            Some(ref mut m) => m,
+            None => {
+                return Err(ParseError::new(
+                    ParseErrorKind::MalformedStatement,
+                    "statement appears before any `newmtl`",
+                )
+                .with_token(token))
+            }
+        };
+        let map = |arg: Token| Some(resolver.resolve(arg.text));
+
+        match token.text {
+            "Ns" => material.ns = parse_f32(first_arg(token, rest)?)?,
+            "Ni" => material.ni = parse_f32(first_arg(token, rest)?)?,
+            "d" => material.d = parse_f32(first_arg(token, rest)?)?,
+            "Tr" => material.tr = parse_f32(first_arg(token, rest)?)?,
+            "illum" => material.illum = parse_illumination_model(first_arg(token, rest)?)?,
+            "Ka" => material.ka = parse_color(token, &rest)?,
+            "Kd" => material.kd = parse_color(token, &rest)?,
+            "Ks" => material.ks = parse_color(token, &rest)?,
+            "Ke" => material.ke = parse_color(token, &rest)?,
+            "Tf" => material.tf = parse_color(token, &rest)?,
+            "map_Ka" => material.map_ka = map(first_arg(token, rest)?),
+            "map_Kd" => material.map_kd = map(first_arg(token, rest)?),
+            "map_refl" => material.map_refl = map(first_arg(token, rest)?),
+            "map_Ke" => material.map_ke = map(first_arg(token, rest)?),
+            "map_d" => material.map_d = map(first_arg(token, rest)?),
+            "map_bump" | "bump" => material.map_bump = map(first_arg(token, rest)?),
+            _ => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnsupportedStatement,
+                    "unknown material statement",
+                )
+                .with_token(token))
             }
         }
 
-        Ok(materials)
+        Ok(())
+    }
+
+    fn finish_material(&mut self) {
+        if let Some(m) = self.current.take() {
+            self.materials.push(m);
+        }
+    }
+
+    fn finish(mut self) -> Vec<Material> {
+        self.finish_material();
+        self.materials
+    }
+}
+
+fn parse_illumination_model(token: Token) -> Result<IlluminationModel, ParseError> {
+    let model = token.text.parse::<u32>().map_err(|_| {
+        ParseError::new(ParseErrorKind::BadNumber, "expected an illumination model")
+            .with_token(token)
+    })?;
+
+    match model {
+        0 => Ok(IlluminationModel::ColorOnAmbientOff),
+        1 => Ok(IlluminationModel::ColorOnAmbientOn),
+        2 => Ok(IlluminationModel::HighlightOn),
+        3 => Ok(IlluminationModel::ReflectionAndRaytraceOn),
+        4 => Ok(IlluminationModel::TransparencyGlassOnReflectionRaytraceOn),
+        5 => Ok(IlluminationModel::ReflectionFresnelOnRaytraceOn),
+        6 => Ok(IlluminationModel::TransparencyRefractionOnReflectionFresnelOffRaytraceOn),
+        7 => Ok(IlluminationModel::TransparencyRefractionOnReflectionFresnelOnRaytraceOn),
+        8 => Ok(IlluminationModel::ReflectionOnRaytraceOff),
+        9 => Ok(IlluminationModel::TransparencyGlassOnReflectionRaytraceOff),
+        10 => Ok(IlluminationModel::CastsShadowsOntoInvisibleSurfacess),
+        _ => Err(ParseError::new(
+            ParseErrorKind::IndexOutOfRange,
+            "illumination models range from 0 to 10",
+        )
+        .with_token(token)),
     }
 }
 
-fn parse_color(parts: &[&str]) -> MaterialColor {
-    match parts[0] {
+fn parse_color(statement: Token, parts: &[Token]) -> Result<MaterialColor, ParseError> {
+    let missing = || {
+        ParseError::new(
+            ParseErrorKind::MalformedStatement,
+            &format!("`{}` is missing color values", statement),
+        )
+        .with_token(statement)
+    };
+
+    match first_arg(statement, parts)?.text {
         "spectral" => {
-            let file = String::from(parts[1]);
-            let factor = match parts.len() {
-                2 => Some(parts[2].parse::<f32>().expect("factor should exist")),
-                _ => None,
+            let file = String::from(parts.get(1).ok_or_else(&missing)?.text);
+            let factor = match parts.get(2) {
+                Some(&factor) => Some(parse_f32(factor)?),
+                None => None,
             };
-            MaterialColor::Spectral(file, factor)
+            Ok(MaterialColor::Spectral(file, factor))
         }
         "xyz" => {
-            let x = parts[1]
-                .parse::<f32>()
-                .expect("Failed to parse ciexyz x value");
-            let y = parts[2]
-                .parse::<f32>()
-                .expect("Failed to parse ciexyz y value");
-            let z = parts[3]
-                .parse::<f32>()
-                .expect("Failed to parse ciexyz z value");
-
-            MaterialColor::CIEXYZ(x, y, z)
+            let values = parse_floats(&parts[1..])?;
+            match values.len() {
+                // a single value is used for all three components
+                1 => Ok(MaterialColor::CIEXYZ(values[0], values[0], values[0])),
+                3 => Ok(MaterialColor::CIEXYZ(values[0], values[1], values[2])),
+                _ => Err(missing()),
+            }
         }
-        r => {
-            let r = r
-                .parse::<f32>()
-                .expect(format!("Failed to parse r value: {}", &r).as_str());
-            let g = parts[1]
-                .parse::<f32>()
-                .expect(format!("Failed to parse r value: {}", &parts[1]).as_str());
-            let b = parts[2]
-                .parse::<f32>()
-                .expect(format!("Failed to parse r value: {}", &parts[2]).as_str());
-
-            MaterialColor::RGB(r, g, b)
+        _ => {
+            let values = parse_floats(parts)?;
+            match values.len() {
+                1 => Ok(MaterialColor::RGB(values[0], values[0], values[0])),
+                3 => Ok(MaterialColor::RGB(values[0], values[1], values[2])),
+                _ => Err(missing()),
+            }
         }
     }
 }
@@ -531,3 +413,59 @@ impl fmt::Display for MaterialColor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::MemoryResolver;
+
+    const SOURCE: &str = "# a material\n\
+                          newmtl a\n\
+                          Kd 1 0.5 0\n\
+                          Pr 0.5\n\
+                          newmtl b\n\
+                          aniso 0.2\n\
+                          d 0.5\n";
+
+    fn parse(source: &str, mode: ParseMode) -> Result<Parsed<Vec<Material>>, ParseError> {
+        Material::from_reader_with_mode(source.as_bytes(), "test.mtl", &MemoryResolver::new(), mode)
+    }
+
+    #[test]
+    fn strict_parse_fails_on_unknown_statements() {
+        let error = parse(SOURCE, ParseMode::Strict).unwrap_err();
+
+        assert!(match error.kind {
+            ParseErrorKind::UnsupportedStatement => true,
+            _ => false,
+        });
+        assert_eq!(error.token, Some("Pr".to_owned()));
+        assert_eq!(error.location.unwrap().to_string(), "test.mtl:4:1");
+    }
+
+    #[test]
+    fn lenient_parse_skips_unknown_statements_with_warnings() {
+        let parsed = parse(SOURCE, ParseMode::Lenient).unwrap();
+        let lines = parsed
+            .warnings
+            .iter()
+            .map(|w| w.location.as_ref().unwrap().line)
+            .collect::<Vec<usize>>();
+
+        assert_eq!(lines, vec![4, 6]);
+        assert_eq!(parsed.value.len(), 2);
+        assert_eq!(parsed.value[1].get_name(), "b");
+        assert_eq!(parsed.value[1].d, 0.5);
+    }
+
+    #[test]
+    fn statements_before_newmtl_fail_in_either_mode() {
+        for &mode in &[ParseMode::Strict, ParseMode::Lenient] {
+            let error = parse("Kd 1 1 1\n", mode).unwrap_err();
+            assert!(match error.kind {
+                ParseErrorKind::MalformedStatement => true,
+                _ => false,
+            });
+        }
+    }
+}
@@ -1,21 +1,43 @@
 //! A module collecting functions and types for representing the description of a renderable mesh
-use graphics::Material;
-use graphics::{Vertex, VertexPositionNormal, VertexPositionNormalTexture, VertexPositionTexture};
-use math::{Vec3, Vec4, ParseVector};
-use parser::{FromFile, ParseError};
+use graphics::{generate_normals, generate_tangents, Material, NormalGeneration};
+use graphics::{
+    Vertex, VertexPosition, VertexPositionNormal, VertexPositionNormalTexture,
+    VertexPositionTexture,
+};
+use math::{triangulate, Aabb, Frustum, Ray, RayHit, Triangulation, Vec3, Vec4};
+use parser::{
+    first_arg, parse_f32, tokenize, FileResolver, FromFile, FromReader, FromReaderWithMode,
+    Location, ParseError, ParseErrorKind, ParseMode, ParseWarning, Parsed, Resolver, Token,
+};
 use std::collections::HashMap;
 use std::fmt;
-use std::fs::File;
+use std::fs;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::mem;
+use std::panic;
+use std::thread;
+
+// how many bytes are read between progress reports while streaming
+const PROGRESS_INTERVAL: u64 = 1024 * 1024;
 
 /// A struct that represents a single mesh description
 #[derive(Clone,Debug)]
 pub struct MeshDescription {
-    /// The collection of vertices
+    /// The collection of unique vertices
     pub vertices: Vec<Vertex>,
-    /// The name of the mesh
+    /// The indices into `vertices` of the corners of each triangle
+    pub indices: Indices,
+    /// How the faces of the mesh were split into triangles
+    pub faces: FaceReport,
+    /// The smoothing group of the face that each triangle came from, `Some(0)` when smoothing
+    /// was turned off and `None` when there was no `s` statement before the face
+    pub smoothing_groups: Vec<Option<u32>>,
+    /// The name of the mesh, this is every group name from its `g` statement
     pub name: String,
+    /// The name of the object from the last `o` statement, empty when there wasn't one
+    pub object: String,
+    /// The groups the mesh belongs to
+    pub groups: Vec<String>,
     /// The name of the associated material
     pub material: String,
 }
@@ -25,8 +47,129 @@ pub struct MeshDescription {
 impl fmt::Display for MeshDescription {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Mesh ({})", self.name).ok();
+        writeln!(f, "\tObject: {}", self.object).ok();
         writeln!(f, "\tMaterial: {}", self.material).ok();
-        writeln!(f, "\tVertices: {}", self.vertices.len())
+        writeln!(f, "\tVertices: {}", self.vertices.len()).ok();
+        writeln!(f, "\tIndices: {}", self.indices.len()).ok();
+        writeln!(f, "\tFaces: {}", self.faces)
+    }
+}
+
+/// Counts of how the faces of a mesh were split into triangles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FaceReport {
+    /// Faces that were already triangles
+    pub triangles: usize,
+    /// Convex polygons that were split into a fan of triangles
+    pub fans: usize,
+    /// Concave polygons, or convex ones with too many collinear corners for a fan,
+    /// that were split by ear clipping
+    pub ear_clipped: usize,
+    /// Faces with fewer than three corners, these have no triangles and are dropped
+    pub degenerate: usize,
+}
+
+impl FaceReport {
+    /// returns the number of polygons with more than three corners that were triangulated
+    pub fn triangulated(&self) -> usize {
+        self.fans + self.ear_clipped
+    }
+
+    /// Combines the counts of two reports
+    ///
+    /// # Arguments
+    /// `other` - the report to combine with
+    pub fn merge(&self, other: &FaceReport) -> FaceReport {
+        FaceReport {
+            triangles: self.triangles + other.triangles,
+            fans: self.fans + other.fans,
+            ear_clipped: self.ear_clipped + other.ear_clipped,
+            degenerate: self.degenerate + other.degenerate,
+        }
+    }
+
+    // counts a face that has been split into triangles
+    fn record(&mut self, triangulation: Triangulation) {
+        match triangulation {
+            Triangulation::Triangle => self.triangles += 1,
+            Triangulation::Fan => self.fans += 1,
+            Triangulation::EarClipping => self.ear_clipped += 1,
+            Triangulation::Degenerate => self.degenerate += 1,
+        }
+    }
+}
+
+impl fmt::Display for FaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} triangles, {} triangulated ({} fans, {} ear clipped), {} degenerate",
+            self.triangles,
+            self.triangulated(),
+            self.fans,
+            self.ear_clipped,
+            self.degenerate
+        )
+    }
+}
+
+/// The indices of a mesh, stored in the smallest type that can refer to every vertex
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Indices {
+    /// Indices for a mesh with at most 65535 vertices
+    U16(Vec<u16>),
+    /// Indices for a mesh with more vertices
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// Creates indices using the smallest type that fits
+    ///
+    /// # Arguments
+    /// `indices` - the indices
+    /// `vertex_count` - the number of vertices the indices refer to
+    ///
+    /// # Remarks
+    ///
+    /// 65535 is never used as a 16 bit index, as it is commonly reserved to restart primitives.
+    pub fn new(indices: Vec<u32>, vertex_count: usize) -> Indices {
+        if vertex_count <= u16::MAX as usize {
+            Indices::U16(indices.into_iter().map(|i| i as u16).collect())
+        } else {
+            Indices::U32(indices)
+        }
+    }
+
+    /// returns the number of indices
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    /// returns true when there are no indices
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets an index as a usize
+    ///
+    /// # Arguments
+    /// `position` - the position of the index
+    pub fn get(&self, position: usize) -> Option<usize> {
+        match self {
+            Indices::U16(indices) => indices.get(position).map(|&i| i as usize),
+            Indices::U32(indices) => indices.get(position).map(|&i| i as usize),
+        }
+    }
+
+    /// returns an iterator over every index as a usize
+    pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = usize> + 'a> {
+        match self {
+            Indices::U16(indices) => Box::new(indices.iter().map(|&i| i as usize)),
+            Indices::U32(indices) => Box::new(indices.iter().map(|&i| i as usize)),
+        }
     }
 }
 
@@ -39,6 +182,35 @@ pub struct MeshDescriptions {
     pub meshes: Vec<MeshDescription>,
 }
 
+/// Something produced while streaming an obj file
+#[derive(Debug)]
+pub enum ObjEvent {
+    /// A group of faces has been read, the parser keeps nothing of it once it has been emitted
+    Mesh(MeshDescription),
+    /// More of the file has been read
+    Progress(StreamProgress),
+}
+
+/// How much of a file has been read while streaming it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamProgress {
+    /// The number of bytes read so far
+    pub bytes_read: u64,
+    /// The size of the file in bytes, or None when it isn't known
+    pub total_bytes: Option<u64>,
+}
+
+impl StreamProgress {
+    /// returns how much of the file has been read between 0 and 1, or None when the size isn't known
+    pub fn fraction(&self) -> Option<f32> {
+        match self.total_bytes {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.bytes_read as f64 / total as f64).min(1.0) as f32),
+            None => None,
+        }
+    }
+}
+
 impl fmt::Display for MeshDescriptions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -56,14 +228,95 @@ impl MeshDescription {
     /// # Arguments
     /// `name` - the name of the mesh
     /// `material` - the name of the material
-    /// `vertices` - the collection of vertices in the mesh
-    fn new(name: String, material: String, vertices: Vec<Vertex>) -> MeshDescription {
+    /// `vertices` - the collection of unique vertices in the mesh
+    /// `indices` - the indices of the corners of each triangle
+    /// `faces` - how the faces were split into triangles
+    pub fn new(
+        name: String,
+        material: String,
+        vertices: Vec<Vertex>,
+        indices: Vec<u32>,
+        faces: FaceReport,
+    ) -> MeshDescription {
+        let indices = Indices::new(indices, vertices.len());
+
         MeshDescription {
             name: name,
+            object: String::new(),
+            groups: Vec::new(),
             material: material,
             vertices: vertices,
+            indices: indices,
+            faces: faces,
+            smoothing_groups: Vec::new(),
         }
     }
+
+    /// Gets the vertices of a triangle
+    ///
+    /// # Arguments
+    /// `triangle` - the index of the triangle, multiplied by 3 this is the position of its
+    /// first index
+    pub fn triangle(&self, triangle: usize) -> Option<[&Vertex; 3]> {
+        let vertex = |corner| {
+            self.indices
+                .get(triangle * 3 + corner)
+                .and_then(|i| self.vertices.get(i))
+        };
+
+        Some([vertex(0)?, vertex(1)?, vertex(2)?])
+    }
+
+    /// Generates normals for the vertices of the mesh that don't have one,
+    /// see [`generate_normals`]
+    ///
+    /// # Arguments
+    /// `generation` - how the normals are generated
+    pub fn generate_normals(&mut self, generation: NormalGeneration) {
+        generate_normals(self, generation);
+    }
+
+    /// Generates a tangent for every vertex of the mesh that has a normal and a texture
+    /// coordinate, see [`generate_tangents`]
+    pub fn generate_tangents(&mut self) {
+        generate_tangents(self);
+    }
+
+    /// Gets the axis aligned bounds of the vertices in the mesh
+    ///
+    /// # Remarks
+    ///
+    /// A mesh without vertices has empty bounds.
+    pub fn bounds(&self) -> Aabb {
+        self.vertices
+            .iter()
+            .fold(Aabb::empty(), |acc, v| acc.grow(&v.position().xyz()))
+    }
+
+    /// Casts a ray against the triangles of the mesh
+    ///
+    /// # Arguments
+    /// `ray` - the ray to cast
+    ///
+    /// # Returns
+    /// the index of the closest triangle that was hit along with the hit,
+    /// see [`MeshDescription::triangle`]
+    pub fn raycast(&self, ray: &Ray) -> Option<(usize, RayHit)> {
+        (0..self.indices.len() / 3)
+            .filter_map(|i| self.triangle(i).map(|t| (i, t)))
+            .filter_map(|(i, t)| {
+                ray.intersect_triangle(
+                    &t[0].position().xyz(),
+                    &t[1].position().xyz(),
+                    &t[2].position().xyz(),
+                )
+                .map(|hit| (i, hit))
+            })
+            .fold(None, |closest: Option<(usize, RayHit)>, (i, hit)| match closest {
+                Some((_, ref c)) if c.distance <= hit.distance => closest,
+                _ => Some((i, hit)),
+            })
+    }
 }
 
 impl MeshDescriptions {
@@ -78,202 +331,1500 @@ impl MeshDescriptions {
             meshes: meshes,
         }
     }
+
+    /// Streams the meshes of an obj file one group at a time
+    ///
+    /// # Arguments
+    /// `file` - the path to the obj file
+    /// `mode` - whether unknown or unsupported statements fail the parse or are skipped
+    /// `on_event` - called with each mesh as soon as its group ends, and as the file is read
+    ///
+    /// # Returns
+    /// the materials from every `mtllib` in the file, along with any warnings
+    pub fn stream_file<F>(
+        file: &str,
+        mode: ParseMode,
+        on_event: F,
+    ) -> Result<Parsed<HashMap<String, Material>>, ParseError>
+    where
+        F: FnMut(ObjEvent),
+    {
+        let reader = fs::File::open(file).map_err(|e| ParseError::from(e).in_file(file))?;
+        let total_bytes = reader.metadata().ok().map(|m| m.len());
+
+        MeshDescriptions::stream_reader(
+            BufReader::new(reader),
+            file,
+            &FileResolver::for_file(file),
+            mode,
+            total_bytes,
+            on_event,
+        )
+    }
+
+    /// Streams the meshes of an obj file from a reader one group at a time
+    ///
+    /// # Arguments
+    /// `reader` - the source to read from
+    /// `name` - the name of the source, used as the file in error and warning locations
+    /// `resolver` - used to find the files of any `mtllib`
+    /// `mode` - whether unknown or unsupported statements fail the parse or are skipped
+    /// `total_bytes` - the size of the source when it is known, used to report progress
+    /// `on_event` - called with each mesh as soon as its group ends, and as the source is read
+    ///
+    /// # Remarks
+    ///
+    /// Faces can refer to any position, normal or texture coordinate before them, so those
+    /// are kept for the whole parse. Only the faces of the current group are held otherwise.
+    pub fn stream_reader<R, F>(
+        mut reader: R,
+        name: &str,
+        resolver: &dyn Resolver,
+        mode: ParseMode,
+        total_bytes: Option<u64>,
+        mut on_event: F,
+    ) -> Result<Parsed<HashMap<String, Material>>, ParseError>
+    where
+        R: BufRead,
+        F: FnMut(ObjEvent),
+    {
+        let mut parser = ObjParser::new(mode);
+        let mut progress = StreamProgress {
+            bytes_read: 0,
+            total_bytes: total_bytes,
+        };
+        let mut reported = 0;
+        let mut line = String::new();
+        let mut line_number = 0;
+
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(|e| ParseError::from(e).at(name, line_number + 1))?;
+            if read == 0 {
+                break;
+            }
+
+            line_number += 1;
+            progress.bytes_read += read as u64;
+            let text = line.trim_end_matches(|c| c == '\n' || c == '\r');
+
+            let result = parser
+                .parse_line(text, &Location::new(name, line_number, 1), resolver)
+                .map_err(|e| e.at(name, line_number));
+            mode.recover(result, &mut parser.warnings)?;
+
+            for mesh in parser.meshes.drain(..) {
+                on_event(ObjEvent::Mesh(mesh));
+            }
+
+            if progress.bytes_read - reported >= PROGRESS_INTERVAL {
+                reported = progress.bytes_read;
+                on_event(ObjEvent::Progress(progress));
+            }
+        }
+
+        let last = parser.take_group();
+        on_event(ObjEvent::Mesh(last));
+        on_event(ObjEvent::Progress(progress));
+
+        Ok(Parsed {
+            value: parser.materials,
+            warnings: parser.warnings,
+        })
+    }
+
+    /// Parses an obj file on several threads
+    ///
+    /// # Arguments
+    /// `file` - the path to the obj file
+    /// `mode` - whether unknown or unsupported statements fail the parse or are skipped
+    /// `threads` - the number of threads to parse on
+    pub fn from_file_parallel(
+        file: &str,
+        mode: ParseMode,
+        threads: usize,
+    ) -> Result<Parsed<MeshDescriptions>, ParseError> {
+        let source = fs::read_to_string(file).map_err(|e| ParseError::from(e).in_file(file))?;
+
+        MeshDescriptions::from_source_parallel(
+            &source,
+            file,
+            &FileResolver::for_file(file),
+            mode,
+            threads,
+        )
+    }
+
+    /// Parses an obj file held in memory on several threads
+    ///
+    /// # Arguments
+    /// `source` - the text of the obj file
+    /// `name` - the name of the source, used as the file in error and warning locations
+    /// `resolver` - used to find the files of any `mtllib`
+    /// `mode` - whether unknown or unsupported statements fail the parse or are skipped
+    /// `threads` - the number of threads to parse on
+    ///
+    /// # Remarks
+    ///
+    /// The source is split into one chunk of lines per thread and the chunks are read in two
+    /// passes on the threads. The first parses every line and gathers the positions, normals and
+    /// texture coordinates of each chunk, the second resolves the faces of each chunk against
+    /// everything defined before them, creating their vertices and splitting them into triangles.
+    /// The calling thread then applies the lines in order, sharing vertices within each run of
+    /// faces and loading any `mtllib`, so the result, including any error or warnings, is the
+    /// same as parsing serially.
+    pub fn from_source_parallel(
+        source: &str,
+        name: &str,
+        resolver: &dyn Resolver,
+        mode: ParseMode,
+        threads: usize,
+    ) -> Result<Parsed<MeshDescriptions>, ParseError> {
+        let chunks = split_lines(source, threads.max(1));
+        let parsed = on_threads(chunks, parse_chunk);
+
+        // a face can refer to anything defined before it, so each chunk starts
+        // after the positions, texture coordinates and normals of the chunks before it
+        let mut positions = Vec::new();
+        let mut textures = Vec::new();
+        let mut normals = Vec::new();
+        let mut starts = Vec::with_capacity(parsed.len());
+        for chunk in parsed.iter() {
+            starts.push((positions.len(), textures.len(), normals.len()));
+            positions.extend_from_slice(&chunk.positions);
+            textures.extend_from_slice(&chunk.textures);
+            normals.extend_from_slice(&chunk.normals);
+        }
+
+        let lines = parsed.into_iter().map(|chunk| chunk.lines).zip(starts).collect();
+        let resolved = on_threads(lines, |(lines, start)| {
+            resolve_chunk(lines, start, &positions, &textures, &normals)
+        });
+
+        let mut parser = ObjParser::new(mode);
+        let mut line_number = 0;
+
+        for statement in resolved.into_iter().flat_map(|chunk| chunk) {
+            line_number += 1;
+
+            let location = Location::new(name, line_number, 1);
+            let result = statement
+                .and_then(|statement| parser.apply(statement, &location, resolver))
+                .map_err(|e| e.at(name, line_number));
+            mode.recover(result, &mut parser.warnings)?;
+        }
+
+        let last = parser.take_group();
+        parser.meshes.push(last);
+
+        Ok(Parsed {
+            value: MeshDescriptions::new(parser.meshes, parser.materials),
+            warnings: parser.warnings,
+        })
+    }
+
+    /// Gets the names of the objects in the collection in the order they first appear
+    pub fn object_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for mesh in self.meshes.iter() {
+            if !names.contains(&&mesh.object[..]) {
+                names.push(&mesh.object);
+            }
+        }
+        names
+    }
+
+    /// Gets the meshes that make up an object
+    ///
+    /// # Arguments
+    /// `object` - the name of the object, empty for meshes that are not part of an object
+    pub fn object(&self, object: &str) -> Vec<&MeshDescription> {
+        self.meshes.iter().filter(|m| m.object == object).collect()
+    }
+
+    /// Gets the meshes that belong to a group
+    ///
+    /// # Arguments
+    /// `group` - the name of the group
+    pub fn group(&self, group: &str) -> Vec<&MeshDescription> {
+        self.meshes
+            .iter()
+            .filter(|m| m.groups.iter().any(|g| g == group))
+            .collect()
+    }
+
+    /// Generates normals for the vertices of every mesh in the collection that don't have one,
+    /// see [`generate_normals`]
+    ///
+    /// # Arguments
+    /// `generation` - how the normals are generated
+    pub fn generate_normals(&mut self, generation: NormalGeneration) {
+        for mesh in &mut self.meshes {
+            mesh.generate_normals(generation);
+        }
+    }
+
+    /// Generates a tangent for every vertex in the collection that has a normal and a texture
+    /// coordinate, see [`generate_tangents`]
+    pub fn generate_tangents(&mut self) {
+        for mesh in &mut self.meshes {
+            mesh.generate_tangents();
+        }
+    }
+
+    /// Gets how the faces of every mesh in the collection were split into triangles
+    pub fn face_report(&self) -> FaceReport {
+        self.meshes
+            .iter()
+            .fold(FaceReport::default(), |acc, m| acc.merge(&m.faces))
+    }
+
+    /// Gets the axis aligned bounds of every mesh in the collection
+    pub fn bounds(&self) -> Aabb {
+        self.meshes
+            .iter()
+            .fold(Aabb::empty(), |acc, m| acc.merge(&m.bounds()))
+    }
+
+    /// Describes everything in the collection, useful for checking that two parses agree
+    ///
+    /// # Remarks
+    ///
+    /// The materials are sorted by name, so the same content always has the same description.
+    pub fn describe(&self) -> String {
+        let mut materials = self.materials.iter().collect::<Vec<_>>();
+        materials.sort_by(|a, b| a.0.cmp(b.0));
+
+        format!("{:?}\n{:?}", self.meshes, materials)
+    }
+
+    /// Gets the meshes that may be visible, skipping any that are outside of the frustum
+    ///
+    /// # Arguments
+    /// `frustum` - the view frustum, in the same space as the mesh vertices
+    pub fn cull(&self, frustum: &Frustum) -> Vec<&MeshDescription> {
+        self.meshes
+            .iter()
+            .filter(|m| frustum.intersects_aabb(&m.bounds()))
+            .collect()
+    }
+
+    /// Casts a ray against every mesh in the collection
+    ///
+    /// # Arguments
+    /// `ray` - the ray to cast
+    ///
+    /// # Returns
+    /// the closest mesh that was hit along with the index of the triangle and the hit
+    pub fn raycast(&self, ray: &Ray) -> Option<(&MeshDescription, usize, RayHit)> {
+        self.meshes
+            .iter()
+            .filter(|m| ray.intersect_aabb(&m.bounds()).is_some())
+            .filter_map(|m| m.raycast(ray).map(|(i, hit)| (m, i, hit)))
+            .fold(None, |closest, (m, i, hit)| match closest {
+                Some((_, _, ref c)) if c.distance <= hit.distance => closest,
+                _ => Some((m, i, hit)),
+            })
+    }
 }
 
 impl FromFile for MeshDescriptions {
     type ParseResult = Result<MeshDescriptions, ParseError>;
 
     fn from_file(file: &str) -> Self::ParseResult {
-        let mut materials: HashMap<String, Material> = HashMap::new();
-        let mut current_material: String = "unknown material".to_owned();
-        let mut faces: Vec<Vertex> = Vec::new();
-        let mut vertex_normals: Vec<Vec3> = Vec::new();
-        let mut vertex_textures: Vec<Vec3> = Vec::new();
-        let mut vertices: Vec<Vec4> = Vec::new();
-        let mut meshes: Vec<MeshDescription> = Vec::new();
-        let mut group_name: String = "".to_owned();
-
-        let directory = Path::new(&file)
-            .parent()
-            .expect("Failed to get parent directory")
-            .to_str()
-            .expect("Failed to get parent directory string");
-
-        let file = File::open(&file).expect(format!("{} not found!", &file).as_str());
-        let reader = BufReader::new(&file);
-
-        for line in reader.lines() {
-            let parts = match line {
-                Ok(ref line) => line[..].split_whitespace().collect::<Vec<&str>>(),
-                Err(e) => return Err(ParseError::GeneralError(String::from(format!("{:?}", e)))),
-            };
-
-            if parts.len() == 0 {
-                continue;
+        MeshDescriptions::from_file_with_mode(file, ParseMode::Strict).map(|parsed| parsed.value)
+    }
+}
+
+impl FromReader for MeshDescriptions {
+    type ParseResult = Result<MeshDescriptions, ParseError>;
+
+    fn from_reader<R: BufRead>(reader: R, name: &str, resolver: &dyn Resolver) -> Self::ParseResult {
+        MeshDescriptions::from_reader_with_mode(reader, name, resolver, ParseMode::Strict)
+            .map(|parsed| parsed.value)
+    }
+}
+
+impl FromReaderWithMode for MeshDescriptions {
+    type Output = MeshDescriptions;
+
+    fn from_reader_with_mode<R: BufRead>(
+        reader: R,
+        name: &str,
+        resolver: &dyn Resolver,
+        mode: ParseMode,
+    ) -> Result<Parsed<MeshDescriptions>, ParseError> {
+        let mut meshes = Vec::new();
+        let materials = MeshDescriptions::stream_reader(reader, name, resolver, mode, None, |event| {
+            if let ObjEvent::Mesh(mesh) = event {
+                meshes.push(mesh);
             }
+        })?;
+
+        Ok(Parsed {
+            value: MeshDescriptions::new(meshes, materials.value),
+            warnings: materials.warnings,
+        })
+    }
+}
+
+// The state built up while reading an obj file line by line
+struct ObjParser {
+    mode: ParseMode,
+    warnings: Vec<ParseWarning>,
+    materials: HashMap<String, Material>,
+    current_material: String,
+    object_name: String,
+    group_names: Vec<String>,
+    smoothing_group: Option<u32>,
+    // the unique vertices and the triangles of the current run of faces that share
+    // an object, groups and material
+    group_vertices: Vec<Vertex>,
+    group_indices: Vec<u32>,
+    group_elements: HashMap<FaceElement<usize>, u32>,
+    group_faces: FaceReport,
+    group_smoothing: Vec<Option<u32>>,
+    vertex_normals: Vec<Vec3>,
+    vertex_textures: Vec<Vec3>,
+    vertices: Vec<Vec4>,
+    // runs that have ended and are waiting to be emitted
+    meshes: Vec<MeshDescription>,
+}
+
+// A line of an obj file that has been parsed but not yet applied to the parser,
+// parsing needs nothing from the lines before it so lines can be parsed on any thread
+enum Statement<'a> {
+    Empty,
+    Position(Vec4),
+    Normal(Vec3),
+    Texture(Vec3),
+    Face(Vec<(FaceElement<isize>, Token<'a>)>),
+    // a face that has already been resolved against the elements before it
+    ResolvedFace(Face),
+    Object(String),
+    Group(Vec<String>),
+    Smoothing(u32),
+    UseMaterial(String),
+    MaterialLibrary(String),
+}
+
+// A face with its indices resolved, its vertices created and its triangles found,
+// which is everything about a face that doesn't depend on the run of faces it joins
+struct Face {
+    elements: Vec<FaceElement<usize>>,
+    vertices: Vec<Vertex>,
+    triangles: Vec<[usize; 3]>,
+    triangulation: Triangulation,
+}
 
-            let (token, rest) = (parts[0], &parts[1..].connect(" "));
-
-            match token {
-                "mtllib" => {
-                    let material_path = format!("{}/{}", &directory, &rest);
-                    materials = match Material::from_file(&material_path) {
-                        Ok(m) => m.iter().fold(materials, |mut acc, m| {
-                            acc.insert(m.get_name(), m.clone());
-                            acc
-                        }),
-                        Err(e) => match e {
-                            ParseError::GeneralError(e) => return Err(ParseError::GeneralError(e)),
-                            ParseError::UnknownToken(e) => return Err(ParseError::UnknownToken(e)),
-                        },
-                    };
+// The lines of a chunk of an obj file parsed on a thread,
+// along with the positions, texture coordinates and normals defined in the chunk
+struct ParsedChunk<'a> {
+    lines: Vec<Result<Statement<'a>, ParseError>>,
+    positions: Vec<Vec4>,
+    textures: Vec<Vec3>,
+    normals: Vec<Vec3>,
+}
+
+// The indices of a face element in the order position, texture, normal. As written in the file
+// these are 1-based, or relative to the end of the elements defined so far when negative.
+// When the face is applied they are resolved to 0-based indices, corners that resolve to the
+// same indices share a vertex.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum FaceElement<I> {
+    Position(I),
+    PositionTexture(I, I),
+    PositionNormal(I, I),
+    PositionNormalTexture(I, I, I),
+}
+
+impl ObjParser {
+    fn new(mode: ParseMode) -> ObjParser {
+        ObjParser {
+            mode: mode,
+            warnings: Vec::new(),
+            materials: HashMap::new(),
+            current_material: "unknown material".to_owned(),
+            object_name: String::new(),
+            group_names: Vec::new(),
+            smoothing_group: None,
+            group_vertices: Vec::new(),
+            group_indices: Vec::new(),
+            group_elements: HashMap::new(),
+            group_faces: FaceReport::default(),
+            group_smoothing: Vec::new(),
+            vertex_normals: Vec::new(),
+            vertex_textures: Vec::new(),
+            vertices: Vec::new(),
+            meshes: Vec::new(),
+        }
+    }
+
+    // `location` is the start of the line, used to record where an mtllib was included from
+    fn parse_line(
+        &mut self,
+        line: &str,
+        location: &Location,
+        resolver: &dyn Resolver,
+    ) -> Result<(), ParseError> {
+        let statement = parse_statement(line)?;
+        self.apply(statement, location, resolver)
+    }
+
+    fn apply(
+        &mut self,
+        statement: Statement,
+        location: &Location,
+        resolver: &dyn Resolver,
+    ) -> Result<(), ParseError> {
+        match statement {
+            Statement::Empty => (),
+            Statement::Position(v) => self.vertices.push(v),
+            Statement::Normal(v) => self.vertex_normals.push(v),
+            Statement::Texture(v) => self.vertex_textures.push(v),
+            Statement::Face(elements) => {
+                let face = resolve_face(
+                    elements,
+                    &self.vertices,
+                    &self.vertex_textures,
+                    &self.vertex_normals,
+                )?;
+                self.add_face(face);
+            }
+            Statement::ResolvedFace(face) => self.add_face(face),
+            Statement::Object(name) => {
+                self.end_run();
+                self.object_name = name;
+                self.group_names.clear();
+            }
+            Statement::Group(names) => {
+                self.end_run();
+                self.group_names = names;
+            }
+            Statement::Smoothing(group) => self.smoothing_group = Some(group),
+            Statement::UseMaterial(name) => {
+                if name != self.current_material {
+                    self.end_run();
+                    self.current_material = name;
                 }
-                "v" => {
-                    match Vec4::from_str(rest) {
-                        Ok(arr) => vertices.push(arr),
-                        Err(e) => return Err(ParseError::GeneralError(e)),
-                    };
+            }
+            Statement::MaterialLibrary(path) => {
+                let mode = self.mode;
+                let materials = resolver
+                    .open(&path)
+                    .and_then(|reader| {
+                        Material::from_reader_with_mode(
+                            reader,
+                            &resolver.resolve(&path),
+                            resolver,
+                            mode,
+                        )
+                    })
+                    .map_err(|e| e.included_from(location.clone()))?;
+
+                self.warnings.extend(
+                    materials
+                        .warnings
+                        .into_iter()
+                        .map(|w| w.included_from(location.clone())),
+                );
+
+                for m in materials.value.iter() {
+                    self.materials.insert(m.get_name(), m.clone());
                 }
-                "vn" => {
-                    match Vec3::from_str(rest) {
-                        Ok(arr) => vertex_normals.push(arr),
-                        Err(e) => return Err(ParseError::GeneralError(e)),
-                    };
+            }
+        }
+
+        Ok(())
+    }
+
+    // adds the triangles of a face to the group, corners with the same indices share a vertex
+    fn add_face(&mut self, face: Face) {
+        let mut corners = Vec::with_capacity(face.elements.len());
+        for (element, vertex) in face.elements.into_iter().zip(face.vertices) {
+            let next = self.group_vertices.len() as u32;
+            let index = *self.group_elements.entry(element).or_insert(next);
+            if index == next {
+                self.group_vertices.push(vertex);
+            }
+            corners.push(index);
+        }
+
+        for triangle in face.triangles {
+            self.group_indices
+                .extend(triangle.iter().map(|&corner| corners[corner]));
+            self.group_smoothing.push(self.smoothing_group);
+        }
+        self.group_faces.record(face.triangulation);
+    }
+
+    // starts a new run of faces, keeping the current one when it has any faces
+    fn end_run(&mut self) {
+        if !self.group_vertices.is_empty() {
+            let mesh = self.take_group();
+            self.meshes.push(mesh);
+        }
+    }
+
+    // ends the current run of faces, moving its faces out rather than copying them
+    fn take_group(&mut self) -> MeshDescription {
+        self.group_elements.clear();
+
+        MeshDescription {
+            object: self.object_name.clone(),
+            groups: self.group_names.clone(),
+            smoothing_groups: mem::replace(&mut self.group_smoothing, Vec::new()),
+            ..MeshDescription::new(
+                self.group_names.join(" "),
+                self.current_material.clone(),
+                mem::replace(&mut self.group_vertices, Vec::new()),
+                mem::replace(&mut self.group_indices, Vec::new()),
+                mem::replace(&mut self.group_faces, FaceReport::default()),
+            )
+        }
+    }
+}
+
+// splits a source into at most `count` chunks of whole lines with roughly the same size
+fn split_lines(source: &str, count: usize) -> Vec<&str> {
+    let mut chunks = Vec::with_capacity(count);
+    let mut rest = source;
+
+    for remaining in (1..count + 1).rev() {
+        if rest.is_empty() {
+            break;
+        }
+
+        let target = rest.len() / remaining;
+        let end = match rest.as_bytes()[target..].iter().position(|&b| b == b'\n') {
+            Some(newline) => target + newline + 1,
+            None => rest.len(),
+        };
+
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    chunks
+}
+
+// runs `work` on each item on its own thread, returning the results in the same order
+fn on_threads<T, R, F>(items: Vec<T>, work: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let work = &work;
+
+    thread::scope(|scope| {
+        let workers = items
+            .into_iter()
+            .map(|item| scope.spawn(move || work(item)))
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+// parses every line of a chunk, a line ends the same way as when streaming
+fn parse_chunk<'a>(chunk: &'a str) -> ParsedChunk<'a> {
+    let mut parsed = ParsedChunk {
+        lines: Vec::new(),
+        positions: Vec::new(),
+        textures: Vec::new(),
+        normals: Vec::new(),
+    };
+
+    for line in chunk.lines() {
+        let line = line.trim_end_matches('\r');
+        let statement = parse_statement(line);
+        match statement {
+            Ok(Statement::Position(v)) => parsed.positions.push(v),
+            Ok(Statement::Texture(v)) => parsed.textures.push(v),
+            Ok(Statement::Normal(v)) => parsed.normals.push(v),
+            _ => (),
+        }
+        parsed.lines.push(statement);
+    }
+
+    parsed
+}
+
+// resolves the faces of a chunk, `start` is how many positions, texture coordinates and normals
+// were defined before the chunk and the slices hold those of every chunk. The positions, texture
+// coordinates and normals themselves are left out as they have been gathered already.
+fn resolve_chunk<'a>(
+    lines: Vec<Result<Statement<'a>, ParseError>>,
+    start: (usize, usize, usize),
+    positions: &[Vec4],
+    textures: &[Vec3],
+    normals: &[Vec3],
+) -> Vec<Result<Statement<'a>, ParseError>> {
+    let (mut position_count, mut texture_count, mut normal_count) = start;
+
+    lines
+        .into_iter()
+        .map(|statement| {
+            statement.and_then(|statement| match statement {
+                Statement::Position(_) => {
+                    position_count += 1;
+                    Ok(Statement::Empty)
                 }
-                "vt" => {
-                    match Vec3::from_str(rest) {
-                        Ok(arr) => vertex_textures.push(arr),
-                        Err(e) => return Err(ParseError::GeneralError(e)),
-                    };
+                Statement::Texture(_) => {
+                    texture_count += 1;
+                    Ok(Statement::Empty)
                 }
-                "g" => {
-                    if faces.len() == 0 {
-                        group_name = rest.clone();
-                        continue;
-                    }
-
-                    meshes.push(MeshDescription::new(
-                        group_name,
-                        current_material.clone(),
-                        faces.clone(),
-                    ));
-                    group_name = rest.clone();
-                    faces.clear();
+                Statement::Normal(_) => {
+                    normal_count += 1;
+                    Ok(Statement::Empty)
                 }
-                "usemtl" => current_material = rest.clone(),
-                "f" => match parse_face(rest, &vertices, &vertex_normals, &vertex_textures) {
-                    Ok(f) => faces.extend_from_slice(&f[..]),
-                    Err(_) => return Err(ParseError::GeneralError("Error parsing face".to_owned())),
-                },
-                "vp" => continue,
-                "s" => continue,
-                "#" => continue,
-                x => return Err(ParseError::UnknownToken(String::from(x))),
+                Statement::Face(elements) => resolve_face(
+                    elements,
+                    &positions[..position_count],
+                    &textures[..texture_count],
+                    &normals[..normal_count],
+                )
+                .map(Statement::ResolvedFace),
+                statement => Ok(statement),
+            })
+        })
+        .collect()
+}
+
+fn parse_statement<'a>(line: &'a str) -> Result<Statement<'a>, ParseError> {
+    let mut parts = tokenize(line);
+    let token = match parts.next() {
+        Some(token) => token,
+        None => return Ok(Statement::Empty),
+    };
+    let args = parts.collect::<Vec<Token>>();
+    let rest = || args.iter().map(|arg| arg.text).collect::<Vec<&str>>().join(" ");
+    // the values as they are written in the line, or the statement when there are none
+    let values = || match (args.first(), args.last()) {
+        (Some(first), Some(last)) => first.through(line, last),
+        _ => token,
+    };
+
+    match token.text {
+        "v" => {
+            let (v, count) = parse_components(&args)?;
+            match count {
+                3 => Ok(Statement::Position(Vec4::new(v[0], v[1], v[2], 1.0))),
+                4 => Ok(Statement::Position(Vec4::new(v[0], v[1], v[2], v[3]))),
+                _ => Err(wrong_component_count("v", "3 or 4", values())),
+            }
+        }
+        "vn" => {
+            let (v, count) = parse_components(&args)?;
+            match count {
+                3 => Ok(Statement::Normal(Vec3::new(v[0], v[1], v[2]))),
+                _ => Err(wrong_component_count("vn", "3", values())),
             }
         }
+        "vt" => {
+            let (v, count) = parse_components(&args)?;
+            match count {
+                1..=3 => Ok(Statement::Texture(Vec3::new(v[0], v[1], v[2]))),
+                _ => Err(wrong_component_count("vt", "1 to 3", values())),
+            }
+        }
+        "f" => parse_face(&args).map(Statement::Face),
+        "o" => Ok(Statement::Object(rest())),
+        "g" => Ok(Statement::Group(args.iter().map(|g| g.text.to_owned()).collect())),
+        "s" => parse_smoothing_group(first_arg(token, &args)?).map(Statement::Smoothing),
+        "usemtl" => Ok(Statement::UseMaterial(rest())),
+        "mtllib" => Ok(Statement::MaterialLibrary(rest())),
+        "vp" => Ok(Statement::Empty),
+        "#" => Ok(Statement::Empty),
+        _ => Err(ParseError::new(
+            ParseErrorKind::UnsupportedStatement,
+            "unknown obj statement",
+        )
+        .with_token(token)),
+    }
+}
 
-        meshes.push(MeshDescription::new(
-            group_name,
-            current_material.clone(),
-            faces.clone(),
-        ));
+// parses every value of a vertex statement, keeping the first four
+// returns the values along with how many there were
+fn parse_components(values: &[Token]) -> Result<([f32; 4], usize), ParseError> {
+    let mut components = [0.0f32; 4];
+    let mut count = 0;
+
+    for &value in values {
+        let value = parse_f32(value)?;
+        if count < components.len() {
+            components[count] = value;
+        }
+        count += 1;
+    }
 
-        Ok(MeshDescriptions::new(meshes, materials))
+    Ok((components, count))
+}
+
+// `off` and 0 both turn smoothing off
+fn parse_smoothing_group(group: Token) -> Result<u32, ParseError> {
+    match group.text {
+        "off" => Ok(0),
+        _ => group.text.parse::<u32>().map_err(|_| {
+            ParseError::new(ParseErrorKind::BadNumber, "expected a smoothing group or `off`")
+                .with_token(group)
+        }),
     }
 }
 
-fn parse_face(
-    parts: &str,
-    positions: &Vec<Vec4>,
-    normals: &Vec<Vec3>,
-    textures: &Vec<Vec3>,
-) -> Result<Vec<Vertex>, ParseError> {
-    let mut vertices: Vec<Vertex> = Vec::new();
-    let parts = parts.split_whitespace().collect::<Vec<&str>>();
+fn wrong_component_count(statement: &str, expected: &str, values: Token) -> ParseError {
+    ParseError::new(
+        ParseErrorKind::MalformedStatement,
+        &format!("`{}` expects {} values", statement, expected),
+    )
+    .with_token(values)
+}
 
-    for part in parts.iter() {
-        let s = String::from(*part);
+fn parse_face<'a>(parts: &[Token<'a>]) -> Result<Vec<(FaceElement<isize>, Token<'a>)>, ParseError> {
+    let mut elements = Vec::with_capacity(4);
 
-        if s.contains("//") {
-            match parse_vertex_normal(&s, &positions, &normals) {
-                Ok(vertex_normal) => vertices.push(Vertex::PositionNormal(vertex_normal)),
-                Err(e) => return Err(ParseError::GeneralError(String::from(e))),
-            };
-        } else {
-            match parts.len() {
-                2 => match parse_vertex_texture(&part, &positions, &textures) {
-                    Ok(vertex_texture) => vertices.push(Vertex::PositionTexture(vertex_texture)),
-                    Err(e) => return Err(ParseError::GeneralError(String::from(e))),
-                },
-                _ => match parse_vertex_texture_normal(&part, &positions, &textures, &normals) {
-                    Ok(vertex_texture_normal) => {
-                        vertices.push(Vertex::PositionNormalTexture(vertex_texture_normal))
-                    }
-                    Err(e) => return Err(ParseError::GeneralError(String::from(e))),
-                },
+    for &part in parts {
+        let mut indices = [""; 3];
+        let mut count = 0;
+        for index in part.text.split('/') {
+            if count < indices.len() {
+                indices[count] = index;
             }
+            count += 1;
+        }
+        let index = |i: usize| parse_index(indices[i], part);
+
+        let element = match (count, indices[1].is_empty()) {
+            (1, _) => FaceElement::Position(index(0)?),
+            (2, false) => FaceElement::PositionTexture(index(0)?, index(1)?),
+            (3, true) => FaceElement::PositionNormal(index(0)?, index(2)?),
+            (3, false) => FaceElement::PositionNormalTexture(index(0)?, index(1)?, index(2)?),
+            _ => {
+                return Err(ParseError::new(
+                    ParseErrorKind::MalformedStatement,
+                    "face element should be `v`, `v/vt`, `v//vn` or `v/vt/vn`",
+                )
+                .with_token(part))
+            }
+        };
+
+        elements.push((element, part));
+    }
+
+    Ok(elements)
+}
+
+// Parses an obj index as written, `part` is the whole face element used in error messages
+fn parse_index(index: &str, part: Token) -> Result<isize, ParseError> {
+    if index.is_empty() {
+        return Err(ParseError::new(
+            ParseErrorKind::MalformedStatement,
+            "face element is missing an index",
+        )
+        .with_token(part));
+    }
+
+    index.parse::<isize>().map_err(|_| {
+        ParseError::new(ParseErrorKind::BadNumber, "expected an index").with_token(part)
+    })
+}
+
+// Resolves a face against the positions, texture coordinates and normals defined before it
+fn resolve_face(
+    elements: Vec<(FaceElement<isize>, Token)>,
+    positions: &[Vec4],
+    textures: &[Vec3],
+    normals: &[Vec3],
+) -> Result<Face, ParseError> {
+    let mut face = Face {
+        elements: Vec::with_capacity(elements.len()),
+        vertices: Vec::with_capacity(elements.len()),
+        triangles: Vec::new(),
+        triangulation: Triangulation::Triangle,
+    };
+
+    for (element, part) in elements {
+        let element = resolve_face_element(
+            element,
+            part,
+            positions.len(),
+            textures.len(),
+            normals.len(),
+        )?;
+
+        face.vertices
+            .push(create_vertex(element, positions, textures, normals));
+        face.elements.push(element);
+    }
+
+    let corners = face
+        .vertices
+        .iter()
+        .map(|vertex| vertex.position().xyz())
+        .collect::<Vec<Vec3>>();
+    let (triangles, triangulation) = triangulate(&corners);
+    face.triangles = triangles;
+    face.triangulation = triangulation;
+
+    Ok(face)
+}
+
+// Resolves the indices of a face element against the number of positions, texture
+// coordinates and normals defined so far
+fn resolve_face_element(
+    element: FaceElement<isize>,
+    part: Token,
+    positions: usize,
+    textures: usize,
+    normals: usize,
+) -> Result<FaceElement<usize>, ParseError> {
+    let resolve = |index, count| resolve_index(index, count, part);
+
+    Ok(match element {
+        FaceElement::Position(p) => FaceElement::Position(resolve(p, positions)?),
+        FaceElement::PositionTexture(p, t) => {
+            FaceElement::PositionTexture(resolve(p, positions)?, resolve(t, textures)?)
+        }
+        FaceElement::PositionNormal(p, n) => {
+            FaceElement::PositionNormal(resolve(p, positions)?, resolve(n, normals)?)
+        }
+        FaceElement::PositionNormalTexture(p, t, n) => FaceElement::PositionNormalTexture(
+            resolve(p, positions)?,
+            resolve(t, textures)?,
+            resolve(n, normals)?,
+        ),
+    })
+}
+
+// Turns a 1-based or negative relative obj index into a 0-based index
+// among the `count` elements defined so far
+fn resolve_index(index: isize, count: usize, part: Token) -> Result<usize, ParseError> {
+    let resolved = if index > 0 {
+        Some(index as usize - 1).filter(|&i| i < count)
+    } else if index < 0 {
+        count.checked_sub(index.wrapping_neg() as usize)
+    } else {
+        None
+    };
+
+    resolved.ok_or_else(|| {
+        ParseError::new(
+            ParseErrorKind::IndexOutOfRange,
+            &format!("index {} is out of range, {} are defined", index, count),
+        )
+        .with_token(part)
+    })
+}
+
+// Creates the vertex for a resolved face element
+fn create_vertex(
+    element: FaceElement<usize>,
+    positions: &[Vec4],
+    textures: &[Vec3],
+    normals: &[Vec3],
+) -> Vertex {
+    match element {
+        FaceElement::Position(p) => Vertex::Position(VertexPosition {
+            position: positions[p],
+        }),
+        FaceElement::PositionTexture(p, t) => Vertex::PositionTexture(VertexPositionTexture {
+            position: positions[p],
+            texture: textures[t],
+        }),
+        FaceElement::PositionNormal(p, n) => Vertex::PositionNormal(VertexPositionNormal {
+            position: positions[p],
+            normal: normals[n],
+        }),
+        FaceElement::PositionNormalTexture(p, t, n) => {
+            Vertex::PositionNormalTexture(VertexPositionNormalTexture {
+                position: positions[p],
+                texture: textures[t],
+                normal: normals[n],
+            })
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::{Mat4, Matrix};
+    use parser::{MemoryResolver, ParseErrorKind};
+
+    fn parse(source: &str) -> MeshDescriptions {
+        MeshDescriptions::from_source(source, "test.obj", &MemoryResolver::new()).unwrap()
+    }
+
+    fn parse_with_mode(
+        source: &str,
+        resolver: &MemoryResolver,
+        mode: ParseMode,
+    ) -> Result<Parsed<MeshDescriptions>, ParseError> {
+        MeshDescriptions::from_reader_with_mode(source.as_bytes(), "test.obj", resolver, mode)
+    }
+
+    fn locations(warnings: &[ParseWarning]) -> Vec<String> {
+        warnings
+            .iter()
+            .map(|w| w.location.as_ref().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn bounds_enclose_the_vertices_of_the_meshes() {
+        let meshes = parse("v 0 0 0\nv 2 1 0\nv 1 3 -1\nf 1 2 3\ng other\nv 5 0 0\nf 1 2 4\n");
+
+        assert_eq!(
+            meshes.meshes[0].bounds(),
+            Aabb::new(Vec3::new(0.0, 0.0, -1.0), Vec3::new(2.0, 3.0, 0.0))
+        );
+        assert_eq!(
+            meshes.bounds(),
+            Aabb::new(Vec3::new(0.0, 0.0, -1.0), Vec3::new(5.0, 3.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn a_mesh_without_vertices_has_empty_bounds() {
+        let empty = MeshDescription::new(
+            String::new(),
+            String::new(),
+            Vec::new(),
+            Vec::new(),
+            FaceReport::default(),
+        );
+        assert!(empty.bounds().is_empty());
+
+        // positions that no face uses aren't part of any mesh
+        let meshes = parse("v 1 1 1\nv 2 1 1\n");
+        assert!(meshes.meshes[0].vertices.is_empty());
+        assert!(meshes.bounds().is_empty());
+    }
+
+    // two squares facing z, the first at z = 1 and the second further away at z = 2
+    const LAYERS: &str = "v 0 0 2\nv 1 0 2\nv 1 1 2\nv 0 1 2\n\
+                          v 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1\n\
+                          f 1 2 3 4\nf 5 6 7 8\n";
+
+    #[test]
+    fn raycasts_hit_the_closest_triangle() {
+        let meshes = parse(LAYERS);
+        let mesh = &meshes.meshes[0];
+
+        let forwards = Ray::new(Vec3::new(0.25, 0.75, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let (triangle, hit) = mesh.raycast(&forwards).unwrap();
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+        assert!(mesh.triangle(triangle).unwrap().iter().all(|v| v.position().z == 1.0));
+
+        // from the other side the squares are hit on their backs, nearest first
+        let backwards = Ray::new(Vec3::new(0.75, 0.25, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let (triangle, hit) = mesh.raycast(&backwards).unwrap();
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+        assert!(mesh.triangle(triangle).unwrap().iter().all(|v| v.position().z == 2.0));
+
+        // between the squares only the one in front of the ray is hit
+        let between = Ray::new(Vec3::new(0.5, 0.5, 1.5), Vec3::new(0.0, 0.0, 1.0));
+        assert!((mesh.raycast(&between).unwrap().1.distance - 0.5).abs() < 1e-6);
+
+        // the closest mesh is picked when the squares are in different groups
+        let groups = parse(&LAYERS.replace("f 1", "g far\nf 1").replace("f 5", "g near\nf 5"));
+        let (closest, _, hit) = groups.raycast(&forwards).unwrap();
+        assert_eq!(closest.name, "near");
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+        assert_eq!(groups.raycast(&backwards).unwrap().0.name, "far");
+    }
+
+    #[test]
+    fn raycasts_that_miss_every_triangle_hit_nothing() {
+        let meshes = parse(LAYERS);
+
+        let beside = Ray::new(Vec3::new(1.5, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let away = Ray::new(Vec3::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(meshes.meshes[0].raycast(&beside).is_none());
+        assert!(meshes.meshes[0].raycast(&away).is_none());
+        assert!(meshes.raycast(&beside).is_none());
+    }
+
+    #[test]
+    fn culling_drops_meshes_outside_the_frustum() {
+        let meshes = parse(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 50 0 0\nv 51 0 0\nv 50 1 0\n\
+             g visible\nf 1 2 3\ng off_screen\nf 4 5 6\ng straddling\nf 1 5 6\n",
+        );
+        let projection = Mat4::perspective((800, 600), std::f32::consts::FRAC_PI_2, (1.0, 100.0));
+        let view = Mat4::view(
+            &Vec3::new(0.0, 0.0, -10.0),
+            &Vec3::new(0.0, 0.0, 1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        let frustum = Frustum::from_matrix(&(projection * view));
+
+        let names = meshes.cull(&frustum).iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["visible", "straddling"]);
+    }
+
+    #[test]
+    fn faces_are_counted_by_how_they_were_split() {
+        let meshes = parse(
+            "v 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\n\
+             f 1 2 3\n\
+             f 1 2 3 6\n\
+             f 1 2 3 4 5 6\n\
+             f 1 2\n",
+        );
+        let faces = meshes.meshes[0].faces;
+
+        assert_eq!(
+            faces,
+            FaceReport {
+                triangles: 1,
+                fans: 1,
+                ear_clipped: 1,
+                degenerate: 1,
+            }
+        );
+        assert_eq!(faces.triangulated(), 2);
+        assert_eq!(meshes.meshes[0].indices.len(), (1 + 2 + 4) * 3);
+    }
+
+    const UNSUPPORTED: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+                               curv 0 1 1 2\n\
+                               f 1 2 3\n\
+                               \n\
+                               cstype bezier\n";
+
+    #[test]
+    fn strict_parse_fails_on_the_first_unsupported_statement() {
+        let error = parse_with_mode(UNSUPPORTED, &MemoryResolver::new(), ParseMode::Strict)
+            .unwrap_err();
+
+        assert!(match error.kind {
+            ParseErrorKind::UnsupportedStatement => true,
+            _ => false,
+        });
+        assert_eq!(error.token, Some("curv".to_owned()));
+        assert_eq!(error.location.unwrap().to_string(), "test.obj:4:1");
+    }
+
+    #[test]
+    fn lenient_parse_warns_with_line_numbers() {
+        let parsed = parse_with_mode(UNSUPPORTED, &MemoryResolver::new(), ParseMode::Lenient)
+            .unwrap();
+
+        assert_eq!(locations(&parsed.warnings), vec!["test.obj:4:1", "test.obj:7:1"]);
+        assert_eq!(parsed.warnings[1].token, Some("cstype".to_owned()));
+        assert_eq!(parsed.value.meshes[0].indices.len(), 3);
+    }
+
+    #[test]
+    fn lenient_parse_still_fails_on_malformed_statements() {
+        let error = parse_with_mode(
+            "cstype bezier\nv 0 0\n",
+            &MemoryResolver::new(),
+            ParseMode::Lenient,
+        )
+        .unwrap_err();
+
+        assert!(match error.kind {
+            ParseErrorKind::MalformedStatement => true,
+            _ => false,
+        });
+        assert_eq!(error.location.unwrap().to_string(), "test.obj:2:3");
+    }
+
+    #[test]
+    fn warnings_from_an_included_file_are_located_in_that_file() {
+        let resolver = MemoryResolver::new().with_file("a.mtl", "newmtl a\nPr 0.5\nKd 1 1 1\n");
+        let parsed = parse_with_mode("\nmtllib a.mtl\nfoo\n", &resolver, ParseMode::Lenient)
+            .unwrap();
+
+        assert_eq!(locations(&parsed.warnings), vec!["a.mtl:2:1", "test.obj:3:1"]);
+        assert_eq!(
+            parsed.warnings[0].include_chain,
+            vec![Location::new("test.obj", 2, 1)]
+        );
+        assert!(parsed.warnings[1].include_chain.is_empty());
+        assert!(parsed.value.materials.contains_key("a"));
+    }
+
+    #[test]
+    fn streamed_meshes_match_the_meshes_from_a_file() {
+        let file = "content/Earth/earth.obj";
+        let parsed = MeshDescriptions::from_file(file).unwrap();
+
+        let mut meshes = Vec::new();
+        let mut progress = Vec::new();
+        let materials = MeshDescriptions::stream_file(file, ParseMode::Strict, |event| match event {
+            ObjEvent::Mesh(mesh) => meshes.push(mesh),
+            ObjEvent::Progress(p) => progress.push(p),
+        })
+        .unwrap();
+
+        assert_eq!(format!("{:?}", meshes), format!("{:?}", parsed.meshes));
+        assert_eq!(materials.value.len(), parsed.materials.len());
+        assert!(materials.warnings.is_empty());
+
+        let size = fs::metadata(file).unwrap().len();
+        let last = progress.last().unwrap();
+        assert!(progress.windows(2).all(|p| p[0].bytes_read <= p[1].bytes_read));
+        assert_eq!(last.bytes_read, size);
+        assert_eq!(last.total_bytes, Some(size));
+        assert_eq!(last.fraction(), Some(1.0));
+    }
 
-    Ok(vertices)
-}
-
-fn parse_vertex_normal(
-    s: &String,
-    vertices: &Vec<Vec4>,
-    normals: &Vec<Vec3>,
-) -> Result<VertexPositionNormal, String> {
-    let parts = s.split("//").collect::<Vec<&str>>();
-
-    match parts[0].parse::<usize>() {
-        Ok(v_index) => match parts[1].parse::<usize>() {
-            Ok(n_index) => Ok(VertexPositionNormal {
-                position: vertices[v_index - 1],
-                normal: normals[n_index - 1],
-            }),
-            Err(_) => Err("Error parsing vertex normal".to_owned()),
-        },
-        Err(_) => Err("Error parsing vertex normal".to_owned()),
-    }
-}
-
-fn parse_vertex_texture_normal(
-    parts: &str,
-    vertices: &Vec<Vec4>,
-    textures: &Vec<Vec3>,
-    normals: &Vec<Vec3>,
-) -> Result<VertexPositionNormalTexture, String> {
-    let parts = parts.split("/").collect::<Vec<&str>>();
-    match parts[0].parse::<usize>() {
-        Ok(v_index) => match parts[1].parse::<usize>() {
-            Ok(t_index) => match parts[2].parse::<usize>() {
-                Ok(n_index) => Ok(VertexPositionNormalTexture {
-                    position: vertices[v_index - 1],
-                    normal: normals[n_index - 1],
-                    texture: textures[t_index - 1],
-                }),
-                Err(_) => Err("Error parsing vertex texture normal".to_owned()),
+    #[test]
+    fn streamed_meshes_end_with_each_group() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+                      g a\nf 1 2 3\n\
+                      g b\nf 3 2 1\n";
+        let mut names = Vec::new();
+        MeshDescriptions::stream_reader(
+            source.as_bytes(),
+            "test.obj",
+            &MemoryResolver::new(),
+            ParseMode::Strict,
+            None,
+            |event| {
+                if let ObjEvent::Mesh(mesh) = event {
+                    names.push(mesh.name.clone());
+                }
             },
-            Err(_) => Err("Error parsing vertex texture normal".to_owned()),
-        },
-        Err(_) => Err("Error parsing vertex texture normal".to_owned()),
-    }
-}
-
-fn parse_vertex_texture(
-    parts: &str,
-    vertices: &Vec<Vec4>,
-    textures: &Vec<Vec3>,
-) -> Result<VertexPositionTexture, String> {
-    let parts = parts.split("/").collect::<Vec<&str>>();
-    match parts[0].parse::<usize>() {
-        Ok(v_index) => match parts[1].parse::<usize>() {
-            Ok(t_index) => Ok(VertexPositionTexture {
-                position: vertices[v_index - 1],
-                texture: textures[t_index - 1],
-            }),
-            Err(_) => Err("Error parsing vertex texture".to_owned()),
-        },
-        Err(_) => Err("Error parsing vertex texture".to_owned()),
+        )
+        .unwrap();
+
+        assert_eq!(names, vec!["a", "b"]);
+    }
+
+    const ELEMENTS: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+                            vt 0 0\nvt 1 0\nvt 0 1\n\
+                            vn 0 0 1\nvn 0 0 -1\n";
+
+    fn error(source: &str) -> ParseError {
+        MeshDescriptions::from_source(source, "test.obj", &MemoryResolver::new()).unwrap_err()
+    }
+
+    #[test]
+    fn face_elements_give_vertices_with_what_they_refer_to() {
+        let faces = [
+            ("f 1 2 3", (false, false)),
+            ("f 1/1 2/2 3/3", (true, false)),
+            ("f 1//2 2//2 3//2", (false, true)),
+            ("f 1/1/2 2/2/2 3/3/2", (true, true)),
+        ];
+
+        for &(face, (texture, normal)) in faces.iter() {
+            let meshes = parse(&format!("{}{}\n", ELEMENTS, face));
+            let vertices = &meshes.meshes[0].vertices;
+
+            assert_eq!(vertices.len(), 3, "{}", face);
+            assert_eq!(vertices[1].position(), Vec4::new(1.0, 0.0, 0.0, 1.0));
+            assert_eq!(vertices[2].texture().is_some(), texture, "{}", face);
+            assert_eq!(vertices[2].normal().is_some(), normal, "{}", face);
+            if texture {
+                assert_eq!(vertices[2].texture(), Some(Vec3::new(0.0, 1.0, 0.0)));
+            }
+            if normal {
+                assert_eq!(vertices[2].normal(), Some(Vec3::new(0.0, 0.0, -1.0)));
+            }
+        }
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_element() {
+        let absolute = parse(&format!("{}f 1/1/1 2/2/1 3/3/2\n", ELEMENTS));
+        let relative = parse(&format!("{}f -3/-3/-2 -2/-2/-2 -1/-1/-1\n", ELEMENTS));
+
+        assert_eq!(
+            format!("{:?}", relative.meshes[0].vertices),
+            format!("{:?}", absolute.meshes[0].vertices)
+        );
+    }
+
+    #[test]
+    fn negative_indices_only_see_elements_defined_before_the_face() {
+        let meshes = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 5 5 5\nf -4 -3 -1\n");
+        let vertices = &meshes.meshes[0].vertices;
+
+        // the second face shares its first two corners with the first
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(vertices[3].position(), Vec4::new(5.0, 5.0, 5.0, 1.0));
+        assert_eq!(meshes.meshes[0].indices.iter().collect::<Vec<usize>>(), vec![0, 1, 2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn corners_with_the_same_indices_share_a_vertex() {
+        let meshes = parse(&format!("{}f 1/1 2/2 3/3\nf 3/3 2/2 1/2\n", ELEMENTS));
+        assert_eq!(meshes.meshes[0].vertices.len(), 4);
+    }
+
+    #[test]
+    fn indices_out_of_range_are_errors() {
+        let cases = [
+            ("f 1 2 4", "4", "index 4 is out of range, 3 are defined"),
+            ("f 1 2 0", "0", "index 0 is out of range, 3 are defined"),
+            ("f 1 2 -4", "-4", "index -4 is out of range, 3 are defined"),
+            ("f 1/4 2/1 3/1", "1/4", "index 4 is out of range, 3 are defined"),
+            ("f 1//3 2//1 3//1", "1//3", "index 3 is out of range, 2 are defined"),
+        ];
+
+        for &(face, token, message) in cases.iter() {
+            let e = error(&format!("{}{}\n", ELEMENTS, face));
+            let column = face.rfind(token).unwrap() + 1;
+
+            assert!(match e.kind {
+                ParseErrorKind::IndexOutOfRange => true,
+                _ => false,
+            }, "{}", face);
+            assert_eq!(e.message, message);
+            assert_eq!(e.token, Some(token.to_owned()));
+            assert_eq!(e.location, Some(Location::new("test.obj", 9, column)), "{}", face);
+        }
+    }
+
+    #[test]
+    fn wrong_component_counts_point_at_the_values() {
+        let e = error("v 0 0 0\nvn  1\t2\n");
+        assert_eq!(e.token, Some("1\t2".to_owned()));
+        assert_eq!(e.location, Some(Location::new("test.obj", 2, 5)));
+
+        let e = error("v 0 0 0\n  vn\n");
+        assert_eq!(e.token, Some("vn".to_owned()));
+        assert_eq!(e.location, Some(Location::new("test.obj", 2, 3)));
+    }
+
+    #[test]
+    fn malformed_face_elements_are_errors() {
+        for face in &["f 1/1/1/1 2 3", "f 1/ 2 3", "f 1// 2 3", "f 1 x 3"] {
+            let e = error(&format!("{}{}\n", ELEMENTS, face));
+            assert!(match e.kind {
+                ParseErrorKind::MalformedStatement | ParseErrorKind::BadNumber => true,
+                _ => false,
+            }, "{}", face);
+        }
+    }
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    // a mesh with the given number of vertices, each used by at least one triangle
+    fn unique_vertices(count: usize) -> MeshDescriptions {
+        let mut source = String::new();
+        for i in 0..count {
+            source.push_str(&format!("v {} {} 0\n", i % 256, i / 256));
+        }
+        for i in (0..count).step_by(3) {
+            let corner = |offset: usize| (i + offset) % count + 1;
+            source.push_str(&format!("f {} {} {}\n", corner(0), corner(1), corner(2)));
+        }
+        parse(&source)
+    }
+
+    #[test]
+    fn indices_are_16_bit_while_every_vertex_fits() {
+        let mesh = &unique_vertices(65535).meshes[0];
+        assert_eq!(mesh.vertices.len(), 65535);
+        match mesh.indices {
+            Indices::U16(ref indices) => assert_eq!(indices.iter().max(), Some(&65534)),
+            _ => panic!("expected 16 bit indices"),
+        }
+
+        let mesh = &unique_vertices(65536).meshes[0];
+        assert_eq!(mesh.vertices.len(), 65536);
+        match mesh.indices {
+            Indices::U32(ref indices) => assert_eq!(indices.iter().max(), Some(&65535)),
+            _ => panic!("expected 32 bit indices"),
+        }
+    }
+
+    #[test]
+    fn indices_address_the_same_vertices_after_dedup() {
+        let source = format!("{}f 1 2 3\nf 1 3 4\nf 4 3 2 1\n", SQUARE);
+        let mesh = &parse(&source).meshes[0];
+        assert_eq!(mesh.vertices.len(), 4);
+
+        let corners = [1, 2, 3, 1, 3, 4, 4, 3, 2, 4, 2, 1];
+        let square = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        assert_eq!(mesh.indices.len(), corners.len());
+        for (index, &corner) in mesh.indices.iter().zip(corners.iter()) {
+            let position = mesh.vertices[index].position();
+            assert_eq!((position.x, position.y), square[corner - 1]);
+        }
+    }
+
+    #[test]
+    fn usemtl_splits_a_group_into_a_mesh_per_material() {
+        let meshes = parse(&format!(
+            "{}g wall\nusemtl brick\nf 1 2 3\nusemtl brick\nf 1 3 4\nusemtl glass\nf 1 2 4\n",
+            SQUARE
+        ));
+
+        let materials = meshes.meshes.iter().map(|m| &m.material[..]).collect::<Vec<&str>>();
+        assert_eq!(materials, vec!["brick", "glass"]);
+        assert!(meshes.meshes.iter().all(|m| m.name == "wall" && m.groups == vec!["wall"]));
+        assert_eq!(meshes.meshes[0].indices.len(), 6);
+        assert_eq!(meshes.meshes[1].indices.len(), 3);
+        assert_eq!(meshes.group("wall").len(), 2);
+    }
+
+    #[test]
+    fn objects_own_the_meshes_after_them() {
+        let meshes = parse(&format!(
+            "{}o house\ng wall\nf 1 2 3\no shed\nf 1 3 4\n",
+            SQUARE
+        ));
+
+        assert_eq!(meshes.object_names(), vec!["house", "shed"]);
+        assert_eq!(meshes.object("house")[0].groups, vec!["wall"]);
+        // a new object starts without any groups
+        assert!(meshes.object("shed")[0].groups.is_empty());
+    }
+
+    #[test]
+    fn a_face_can_be_in_several_groups() {
+        let meshes = parse(&format!("{}g wall outside\nf 1 2 3\n", SQUARE));
+        let mesh = &meshes.meshes[0];
+
+        assert_eq!(mesh.name, "wall outside");
+        assert_eq!(mesh.groups, vec!["wall", "outside"]);
+        assert_eq!(meshes.group("wall").len(), 1);
+        assert_eq!(meshes.group("outside").len(), 1);
+        assert!(meshes.group("wall outside").is_empty());
+    }
+
+    #[test]
+    fn smoothing_groups_are_kept_per_triangle() {
+        let meshes = parse(&format!(
+            "{}s 1\nf 1 2 3\ns off\nf 1 3 4\ns 2\nf 1 2 3 4\ns 0\nf 4 3 2\n",
+            SQUARE
+        ));
+        let mesh = &meshes.meshes[0];
+
+        // smoothing doesn't split the mesh, and the quad gives two triangles
+        assert_eq!(meshes.meshes.len(), 1);
+        assert_eq!(
+            mesh.smoothing_groups,
+            vec![Some(1), Some(0), Some(2), Some(2), Some(0)]
+        );
+        assert_eq!(mesh.smoothing_groups.len(), mesh.indices.len() / 3);
+    }
+
+    #[test]
+    fn faces_before_any_smoothing_group_have_none() {
+        let meshes = parse(&format!("{}f 1 2 3
+s off
+f 1 3 4
+", SQUARE));
+        assert_eq!(meshes.meshes[0].smoothing_groups, vec![None, Some(0)]);
+
+        let meshes = parse(&format!("{}f 1 2 3 4
+", SQUARE));
+        assert_eq!(meshes.meshes[0].smoothing_groups, vec![None, None]);
+    }
+
+    // describes everything a parse produced, used to compare the serial and parallel parsers
+    fn describe(parsed: Result<Parsed<MeshDescriptions>, ParseError>) -> String {
+        match parsed {
+            Ok(parsed) => {
+                let warnings = parsed.warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+                format!("{}\n{:?}", parsed.value.describe(), warnings)
+            }
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn assert_parallel_matches_serial(source: &str, mode: ParseMode) -> String {
+        let resolver = MemoryResolver::new()
+            .with_file("test.mtl", "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n");
+        let serial = describe(parse_with_mode(source, &resolver, mode));
+
+        for threads in 1..=4 {
+            let parallel = MeshDescriptions::from_source_parallel(
+                source, "test.obj", &resolver, mode, threads,
+            );
+            assert_eq!(describe(parallel), serial, "parsed on {} threads", threads);
+        }
+
+        serial
+    }
+
+    // groups of quads whose faces refer back to earlier groups, so that they do across chunks
+    fn groups(count: usize) -> String {
+        let mut source = String::from("mtllib test.mtl\n");
+        for group in 0..count {
+            let material = if group % 2 == 0 { "red" } else { "blue" };
+            source += &format!("g group{}\nusemtl {}\ns {}\n", group, material, group % 3);
+            for corner in 0..4 {
+                source += &format!("v {} {} 0\nvt 0.{} 0.5\nvn 0 0 1\n", group + corner % 2, corner / 2, corner);
+            }
+            source += "f -4/-4/-4 -3/-3/-3 -1/-1/-1 -2/-2/-2\nf 1 2 3\n\n# a comment\n";
+            source += "f -4//-1 -3//-2 -2//-3 -1//-4\n";
+        }
+        source
+    }
+
+    #[test]
+    fn parallel_parse_matches_serial_parse_of_a_model() {
+        let file = "content/Earth/earth.obj";
+        let source = fs::read_to_string(file).unwrap();
+        let resolver = FileResolver::for_file(file);
+        let serial = MeshDescriptions::from_reader_with_mode(source.as_bytes(), file, &resolver, ParseMode::Strict);
+        let serial = describe(serial);
+
+        for threads in 1..=4 {
+            let parallel = MeshDescriptions::from_file_parallel(file, ParseMode::Strict, threads);
+            assert_eq!(describe(parallel), serial, "parsed on {} threads", threads);
+        }
+    }
+
+    #[test]
+    fn parallel_parse_matches_serial_parse() {
+        let described = assert_parallel_matches_serial(&groups(20), ParseMode::Strict);
+        assert!(described.contains("group19"));
+        assert_parallel_matches_serial("", ParseMode::Strict);
+        assert_parallel_matches_serial("\n\n", ParseMode::Strict);
+    }
+
+    #[test]
+    fn parallel_parse_matches_serial_parse_with_crlf_line_endings() {
+        let source = groups(20).replace('\n', "\r\n");
+        assert_parallel_matches_serial(&source, ParseMode::Strict);
+
+        // a carriage return at the end of the last line is trimmed without a newline after it
+        assert_parallel_matches_serial(source.trim_end_matches('\n'), ParseMode::Strict);
+    }
+
+    #[test]
+    fn parallel_parse_matches_serial_parse_without_a_trailing_newline() {
+        let source = groups(20);
+        let source = source.trim_end();
+        assert!(source.ends_with("-1//-4"));
+        assert_parallel_matches_serial(source, ParseMode::Strict);
+    }
+
+    #[test]
+    fn parallel_parse_reports_the_first_error_in_a_later_chunk() {
+        let mut source = groups(20);
+        source += "f 1 2 999\n";
+        source += &groups(2);
+        source += "v 1 x 0\n";
+
+        let described = assert_parallel_matches_serial(&source, ParseMode::Strict);
+        assert!(described.contains("index 999 is out of range"), "{}", described);
+    }
+
+    #[test]
+    fn parallel_parse_keeps_lenient_warnings_in_order() {
+        let mut source = groups(10);
+        source += "curv 1 2\n";
+        source += &groups(10);
+        source += "surf 1 2\n";
+
+        let described = assert_parallel_matches_serial(&source, ParseMode::Lenient);
+        assert!(described.contains("curv") && described.contains("surf"), "{}", described);
     }
 }
@@ -1,12 +1,16 @@
 //! Represents a collection of types and functions for the rendering pipeline
 mod material;
 mod mesh;
+mod normals;
+mod tangents;
 mod vertex;
 
 //todo: Make into prelude
 //todo: Make into specific types
 pub use self::material::*;
 pub use self::mesh::*;
+pub use self::normals::*;
+pub use self::tangents::*;
 pub use self::vertex::*;
 
 /// Represents the default field of view
@@ -14,82 +18,99 @@ pub const FOV: f32 = 3.141592 / 3.0;
 
 use glium::{
     Program, Display,
-    vertex::VertexBuffer
+    index::{self, IndexBuffer, IndexBufferAny, PrimitiveType},
+    vertex::{BufferCreationError, VertexBuffer},
 };
 
 use std::{
     ffi::OsStr,
-    fs::{read_to_string, File},
     path::Path,
 };
 
-use io::to_cursor;
+use assets::AssetError;
+use io::Vfs;
 
 // todo: vertex and fragment shader source should be passed in - not file handles 
-// todo: Shouldn't return Program, Should return option
 // todo: Example missing
 /// Creates a shader from a vertex and fragment shader program
 /// 
 /// # Arguments
+/// `vfs` - The file system to load the shader programs from
 /// `vertex` - The vertex shader program file
 /// `fragment` - The fragment shader program file
 /// `display` - The glium, display & window
 /// 
-/// returns an OpenGL program
-/// 
-/// # Panics
-/// * When there is no file found for the vertex shader
-/// * When there is no file found for the fragment shader
-/// * When the program doesn't compile
-pub fn create_shader(vertex: &str, fragment: &str, display: &glium::Display) -> Program {
-    let vertex_shader_src =
-        read_to_string(&vertex).expect(format!("Failed to find {}", &vertex).as_str());
-    let fragment_shader_src =
-        read_to_string(&fragment).expect(format!("Failed to find {}", &fragment).as_str());
-
-    glium::Program::from_source(
+/// returns an OpenGL program, or an error when either file can't be read
+/// or the program doesn't compile
+pub fn create_shader(
+    vfs: &Vfs,
+    vertex: &str,
+    fragment: &str,
+    display: &glium::Display,
+) -> Result<Program, AssetError> {
+    let vertex_shader_src = vfs.read_to_string(&vertex)?;
+    let fragment_shader_src = vfs.read_to_string(&fragment)?;
+
+    Ok(glium::Program::from_source(
         display,
         &vertex_shader_src[..],
         &fragment_shader_src[..],
         None,
-    )
-    .expect("Failed to compile shader")
+    )?)
 }
 
 fn get_image_format(path: &str) -> Option<image::ImageFormat> {
     let extension = Path::new(&path)
         .extension()
-        .and_then(OsStr::to_str)
-        .unwrap();
+        .and_then(OsStr::to_str)?;
 
-    match extension {
+    match &extension.to_lowercase()[..] {
         "png" => Some(image::PNG),
         "jpeg" | "jpg" => Some(image::JPEG),
         _ => None,
     }
 }
 
-// todo: Shouldn't load from file, should pass in file
-// todo: Make sure unexpected formats are handled better
 // todo: Missing examples
-/// Loads an OpenGL texture from a file as raw image data
+/// Decodes the contents of an image file as raw image data
 /// # Arguments
-/// `path` - the path to the texture file
-/// 
-/// # Panics
-/// * When the file could not be found
-/// * When the file is in an unsupported format
-pub fn load_texture<'a>(path: &str) -> glium::texture::RawImage2d<'a, u8> {
-    let file = File::open(&path).expect(format!("Could not find file {}", &path).as_str());
-
-    let image_format = get_image_format(&path);
-
-    let image = image::load(to_cursor(file), image_format.expect("unexpected extension"))
-        .unwrap()
-        .to_rgba();
+/// `contents` - the contents of the image file
+/// `path` - the path of the image file, its extension is used to pick the image format
+///
+/// # Remarks
+///
+/// When the extension isn't recognised the format is guessed from the contents.
+pub fn decode_texture<'a>(
+    contents: &[u8],
+    path: &str,
+) -> image::ImageResult<glium::texture::RawImage2d<'a, u8>> {
+    let image = match get_image_format(&path) {
+        Some(format) => image::load_from_memory_with_format(contents, format)?,
+        None => image::load_from_memory(contents)?,
+    }
+    .to_rgba();
 
     let image_dimensions = image.dimensions();
-    glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), image_dimensions)
+    Ok(glium::texture::RawImage2d::from_raw_rgba_reversed(
+        &image.into_raw(),
+        image_dimensions,
+    ))
+}
+
+// todo: Missing examples
+/// Loads an OpenGL texture from a file as raw image data
+/// # Arguments
+/// `vfs` - the file system to load the texture from
+/// `path` - the path to the texture file
+///
+/// returns the image, or an error when the file can't be read or decoded
+pub fn load_texture<'a>(
+    vfs: &Vfs,
+    path: &str,
+) -> Result<glium::texture::RawImage2d<'a, u8>, AssetError> {
+    let contents = vfs.read(&path)?;
+
+    Ok(decode_texture(&contents, &path)?)
 }
 
 // todo: Missing examples
@@ -120,93 +141,110 @@ impl<'a> TextureConvert for glium::texture::RawImage2d<'a, u8> {
 }
 
 implement_vertex!(VertexPositionNormalTexture, position, normal, texture);
+implement_vertex!(VertexPositionNormalTangentTexture, position, normal, tangent, texture);
+
+/// Uploads the vertices of a mesh to a vertex buffer
+///
+/// # Arguments
+/// `display` - The glium display
+/// `vertices` - the vertices, see [`Vertex::to_position_normal_texture`]
+pub fn create_vertex_buffer(
+    display: &Display,
+    vertices: &[Vertex],
+) -> Result<VertexBuffer<VertexPositionNormalTexture>, BufferCreationError> {
+    let vertices = vertices
+        .iter()
+        .map(|v| v.to_position_normal_texture())
+        .collect::<Vec<_>>();
+
+    VertexBuffer::new(display, &vertices)
+}
+
+/// Uploads the vertices of a mesh, along with their tangents, to a vertex buffer
+///
+/// # Arguments
+/// `display` - The glium display
+/// `vertices` - the vertices, see [`Vertex::to_position_normal_tangent_texture`]
+/// and [`generate_tangents`]
+pub fn create_tangent_vertex_buffer(
+    display: &Display,
+    vertices: &[Vertex],
+) -> Result<VertexBuffer<VertexPositionNormalTangentTexture>, BufferCreationError> {
+    let vertices = vertices
+        .iter()
+        .map(|v| v.to_position_normal_tangent_texture())
+        .collect::<Vec<_>>();
+
+    VertexBuffer::new(display, &vertices)
+}
+
+/// Uploads the indices of a mesh to an index buffer of triangles,
+/// keeping the index type that was chosen for the mesh
+///
+/// # Arguments
+/// `display` - The glium display
+/// `indices` - the indices
+pub fn create_index_buffer(
+    display: &Display,
+    indices: &Indices,
+) -> Result<IndexBufferAny, index::BufferCreationError> {
+    Ok(match indices {
+        Indices::U16(i) => IndexBuffer::new(display, PrimitiveType::TrianglesList, &i[..])?.into(),
+        Indices::U32(i) => IndexBuffer::new(display, PrimitiveType::TrianglesList, &i[..])?.into(),
+    })
+}
 
 /// Creates a 2d square to render a billboarded texture on
 /// 
 /// # Arguments
 /// `display` - The glium display
 /// 
+/// # Remarks
+///
+/// The square faces down the negative z axis and its vertices are in triangle strip order.
+/// Its tangents are generated the same way as a loaded mesh, see [`generate_tangents`].
+///
 /// # Panics
 /// when the buffer is not created
-pub fn create_billboard(display: &Display) -> VertexBuffer<VertexPositionNormalTexture> {
+pub fn create_billboard(display: &Display) -> VertexBuffer<VertexPositionNormalTangentTexture> {
     use ::math::{Vec3,Vec4};
 
-    VertexBuffer::new(
-        display,
-        &[
-            VertexPositionNormalTexture {
-                position: Vec4 {
-                    x: -1.0,
-                    y: 1.0,
-                    z: 0.0,
-                    w: 1.0,
-                },
-                normal: Vec3 {
-                    x: 0.0,
-                    y: 0.0,
-                    z: -1.0,
-                },
-                texture: Vec3 {
-                    x: 0.0,
-                    y: 1.0,
-                    z: 1.0,
-                },
-            },
-            VertexPositionNormalTexture {
-                position: Vec4 {
-                    x: 1.0,
-                    y: 1.0,
-                    z: 0.0,
-                    w: 1.0,
-                },
-                normal: Vec3 {
-                    x: 0.0,
-                    y: 0.0,
-                    z: -1.0,
-                },
-                texture: Vec3 {
-                    x: 1.0,
-                    y: 1.0,
-                    z: 1.0,
-                },
+    let corner = |x: f32, y: f32| {
+        Vertex::PositionNormalTexture(VertexPositionNormalTexture {
+            position: Vec4 {
+                x: x,
+                y: y,
+                z: 0.0,
+                w: 1.0,
             },
-            VertexPositionNormalTexture {
-                position: Vec4 {
-                    x: -1.0,
-                    y: -1.0,
-                    z: 0.0,
-                    w: 1.0,
-                },
-                normal: Vec3 {
-                    x: 0.0,
-                    y: 0.0,
-                    z: -1.0,
-                },
-                texture: Vec3 {
-                    x: 0.0,
-                    y: 0.0,
-                    z: 1.0,
-                },
+            normal: Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: -1.0,
             },
-            VertexPositionNormalTexture {
-                position: Vec4 {
-                    x: 1.0,
-                    y: -1.0,
-                    z: 0.0,
-                    w: 1.0,
-                },
-                normal: Vec3 {
-                    x: 0.0,
-                    y: 0.0,
-                    z: -1.0,
-                },
-                texture: Vec3 {
-                    x: 1.0,
-                    y: 0.0,
-                    z: 1.0,
-                },
+            texture: Vec3 {
+                x: (x + 1.0) / 2.0,
+                y: (y + 1.0) / 2.0,
+                z: 1.0,
             },
-        ],
-    )
-    .expect("Failed to create billboard")
+        })
+    };
+
+    // the two triangles of the strip, the vertices keep their order as none of them are split
+    let mut square = MeshDescription::new(
+        String::from("billboard"),
+        String::new(),
+        vec![corner(-1.0, 1.0), corner(1.0, 1.0), corner(-1.0, -1.0), corner(1.0, -1.0)],
+        vec![0, 1, 2, 2, 1, 3],
+        FaceReport::default(),
+    );
+    square.generate_tangents();
+
+    let vertices = square
+        .vertices
+        .iter()
+        .map(|v| v.to_position_normal_tangent_texture())
+        .collect::<Vec<_>>();
+
+    VertexBuffer::new(display, &vertices).expect("Failed to create billboard")
 }
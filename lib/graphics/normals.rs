@@ -0,0 +1,442 @@
+//! A module for generating vertex normals for meshes that don't have them,
+//! such as an obj file without any `vn` statements.
+use graphics::{Indices, MeshDescription};
+use math::{Vec3, Vector};
+use std::collections::HashMap;
+
+/// How normals are generated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NormalGeneration {
+    /// Each triangle uses its own face normal, giving a faceted look
+    Flat,
+    /// Each corner uses the weighted average of the face normals of the triangles around it
+    Smooth {
+        /// Triangles that meet at a greater angle than this, in radians, are not averaged
+        /// so the edge between them stays sharp
+        crease_angle: f32,
+        /// How much each triangle contributes to the average
+        weighting: NormalWeighting,
+    },
+}
+
+/// How much each triangle around a corner contributes to a smooth normal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// Larger triangles contribute more
+    Area,
+    /// Triangles contribute by the angle of their corner, so long thin triangles
+    /// don't pull the normal towards them
+    Angle,
+}
+
+impl NormalGeneration {
+    /// Smooth normals with a crease angle of 60 degrees weighted by angle
+    pub fn smooth() -> NormalGeneration {
+        NormalGeneration::Smooth {
+            crease_angle: 60.0f32.to_radians(),
+            weighting: NormalWeighting::Angle,
+        }
+    }
+}
+
+/// Generates normals for the vertices of a mesh that don't have one
+///
+/// # Arguments
+/// `mesh` - the mesh, its vertices and indices are rebuilt
+/// `generation` - how the normals are generated
+///
+/// # Remarks
+///
+/// Vertices that already have a normal are unchanged. A vertex is split when the triangles
+/// that share it need different normals, such as along a crease.
+///
+/// Smooth normals are only averaged between triangles in the same smoothing group, and
+/// triangles with smoothing turned off use their face normal. A triangle without any area has
+/// no face normal of its own so it uses the average of the triangles that touch its corners,
+/// or points up the y axis when nothing does. When none of the faces of a mesh had an `s`
+/// statement before them every triangle is treated as being in the same group, so only the
+/// crease angle decides which edges are sharp. Otherwise faces before the first `s` statement
+/// have smoothing turned off.
+pub fn generate_normals(mesh: &mut MeshDescription, generation: NormalGeneration) {
+    if mesh.vertices.iter().all(|v| v.normal().is_some()) {
+        return;
+    }
+
+    let indices = mesh.indices.iter().collect::<Vec<usize>>();
+    let triangles = indices.len() / 3;
+    let positions = indices
+        .iter()
+        .map(|&i| mesh.vertices[i].position().xyz())
+        .collect::<Vec<Vec3>>();
+
+    // the length of a face normal is twice the area of its triangle
+    let face_normals = (0..triangles)
+        .map(|t| {
+            let (a, b, c) = (positions[t * 3], positions[t * 3 + 1], positions[t * 3 + 2]);
+            (b - a).cross(&(c - a))
+        })
+        .collect::<Vec<Vec3>>();
+
+    let around = corners_by_position(&positions);
+    let units = unit_normals(&face_normals, &positions, &around);
+
+    let corner_normals = match generation {
+        NormalGeneration::Flat => (0..triangles * 3)
+            .map(|corner| units[corner / 3])
+            .collect::<Vec<Vec3>>(),
+        NormalGeneration::Smooth {
+            crease_angle,
+            weighting,
+        } => {
+            let smoothing = smoothing_groups(mesh, triangles);
+            smooth_normals(
+                &positions,
+                &face_normals,
+                &units,
+                &around,
+                &smoothing,
+                crease_angle.cos(),
+                weighting,
+            )
+        }
+    };
+
+    // rebuild the vertices, sharing a vertex between corners that end up with the same normal
+    let mut vertices = Vec::with_capacity(mesh.vertices.len());
+    let mut rebuilt = Vec::with_capacity(indices.len());
+    let mut shared = HashMap::new();
+
+    for (corner, &index) in indices.iter().enumerate() {
+        let vertex = mesh.vertices[index];
+        let normal = match vertex.normal() {
+            Some(_) => None,
+            None => Some(corner_normals[corner]),
+        };
+        let key = (index, normal.map(|n| vector_key(&n)));
+
+        let next = vertices.len() as u32;
+        let shared_index = *shared.entry(key).or_insert(next);
+        if shared_index == next {
+            vertices.push(match normal {
+                Some(normal) => vertex.with_normal(normal),
+                None => vertex,
+            });
+        }
+        rebuilt.push(shared_index);
+    }
+
+    mesh.indices = Indices::new(rebuilt, vertices.len());
+    mesh.vertices = vertices;
+}
+
+// the smoothing group of each triangle, or every triangle in one group when none were given
+fn smoothing_groups(mesh: &MeshDescription, triangles: usize) -> Vec<u32> {
+    if mesh.smoothing_groups.len() == triangles && mesh.smoothing_groups.iter().any(|g| g.is_some()) {
+        mesh.smoothing_groups.iter().map(|g| g.unwrap_or(0)).collect()
+    } else {
+        vec![1; triangles]
+    }
+}
+
+// the corners at each position, corners are matched by position so seams in the texture
+// coordinates stay smooth
+fn corners_by_position(positions: &[Vec3]) -> HashMap<[u32; 3], Vec<usize>> {
+    let mut around = HashMap::new();
+    for (corner, p) in positions.iter().enumerate() {
+        around
+            .entry(vector_key(p))
+            .or_insert_with(Vec::new)
+            .push(corner);
+    }
+    around
+}
+
+// the exact bits of a vector, for use as a key
+fn vector_key(p: &Vec3) -> [u32; 3] {
+    [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]
+}
+
+// the unit normal of each triangle, borrowed from its neighbours when it has no area
+fn unit_normals(
+    face_normals: &[Vec3],
+    positions: &[Vec3],
+    around: &HashMap<[u32; 3], Vec<usize>>,
+) -> Vec<Vec3> {
+    let degenerate = |t: usize| face_normals[t].length_squared() == 0.0;
+
+    (0..face_normals.len())
+        .map(|t| {
+            if !degenerate(t) {
+                return face_normals[t].normalize();
+            }
+
+            let sum = positions[t * 3..t * 3 + 3]
+                .iter()
+                .flat_map(|p| around[&vector_key(p)].iter())
+                .map(|&corner| corner / 3)
+                .filter(|&other| !degenerate(other))
+                .fold(Vec3::zero(), |acc, other| acc + face_normals[other].normalize());
+
+            if sum.length_squared() > 0.0 {
+                sum.normalize()
+            } else {
+                Vec3 {
+                    x: 0.0,
+                    y: 1.0,
+                    z: 0.0,
+                }
+            }
+        })
+        .collect()
+}
+
+fn smooth_normals(
+    positions: &[Vec3],
+    face_normals: &[Vec3],
+    units: &[Vec3],
+    around: &HashMap<[u32; 3], Vec<usize>>,
+    smoothing: &[u32],
+    min_cos: f32,
+    weighting: NormalWeighting,
+) -> Vec<Vec3> {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(corner, p)| {
+            let triangle = corner / 3;
+            if smoothing[triangle] == 0 {
+                return units[triangle];
+            }
+
+            let sum = around[&vector_key(p)]
+                .iter()
+                .filter(|&&other| {
+                    let t = other / 3;
+                    t == triangle
+                        || (smoothing[t] == smoothing[triangle]
+                            && units[t].dot(&units[triangle]) >= min_cos)
+                })
+                .fold(Vec3::zero(), |acc, &other| {
+                    let t = other / 3;
+                    // a triangle without any area only borrowed its normal, so it adds nothing
+                    if face_normals[t].length_squared() == 0.0 {
+                        return acc;
+                    }
+                    let weight = match weighting {
+                        NormalWeighting::Area => face_normals[t].length() * 0.5,
+                        NormalWeighting::Angle => corner_angle(positions, other),
+                    };
+                    acc + units[t] * weight
+                });
+
+            if sum.length_squared() > 0.0 {
+                sum.normalize()
+            } else {
+                units[triangle]
+            }
+        })
+        .collect()
+}
+
+// the angle of a triangle at one of its corners in radians
+fn corner_angle(positions: &[Vec3], corner: usize) -> f32 {
+    let first = corner - corner % 3;
+    let p = positions[corner];
+    let a = positions[first + (corner + 1) % 3] - p;
+    let b = positions[first + (corner + 2) % 3] - p;
+
+    let lengths = a.length() * b.length();
+    if lengths == 0.0 {
+        0.0
+    } else {
+        (a.dot(&b) / lengths).max(-1.0).min(1.0).acos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::MeshDescriptions;
+    use parser::{FromReader, MemoryResolver};
+
+    const CUBE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1\n";
+    const CUBE_FACES: &str =
+        "f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 4 8 7 3\nf 1 5 8 4\nf 2 3 7 6\n";
+
+    fn parse(source: &str) -> MeshDescription {
+        let resolver = MemoryResolver::new();
+        let mut meshes = MeshDescriptions::from_source(source, "test.obj", &resolver).unwrap();
+        meshes.meshes.remove(0)
+    }
+
+    fn smooth(degrees: f32) -> NormalGeneration {
+        NormalGeneration::Smooth {
+            crease_angle: degrees.to_radians(),
+            weighting: NormalWeighting::Angle,
+        }
+    }
+
+    fn normals(mesh: &MeshDescription) -> Vec<Vec3> {
+        mesh.vertices.iter().map(|v| v.normal().unwrap()).collect()
+    }
+
+    fn is_axis(n: &Vec3) -> bool {
+        let sum = n.x.abs() + n.y.abs() + n.z.abs();
+        (sum - 1.0).abs() < 1e-5 && (n.length() - 1.0).abs() < 1e-5
+    }
+
+    fn is_diagonal(n: &Vec3) -> bool {
+        let third = 1.0 / 3.0f32.sqrt();
+        [n.x, n.y, n.z].iter().all(|c| (c.abs() - third).abs() < 1e-5)
+    }
+
+    #[test]
+    fn flat_normals_split_every_corner_of_a_cube() {
+        let mut mesh = parse(&format!("{}{}", CUBE, CUBE_FACES));
+        generate_normals(&mut mesh, NormalGeneration::Flat);
+
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+        assert!(normals(&mesh).iter().all(is_axis));
+    }
+
+    #[test]
+    fn flat_normals_face_outwards() {
+        let mut mesh = parse(&format!("{}{}", CUBE, CUBE_FACES));
+        generate_normals(&mut mesh, NormalGeneration::Flat);
+
+        let centre = Vec3::from_val(0.5);
+        for vertex in &mesh.vertices {
+            let outwards = vertex.position().xyz() - centre;
+            assert!(vertex.normal().unwrap().dot(&outwards) > 0.0);
+        }
+    }
+
+    #[test]
+    fn edges_sharper_than_the_crease_angle_stay_split() {
+        let mut mesh = parse(&format!("{}{}", CUBE, CUBE_FACES));
+        generate_normals(&mut mesh, smooth(60.0));
+
+        assert_eq!(mesh.vertices.len(), 24);
+        assert!(normals(&mesh).iter().all(is_axis));
+    }
+
+    #[test]
+    fn edges_within_the_crease_angle_are_smoothed() {
+        for &weighting in &[NormalWeighting::Angle, NormalWeighting::Area] {
+            let mut mesh = parse(&format!("{}{}", CUBE, CUBE_FACES));
+            let generation = NormalGeneration::Smooth {
+                crease_angle: 100.0f32.to_radians(),
+                weighting: weighting,
+            };
+            generate_normals(&mut mesh, generation);
+
+            assert_eq!(mesh.vertices.len(), 8);
+            for n in normals(&mesh) {
+                assert!((n.length() - 1.0).abs() < 1e-5);
+            }
+        }
+
+        // weighting by angle isn't pulled around by how the quads were split
+        let mut mesh = parse(&format!("{}{}", CUBE, CUBE_FACES));
+        generate_normals(&mut mesh, smooth(100.0));
+        assert!(normals(&mesh).iter().all(is_diagonal));
+    }
+
+    #[test]
+    fn smoothing_groups_keep_their_edges_sharp() {
+        // the top is in a different group, so only its corners are split
+        let mut mesh = parse(&format!(
+            "{}s 2\nf 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 1 5 8 4\nf 2 3 7 6\ns 1\nf 4 8 7 3\n",
+            CUBE
+        ));
+        generate_normals(&mut mesh, smooth(180.0));
+
+        assert_eq!(mesh.vertices.len(), 12);
+        let up = normals(&mesh)
+            .iter()
+            .filter(|n| (n.y - 1.0).abs() < 1e-5)
+            .count();
+        assert_eq!(up, 4);
+    }
+
+    #[test]
+    fn smoothing_off_uses_face_normals() {
+        let mut mesh = parse(&format!(
+            "{}s off\nf 1 4 3 2\ns 1\nf 5 6 7 8\nf 1 2 6 5\nf 4 8 7 3\nf 1 5 8 4\nf 2 3 7 6\n",
+            CUBE
+        ));
+        generate_normals(&mut mesh, smooth(180.0));
+
+        let back = normals(&mesh)
+            .iter()
+            .filter(|n| (n.z + 1.0).abs() < 1e-5)
+            .count();
+        assert_eq!(back, 4);
+        assert_eq!(mesh.vertices.len(), 12);
+    }
+
+    #[test]
+    fn smoothing_off_for_every_face_keeps_the_mesh_flat() {
+        let mut mesh = parse(&format!("{}s off\n{}", CUBE, CUBE_FACES));
+        generate_normals(&mut mesh, smooth(180.0));
+
+        assert_eq!(mesh.vertices.len(), 24);
+        assert!(normals(&mesh).iter().all(is_axis));
+
+        // `s 0` is the same as `s off`
+        let mut mesh = parse(&format!("{}s 0\n{}", CUBE, CUBE_FACES));
+        generate_normals(&mut mesh, smooth(180.0));
+        assert!(normals(&mesh).iter().all(is_axis));
+    }
+
+    #[test]
+    fn faces_without_a_smoothing_group_are_smoothed_together() {
+        let mut mesh = parse(&format!("{}{}", CUBE, CUBE_FACES));
+        generate_normals(&mut mesh, smooth(180.0));
+
+        assert_eq!(mesh.vertices.len(), 8);
+        assert!(normals(&mesh).iter().all(is_diagonal));
+    }
+
+    #[test]
+    fn faces_before_the_first_smoothing_group_are_flat() {
+        let mut mesh = parse(&format!(
+            "{}f 1 4 3 2\ns 1\nf 5 6 7 8\nf 1 2 6 5\nf 4 8 7 3\nf 1 5 8 4\nf 2 3 7 6\n",
+            CUBE
+        ));
+        generate_normals(&mut mesh, smooth(180.0));
+
+        let back = normals(&mesh)
+            .iter()
+            .filter(|n| (n.z + 1.0).abs() < 1e-5)
+            .count();
+        assert_eq!(back, 4);
+    }
+
+    #[test]
+    fn triangles_without_area_borrow_a_normal() {
+        // the second triangle is a line along the edge of the first
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 2 0 0\nf 1 2 3\nf 1 2 4\n";
+        for &generation in &[NormalGeneration::Flat, smooth(60.0)] {
+            let mut mesh = parse(source);
+            generate_normals(&mut mesh, generation);
+
+            for n in normals(&mesh) {
+                assert!((n.z - 1.0).abs() < 1e-5, "{:?}", n);
+            }
+        }
+
+        let mut mesh = parse("v 0 0 0\nv 1 0 0\nv 2 0 0\nf 1 2 3\n");
+        generate_normals(&mut mesh, NormalGeneration::Flat);
+        assert!(normals(&mesh).iter().all(|n| (n.y - 1.0).abs() < 1e-5));
+    }
+
+    #[test]
+    fn existing_normals_are_kept() {
+        let mut mesh = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 -1\nf 1//1 2//1 3//1\n");
+        generate_normals(&mut mesh, NormalGeneration::Flat);
+
+        assert!(normals(&mesh).iter().all(|n| n.z == -1.0));
+    }
+}
@@ -0,0 +1,266 @@
+//! A module for generating the vertex tangents used to apply normal maps.
+//! The tangents follow the conventions of MikkTSpace, which most tools bake normal maps with,
+//! so a shader that builds the bitangent as `tangent.w * cross(normal, tangent.xyz)` shades
+//! those maps as they were baked.
+use graphics::{Indices, MeshDescription};
+use math::{Vec3, Vec4, Vector};
+use std::collections::HashMap;
+
+/// Generates a tangent for every vertex of a mesh that has a normal and a texture coordinate
+///
+/// # Arguments
+/// `mesh` - the mesh, its vertices and indices are rebuilt
+///
+/// # Remarks
+///
+/// Each triangle's tangent points along its increasing `u` texture coordinate. The tangents
+/// of the triangles around a vertex are flattened onto the plane of the vertex normal and
+/// averaged, weighted by the angle of each triangle at the vertex.
+///
+/// A vertex is split where the texture is mirrored, so that each side gets the sign of its own
+/// bitangent. A vertex whose triangles have no area in texture space gets a tangent that is
+/// perpendicular to its normal. Vertices without a normal or a texture coordinate are unchanged,
+/// see [`generate_normals`] for the normals.
+pub fn generate_tangents(mesh: &mut MeshDescription) {
+    let indices = mesh.indices.iter().collect::<Vec<usize>>();
+    let triangles = indices.len() / 3;
+    let faces = (0..triangles)
+        .map(|t| face_tangent(mesh, &indices[t * 3..t * 3 + 3]))
+        .collect::<Vec<Option<(Vec3, bool)>>>();
+
+    // the sum of the tangents around each vertex, kept apart by the sign of the bitangent
+    let mut sums = HashMap::new();
+    for (corner, &index) in indices.iter().enumerate() {
+        let (tangent, preserving) = match faces[corner / 3] {
+            Some(face) => face,
+            None => continue,
+        };
+        let normal = match mesh.vertices[index].normal() {
+            Some(normal) => normal.normalize(),
+            None => continue,
+        };
+
+        let weight = corner_angle(mesh, &indices, corner, &normal);
+        let sum = sums.entry((index, preserving)).or_insert_with(Vec3::zero);
+        *sum += project(&tangent, &normal).normalize() * weight;
+    }
+
+    let mut vertices = Vec::with_capacity(mesh.vertices.len());
+    let mut rebuilt = Vec::with_capacity(indices.len());
+    let mut shared = HashMap::new();
+
+    for (corner, &index) in indices.iter().enumerate() {
+        let vertex = mesh.vertices[index];
+        let preserving = faces[corner / 3].map_or(true, |(_, preserving)| preserving);
+
+        let next = vertices.len() as u32;
+        let shared_index = *shared.entry((index, preserving)).or_insert(next);
+        if shared_index == next {
+            let tangent = vertex.normal().map(|normal| {
+                let sum = sums.get(&(index, preserving)).cloned().unwrap_or_else(Vec3::zero);
+                let tangent = if sum.length_squared() > 0.0 {
+                    sum.normalize()
+                } else {
+                    perpendicular(&normal.normalize())
+                };
+                let sign = if preserving { 1.0 } else { -1.0 };
+                Vec4 {
+                    x: tangent.x,
+                    y: tangent.y,
+                    z: tangent.z,
+                    w: sign,
+                }
+            });
+
+            vertices.push(
+                tangent
+                    .and_then(|tangent| vertex.with_tangent(tangent))
+                    .unwrap_or(vertex),
+            );
+        }
+        rebuilt.push(shared_index);
+    }
+
+    mesh.indices = Indices::new(rebuilt, vertices.len());
+    mesh.vertices = vertices;
+}
+
+// the unit tangent of a triangle along increasing u and whether its texture keeps the winding
+// of the triangle, or none when a corner has no texture coordinate or the texture has no area
+fn face_tangent(mesh: &MeshDescription, corners: &[usize]) -> Option<(Vec3, bool)> {
+    let vertex = |i: usize| {
+        let v = mesh.vertices[corners[i]];
+        v.texture().map(|t| (v.position().xyz(), t))
+    };
+    let ((p0, t0), (p1, t1), (p2, t2)) = (vertex(0)?, vertex(1)?, vertex(2)?);
+
+    let (d1, d2) = (p1 - p0, p2 - p0);
+    let (t21, t31) = (t1 - t0, t2 - t0);
+    let area = t21.x * t31.y - t21.y * t31.x;
+    if area == 0.0 {
+        return None;
+    }
+
+    let tangent = d1 * t31.y - d2 * t21.y;
+    if tangent.length_squared() == 0.0 {
+        return None;
+    }
+
+    let preserving = area > 0.0;
+    let sign = if preserving { 1.0 } else { -1.0 };
+    Some((tangent.normalize() * sign, preserving))
+}
+
+// the angle of a triangle at one of its corners, measured on the plane of the vertex normal
+fn corner_angle(mesh: &MeshDescription, indices: &[usize], corner: usize, normal: &Vec3) -> f32 {
+    let first = corner - corner % 3;
+    let position = |c: usize| mesh.vertices[indices[c]].position().xyz();
+    let p = position(corner);
+    let a = project(&(position(first + (corner + 1) % 3) - p), normal);
+    let b = project(&(position(first + (corner + 2) % 3) - p), normal);
+
+    let lengths = a.length() * b.length();
+    if lengths == 0.0 {
+        0.0
+    } else {
+        (a.dot(&b) / lengths).max(-1.0).min(1.0).acos()
+    }
+}
+
+// removes the part of `v` along the unit vector `normal`
+fn project(v: &Vec3, normal: &Vec3) -> Vec3 {
+    *v - *normal * normal.dot(v)
+}
+
+// any unit vector perpendicular to the unit vector `normal`
+fn perpendicular(normal: &Vec3) -> Vec3 {
+    let axis = if normal.x.abs() < 0.9 {
+        Vec3 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    } else {
+        Vec3 {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        }
+    };
+
+    project(&axis, normal).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::{MeshDescriptions, Vertex};
+    use parser::{FromReader, MemoryResolver};
+
+    fn parse(source: &str) -> MeshDescription {
+        let resolver = MemoryResolver::new();
+        let mut meshes = MeshDescriptions::from_source(source, "test.obj", &resolver).unwrap();
+        meshes.meshes.remove(0)
+    }
+
+    fn bitangent(vertex: &Vertex) -> Vec3 {
+        let tangent = vertex.tangent().unwrap();
+        vertex.normal().unwrap().cross(&tangent.xyz()) * tangent.w
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    const X: Vec3 = Vec3 { x: 1.0, y: 0.0, z: 0.0 };
+    const Y: Vec3 = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+
+    #[test]
+    fn tangent_follows_u_and_bitangent_follows_v() {
+        let mut mesh = parse(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+             f 1/1/1 2/2/1 3/3/1 4/4/1\n",
+        );
+        generate_tangents(&mut mesh);
+
+        assert_eq!(mesh.vertices.len(), 4);
+        for vertex in &mesh.vertices {
+            let tangent = vertex.tangent().unwrap();
+            assert_close(tangent.xyz(), X);
+            assert_eq!(tangent.w, 1.0);
+            assert_close(bitangent(vertex), Y);
+        }
+    }
+
+    #[test]
+    fn mirrored_texture_flips_the_sign() {
+        // the right quad mirrors the texture of the left one along their shared edge
+        let mut mesh = parse(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+             f 1/1/1 2/2/1 3/3/1 4/4/1\nf 2/2/1 5/1/1 6/4/1 3/3/1\n",
+        );
+        generate_tangents(&mut mesh);
+
+        // the shared edge is split so each side has its own sign
+        assert_eq!(mesh.vertices.len(), 8);
+        for vertex in &mesh.vertices {
+            let tangent = vertex.tangent().unwrap();
+            let right = vertex.position().x > 1.0
+                || (vertex.position().x == 1.0 && tangent.w < 0.0);
+            if right {
+                assert_close(tangent.xyz(), X * -1.0);
+                assert_eq!(tangent.w, -1.0);
+            } else {
+                assert_close(tangent.xyz(), X);
+                assert_eq!(tangent.w, 1.0);
+            }
+            assert_close(bitangent(vertex), Y);
+        }
+    }
+
+    #[test]
+    fn billboard_facing_negative_z() {
+        let mut mesh = parse(
+            "v -1 1 0\nv 1 1 0\nv -1 -1 0\nv 1 -1 0\n\
+             vt 0 1\nvt 1 1\nvt 0 0\nvt 1 0\nvn 0 0 -1\n\
+             f 1/1/1 2/2/1 3/3/1\nf 3/3/1 2/2/1 4/4/1\n",
+        );
+        generate_tangents(&mut mesh);
+
+        for vertex in &mesh.vertices {
+            let tangent = vertex.tangent().unwrap();
+            assert_close(tangent.xyz(), X);
+            assert_eq!(tangent.w, -1.0);
+            assert_close(bitangent(vertex), Y);
+        }
+    }
+
+    #[test]
+    fn vertices_without_texture_coordinates_are_unchanged() {
+        let mut mesh = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n");
+        generate_tangents(&mut mesh);
+
+        assert!(mesh.vertices.iter().all(|v| v.tangent().is_none()));
+    }
+
+    #[test]
+    fn tangents_are_perpendicular_to_normals() {
+        // a bent strip where the face tangents aren't in the plane of the shared normals
+        let mut mesh = parse(
+            "v 0 0 0\nv 1 0 0.5\nv 1 1 0.5\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+             vt 0 0\nvt 0.5 0\nvt 0.5 1\nvt 0 1\nvt 1 0\nvt 1 1\n\
+             vn 0 0 1\nvn 0.2 0 1\n\
+             f 1/1/1 2/2/2 3/3/2 4/4/1\nf 2/2/2 5/5/1 6/6/1 3/3/2\n",
+        );
+        generate_tangents(&mut mesh);
+
+        for vertex in &mesh.vertices {
+            let normal = vertex.normal().unwrap().normalize();
+            let tangent = vertex.tangent().unwrap().xyz();
+            assert!(normal.dot(&tangent).abs() < 1e-5);
+            assert!((tangent.length() - 1.0).abs() < 1e-5);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 //! A module for standard Vertex types for use in rendering 3d images
-use math::{Vec3, Vec4};
+use math::{Vec3, Vec4, Vector};
 use std::fmt;
 
 /// A vertex that contains a position, a normal and a texture coordinate
@@ -13,6 +13,20 @@ pub struct VertexPositionNormalTexture {
     pub texture: Vec3,
 }
 
+/// A vertex that contains a position, a normal, a tangent and a texture coordinate
+#[derive(Clone, Copy,Debug)]
+pub struct VertexPositionNormalTangentTexture {
+    /// The position of the vertex
+    pub position: Vec4,
+    /// The vertex normal
+    pub normal: Vec3,
+    /// The vertex tangent, `w` is the sign of the bitangent which is
+    /// `w * cross(normal, tangent.xyz)`
+    pub tangent: Vec4,
+    /// The texture coordinates of the vertex
+    pub texture: Vec3,
+}
+
 /// A vertex that contains a position and a texture coordinate
 #[derive(Clone, Copy,Debug)]
 pub struct VertexPositionTexture {
@@ -31,15 +45,167 @@ pub struct VertexPositionNormal {
     pub normal: Vec3,
 }
 
+/// A vertex that only contains a position
+#[derive(Clone, Copy,Debug)]
+pub struct VertexPosition {
+    /// The position of the vertex
+    pub position: Vec4,
+}
+
 /// An enum that represents one of a variety of vertex types
 #[derive(Clone, Copy,Debug)]
 pub enum Vertex {
+    /// A vertex that contains a position, normal, tangent and texture coordinate
+    PositionNormalTangentTexture(VertexPositionNormalTangentTexture),
     /// A vertex that contains a position, normal and texture coordinate
     PositionNormalTexture(VertexPositionNormalTexture),
     /// A vertex that has a position and a texture coordinate
     PositionTexture(VertexPositionTexture),
     /// A vertex that has a position and a normal
     PositionNormal(VertexPositionNormal),
+    /// A vertex that only has a position
+    Position(VertexPosition),
+}
+
+impl Vertex {
+    /// Gets the position of the vertex regardless of its type
+    pub fn position(&self) -> Vec4 {
+        match self {
+            Vertex::PositionNormalTangentTexture(v) => v.position,
+            Vertex::PositionNormalTexture(v) => v.position,
+            Vertex::PositionTexture(v) => v.position,
+            Vertex::PositionNormal(v) => v.position,
+            Vertex::Position(v) => v.position,
+        }
+    }
+
+    /// Gets the normal of the vertex if it has one
+    pub fn normal(&self) -> Option<Vec3> {
+        match self {
+            Vertex::PositionNormalTangentTexture(v) => Some(v.normal),
+            Vertex::PositionNormalTexture(v) => Some(v.normal),
+            Vertex::PositionNormal(v) => Some(v.normal),
+            Vertex::PositionTexture(_) | Vertex::Position(_) => None,
+        }
+    }
+
+    /// Gets the tangent of the vertex if it has one
+    pub fn tangent(&self) -> Option<Vec4> {
+        match self {
+            Vertex::PositionNormalTangentTexture(v) => Some(v.tangent),
+            _ => None,
+        }
+    }
+
+    /// Gets the texture coordinate of the vertex if it has one
+    pub fn texture(&self) -> Option<Vec3> {
+        match self {
+            Vertex::PositionNormalTangentTexture(v) => Some(v.texture),
+            Vertex::PositionNormalTexture(v) => Some(v.texture),
+            Vertex::PositionTexture(v) => Some(v.texture),
+            Vertex::PositionNormal(_) | Vertex::Position(_) => None,
+        }
+    }
+
+    /// Creates a copy of the vertex with the given normal, adding a normal to
+    /// a vertex that doesn't have one
+    ///
+    /// # Remarks
+    ///
+    /// A tangent no longer matches the new normal so it is dropped.
+    pub fn with_normal(&self, normal: Vec3) -> Vertex {
+        match *self {
+            Vertex::PositionNormalTangentTexture(v) => {
+                Vertex::PositionNormalTexture(VertexPositionNormalTexture {
+                    position: v.position,
+                    normal: normal,
+                    texture: v.texture,
+                })
+            }
+            Vertex::PositionNormalTexture(v) => {
+                Vertex::PositionNormalTexture(VertexPositionNormalTexture { normal: normal, ..v })
+            }
+            Vertex::PositionTexture(v) => Vertex::PositionNormalTexture(VertexPositionNormalTexture {
+                position: v.position,
+                normal: normal,
+                texture: v.texture,
+            }),
+            Vertex::PositionNormal(v) => {
+                Vertex::PositionNormal(VertexPositionNormal { normal: normal, ..v })
+            }
+            Vertex::Position(v) => Vertex::PositionNormal(VertexPositionNormal {
+                position: v.position,
+                normal: normal,
+            }),
+        }
+    }
+
+    /// Creates a copy of the vertex with the given tangent
+    ///
+    /// # Returns
+    /// the vertex with its tangent, or none when the vertex doesn't have both
+    /// a normal and a texture coordinate
+    pub fn with_tangent(&self, tangent: Vec4) -> Option<Vertex> {
+        match *self {
+            Vertex::PositionNormalTangentTexture(v) => Some(Vertex::PositionNormalTangentTexture(
+                VertexPositionNormalTangentTexture { tangent: tangent, ..v },
+            )),
+            Vertex::PositionNormalTexture(v) => Some(Vertex::PositionNormalTangentTexture(
+                VertexPositionNormalTangentTexture {
+                    position: v.position,
+                    normal: v.normal,
+                    tangent: tangent,
+                    texture: v.texture,
+                },
+            )),
+            Vertex::PositionTexture(_) | Vertex::PositionNormal(_) | Vertex::Position(_) => None,
+        }
+    }
+
+    /// Converts the vertex to one with a position, normal and texture coordinate,
+    /// a normal or texture coordinate that the vertex doesn't have is zero
+    pub fn to_position_normal_texture(&self) -> VertexPositionNormalTexture {
+        match *self {
+            Vertex::PositionNormalTangentTexture(v) => VertexPositionNormalTexture {
+                position: v.position,
+                normal: v.normal,
+                texture: v.texture,
+            },
+            Vertex::PositionNormalTexture(v) => v,
+            Vertex::PositionTexture(v) => VertexPositionNormalTexture {
+                position: v.position,
+                normal: Vec3::zero(),
+                texture: v.texture,
+            },
+            Vertex::PositionNormal(v) => VertexPositionNormalTexture {
+                position: v.position,
+                normal: v.normal,
+                texture: Vec3::zero(),
+            },
+            Vertex::Position(v) => VertexPositionNormalTexture {
+                position: v.position,
+                normal: Vec3::zero(),
+                texture: Vec3::zero(),
+            },
+        }
+    }
+
+    /// Converts the vertex to one with a position, normal, tangent and texture coordinate,
+    /// anything that the vertex doesn't have is zero
+    pub fn to_position_normal_tangent_texture(&self) -> VertexPositionNormalTangentTexture {
+        match *self {
+            Vertex::PositionNormalTangentTexture(v) => v,
+            _ => {
+                let v = self.to_position_normal_texture();
+                VertexPositionNormalTangentTexture {
+                    position: v.position,
+                    normal: v.normal,
+                    tangent: Vec4::zero(),
+                    texture: v.texture,
+                }
+            }
+        }
+    }
 }
 
 fn format_vec4(v: &Vec4) -> String {
@@ -50,6 +216,15 @@ fn format_vec3(v: &Vec3) -> String {
     format!("({},{},{})", v[0], v[1], v[2])
 }
 
+impl fmt::Display for VertexPositionNormalTangentTexture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "(pos: {})", format_vec4(&self.position))?;
+        writeln!(f, "(normal: {})", format_vec3(&self.normal))?;
+        writeln!(f, "(tangent: {})", format_vec4(&self.tangent))?;
+        writeln!(f, "(texture: {})", format_vec3(&self.texture))
+    }
+}
+
 impl fmt::Display for VertexPositionNormalTexture {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "(pos: {})", format_vec4(&self.position));
@@ -72,12 +247,20 @@ impl fmt::Display for VertexPositionNormal {
     }
 }
 
+impl fmt::Display for VertexPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "(pos: {})", format_vec4(&self.position))
+    }
+}
+
 impl fmt::Display for Vertex {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Vertex::PositionNormalTangentTexture(t) => write!(f, "{}", t),
             Vertex::PositionNormalTexture(t) => write!(f, "{}", t),
             Vertex::PositionTexture(t) => write!(f, "{}", t),
             Vertex::PositionNormal(t) => write!(f, "{}", t),
+            Vertex::Position(t) => write!(f, "{}", t),
         }
     }
 }
@@ -0,0 +1,685 @@
+//! A single file archive format for packing content.
+//!
+//! # Layout
+//!
+//! All numbers are little endian.
+//!
+//! * A 24 byte header: the magic bytes `RPAK`, the format version (`u32`), the number
+//! of entries (`u32`), padding (`u32`) and the offset of the table of contents (`u64`)
+//! * The data of each entry, one after another
+//! * The table of contents, for each entry: the length of its path (`u16`), the path as
+//! utf-8 using `/` separators, the offset of its data (`u64`), the size of its data in
+//! the archive (`u64`), its size once unpacked (`u64`), its compression (`u8`) and the
+//! crc32 checksum of the unpacked data (`u32`)
+use flate2;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use io::vfs::{normalize_path, Mount};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"RPAK";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 24;
+// the size of an entry in the table of contents without its path
+const ENTRY_SIZE: u64 = 31;
+// deflate can't shrink data by more than this, so a larger unpacked size is corrupt
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+/// How the data of an entry is stored in an archive
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// The data is stored as is
+    None,
+    /// The data is compressed with deflate
+    Deflate,
+}
+
+/// An entry in the table of contents of an archive
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchiveEntry {
+    /// The path of the entry within the archive
+    pub path: String,
+    /// The offset of the data of the entry from the start of the archive
+    pub offset: u64,
+    /// The size of the data of the entry in the archive
+    pub stored_size: u64,
+    /// The size of the entry once it has been unpacked
+    pub size: u64,
+    /// How the data of the entry is stored
+    pub compression: Compression,
+    /// The crc32 checksum of the unpacked data
+    pub checksum: u32,
+}
+
+/// An archive on disk that can be read from, or mounted into a [`Vfs`](super::Vfs)
+#[derive(Clone, Debug)]
+pub struct Archive {
+    path: PathBuf,
+    entries: Vec<ArchiveEntry>,
+    index: HashMap<String, usize>,
+}
+
+/// Writes an archive entry by entry
+#[derive(Debug)]
+pub struct ArchiveWriter<W: Write + Seek> {
+    writer: W,
+    entries: Vec<ArchiveEntry>,
+    offset: u64,
+}
+
+impl Compression {
+    fn from_u8(value: u8) -> io::Result<Compression> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Deflate),
+            _ => Err(invalid_data(&format!("unknown compression {}", value))),
+        }
+    }
+
+    fn to_u8(&self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+        }
+    }
+}
+
+impl Archive {
+    /// Opens an archive and reads its table of contents
+    ///
+    /// # Arguments
+    /// `path` - the path of the archive on disk
+    ///
+    /// # Remarks
+    ///
+    /// An `InvalidData` error is returned when the table of contents doesn't fit in the
+    /// archive, or has an entry whose data is outside of the archive or whose path isn't a
+    /// normalized relative path, such as `../x` or `/etc/x`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Archive> {
+        let path = path.as_ref().to_path_buf();
+        let mut reader = BufReader::new(File::open(&path)?);
+        let length = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not an archive"));
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported archive version {}",
+                version
+            )));
+        }
+
+        let count = read_u32(&mut reader)?;
+        read_u32(&mut reader)?;
+        let toc_offset = read_u64(&mut reader)?;
+        if toc_offset < HEADER_SIZE || toc_offset > length {
+            return Err(invalid_data("table of contents is outside of the archive"));
+        }
+
+        if u64::from(count) * ENTRY_SIZE > length - toc_offset {
+            return Err(invalid_data("table of contents is larger than the archive"));
+        }
+
+        reader.seek(SeekFrom::Start(toc_offset))?;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let entry = read_entry(&mut reader)?;
+            check_entry(&entry, toc_offset)?;
+            entries.push(entry);
+        }
+
+        let index = entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.path.clone(), i))
+            .collect();
+
+        Ok(Archive {
+            path: path,
+            entries: entries,
+            index: index,
+        })
+    }
+
+    /// returns the entries in the table of contents
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    /// returns the entry for a path, if there is one
+    ///
+    /// # Arguments
+    /// `path` - the path of the entry within the archive
+    pub fn entry(&self, path: &str) -> Option<&ArchiveEntry> {
+        self.index
+            .get(&normalize_path(path))
+            .map(|&i| &self.entries[i])
+    }
+
+    /// Reads and unpacks an entry
+    ///
+    /// # Arguments
+    /// `path` - the path of the entry within the archive
+    ///
+    /// # Remarks
+    ///
+    /// The unpacked data is checked against the checksum in the table of contents,
+    /// an `InvalidData` error is returned when they don't match.
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let entry = match self.entry(path) {
+            Some(entry) => entry,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not in the archive", path),
+                ))
+            }
+        };
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let stored = file.take(entry.stored_size);
+
+        // the unpacked size is only trusted once it has been read
+        let mut contents = Vec::with_capacity(entry.stored_size as usize);
+        match entry.compression {
+            Compression::None => stored.take(entry.size).read_to_end(&mut contents)?,
+            Compression::Deflate => DeflateDecoder::new(stored)
+                .take(entry.size)
+                .read_to_end(&mut contents)?,
+        };
+
+        if contents.len() as u64 != entry.size || checksum(&contents) != entry.checksum {
+            return Err(invalid_data(&format!("{} is corrupt", entry.path)));
+        }
+
+        Ok(contents)
+    }
+
+    /// Unpacks every entry into a directory
+    ///
+    /// # Arguments
+    /// `directory` - the directory to unpack into, it is created if it doesn't exist
+    ///
+    /// returns the number of entries unpacked
+    pub fn unpack<P: AsRef<Path>>(&self, directory: P) -> io::Result<usize> {
+        let directory = directory.as_ref();
+
+        for entry in self.entries.iter() {
+            let target = directory.join(&entry.path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, self.read(&entry.path)?)?;
+        }
+
+        Ok(self.entries.len())
+    }
+}
+
+impl Mount for Archive {
+    fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>> {
+        Ok(Box::new(Cursor::new(self.read(path)?)))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        let path = normalize_path(path);
+        let prefix = format!("{}/", path);
+
+        path.is_empty()
+            || self.index.contains_key(&path)
+            || self.entries.iter().any(|e| e.path.starts_with(&prefix))
+    }
+
+    fn list(&self, directory: &str) -> io::Result<Vec<String>> {
+        let directory = normalize_path(directory);
+        let prefix = if directory.is_empty() {
+            directory
+        } else {
+            format!("{}/", directory)
+        };
+
+        let mut names = self
+            .entries
+            .iter()
+            .filter(|e| e.path.starts_with(&prefix))
+            .filter_map(|e| e.path[prefix.len()..].split('/').next())
+            .map(|name| name.to_owned())
+            .collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+
+        Ok(names)
+    }
+}
+
+impl<W: Write + Seek> ArchiveWriter<W> {
+    /// Starts writing an archive
+    ///
+    /// # Arguments
+    /// `writer` - where to write the archive
+    pub fn new(mut writer: W) -> io::Result<ArchiveWriter<W>> {
+        // the header is written again with the real values once the archive is finished
+        writer.write_all(&[0u8; HEADER_SIZE as usize])?;
+
+        Ok(ArchiveWriter {
+            writer: writer,
+            entries: Vec::new(),
+            offset: HEADER_SIZE,
+        })
+    }
+
+    /// Adds an entry to the archive
+    ///
+    /// # Arguments
+    /// `path` - the path of the entry within the archive
+    /// `contents` - the unpacked data of the entry
+    /// `compression` - how to store the data
+    ///
+    /// # Remarks
+    ///
+    /// Data that doesn't get smaller when compressed, such as a jpeg, is stored as is.
+    pub fn add(&mut self, path: &str, contents: &[u8], compression: Compression) -> io::Result<()> {
+        let path = normalize_path(path);
+        if path.is_empty() || path.len() > u16::max_value() as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a valid entry path", path),
+            ));
+        }
+
+        let compressed = match compression {
+            Compression::None => None,
+            Compression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(contents)?;
+                Some(encoder.finish()?).filter(|c| c.len() < contents.len())
+            }
+        };
+
+        let (stored, compression) = match compressed {
+            Some(ref c) => (&c[..], Compression::Deflate),
+            None => (contents, Compression::None),
+        };
+
+        self.writer.write_all(stored)?;
+        self.entries.push(ArchiveEntry {
+            path: path,
+            offset: self.offset,
+            stored_size: stored.len() as u64,
+            size: contents.len() as u64,
+            compression: compression,
+            checksum: checksum(contents),
+        });
+        self.offset += stored.len() as u64;
+
+        Ok(())
+    }
+
+    /// Writes the table of contents and header, finishing the archive
+    ///
+    /// returns the writer that the archive was written to
+    pub fn finish(mut self) -> io::Result<W> {
+        let toc_offset = self.offset;
+
+        for entry in self.entries.iter() {
+            self.writer
+                .write_all(&(entry.path.len() as u16).to_le_bytes())?;
+            self.writer.write_all(entry.path.as_bytes())?;
+            self.writer.write_all(&entry.offset.to_le_bytes())?;
+            self.writer.write_all(&entry.stored_size.to_le_bytes())?;
+            self.writer.write_all(&entry.size.to_le_bytes())?;
+            self.writer.write_all(&[entry.compression.to_u8()])?;
+            self.writer.write_all(&entry.checksum.to_le_bytes())?;
+        }
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.write_all(&VERSION.to_le_bytes())?;
+        self.writer
+            .write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&0u32.to_le_bytes())?;
+        self.writer.write_all(&toc_offset.to_le_bytes())?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Packs every file in a directory into an archive
+///
+/// # Arguments
+/// `directory` - the directory to pack
+/// `archive` - the path of the archive to create
+/// `compression` - how to store the files
+///
+/// returns the number of files packed
+pub fn pack_directory<P: AsRef<Path>, Q: AsRef<Path>>(
+    directory: P,
+    archive: Q,
+    compression: Compression,
+) -> io::Result<usize> {
+    let mut files = Vec::new();
+    collect_files(directory.as_ref(), "", &mut files)?;
+    files.sort();
+
+    let mut writer = ArchiveWriter::new(io::BufWriter::new(File::create(archive)?))?;
+    for (path, file) in files.iter() {
+        writer.add(path, &fs::read(file)?, compression)?;
+    }
+    writer.finish()?;
+
+    Ok(files.len())
+}
+
+fn collect_files(
+    directory: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, PathBuf)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &path, files)?;
+        } else {
+            files.push((path, entry.path()));
+        }
+    }
+
+    Ok(())
+}
+
+// checks that an entry's data is within the archive data and its path can't escape a directory
+fn check_entry(entry: &ArchiveEntry, toc_offset: u64) -> io::Result<()> {
+    let end = entry.offset.checked_add(entry.stored_size);
+    if entry.offset < HEADER_SIZE || end.map_or(true, |end| end > toc_offset) {
+        return Err(invalid_data(&format!(
+            "{} is outside of the archive data",
+            entry.path
+        )));
+    }
+
+    let size_fits = match entry.compression {
+        Compression::None => entry.size == entry.stored_size,
+        Compression::Deflate => entry
+            .stored_size
+            .checked_mul(MAX_DEFLATE_RATIO)
+            .map_or(true, |largest| entry.size <= largest),
+    };
+    if !size_fits {
+        return Err(invalid_data(&format!(
+            "{} has an unpacked size that doesn't match its data",
+            entry.path
+        )));
+    }
+
+    let relative = Path::new(&entry.path)
+        .components()
+        .all(|c| match c {
+            Component::Normal(_) => true,
+            _ => false,
+        });
+    if entry.path.is_empty() || !relative || entry.path != normalize_path(&entry.path) {
+        return Err(invalid_data(&format!(
+            "{} is not a valid entry path",
+            entry.path
+        )));
+    }
+
+    Ok(())
+}
+
+fn read_entry<R: Read>(reader: &mut R) -> io::Result<ArchiveEntry> {
+    let mut length = [0u8; 2];
+    reader.read_exact(&mut length)?;
+
+    let mut path = vec![0u8; u16::from_le_bytes(length) as usize];
+    reader.read_exact(&mut path)?;
+    let path = String::from_utf8(path).map_err(|_| invalid_data("entry path is not utf-8"))?;
+
+    let offset = read_u64(reader)?;
+    let stored_size = read_u64(reader)?;
+    let size = read_u64(reader)?;
+    let mut compression = [0u8; 1];
+    reader.read_exact(&mut compression)?;
+    let checksum = read_u32(reader)?;
+
+    Ok(ArchiveEntry {
+        path: path,
+        offset: offset,
+        stored_size: stored_size,
+        size: size,
+        compression: Compression::from_u8(compression[0])?,
+        checksum: checksum,
+    })
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn checksum(contents: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(contents);
+    crc.sum()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    // a directory for a test's files that is removed once the test is done with it
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Scratch {
+            let root = env::temp_dir().join(format!("archive-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            Scratch(root)
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn text() -> Vec<u8> {
+        "v 0 0 0\n".repeat(200).into_bytes()
+    }
+
+    fn noise() -> Vec<u8> {
+        let mut state = 0x2545_f491u32;
+        (0..512)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    // an archive with a text file and a file that doesn't compress
+    fn build(paths: (&str, &str)) -> Vec<u8> {
+        let mut writer = ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.add(paths.0, &text(), Compression::Deflate).unwrap();
+        writer.add(paths.1, &noise(), Compression::Deflate).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn open(scratch: &Scratch, bytes: &[u8]) -> io::Result<Archive> {
+        let path = scratch.path("test.rpak");
+        fs::write(&path, bytes).unwrap();
+        Archive::open(path)
+    }
+
+    fn toc_offset(bytes: &[u8]) -> usize {
+        let mut offset = [0u8; 8];
+        offset.copy_from_slice(&bytes[16..24]);
+        u64::from_le_bytes(offset) as usize
+    }
+
+    // replaces part of the first entry in the table of contents, `at` is relative to
+    // the end of its path
+    fn patch_first_entry(bytes: &mut [u8], at: usize, value: &[u8]) {
+        let toc = toc_offset(bytes);
+        let path_length = u16::from_le_bytes([bytes[toc], bytes[toc + 1]]) as usize;
+        let start = toc + 2 + path_length + at;
+        bytes[start..start + value.len()].copy_from_slice(value);
+    }
+
+    fn assert_invalid(result: io::Result<Archive>) {
+        match result {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData, "{}", e),
+            Ok(_) => panic!("a corrupt archive was opened"),
+        }
+    }
+
+    #[test]
+    fn pack_read_and_unpack() {
+        let scratch = Scratch::new("round-trip");
+        let archive = open(&scratch, &build(("models/a.obj", "textures/b.png"))).unwrap();
+
+        let entries = archive.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].compression, Compression::Deflate);
+        assert!(entries[0].stored_size < entries[0].size);
+        assert_eq!(entries[1].compression, Compression::None);
+
+        assert_eq!(archive.read("models/a.obj").unwrap(), text());
+        assert_eq!(archive.read("./models//a.obj").unwrap(), text());
+        assert_eq!(archive.read("textures/b.png").unwrap(), noise());
+        assert_eq!(
+            archive.read("missing").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        assert!(archive.exists("models"));
+        assert!(!archive.exists("model"));
+        assert_eq!(archive.list("").unwrap(), vec!["models", "textures"]);
+
+        let unpacked = scratch.path("unpacked");
+        assert_eq!(archive.unpack(&unpacked).unwrap(), 2);
+        assert_eq!(fs::read(unpacked.join("models/a.obj")).unwrap(), text());
+        assert_eq!(fs::read(unpacked.join("textures/b.png")).unwrap(), noise());
+    }
+
+    #[test]
+    fn pack_directory_round_trip() {
+        let scratch = Scratch::new("directory");
+        let content = scratch.path("content");
+        fs::create_dir_all(content.join("sub")).unwrap();
+        fs::write(content.join("a.txt"), text()).unwrap();
+        fs::write(content.join("sub/b.bin"), noise()).unwrap();
+
+        let packed = scratch.path("content.rpak");
+        assert_eq!(
+            pack_directory(&content, &packed, Compression::Deflate).unwrap(),
+            2
+        );
+
+        let archive = Archive::open(&packed).unwrap();
+        let unpacked = scratch.path("unpacked");
+        archive.unpack(&unpacked).unwrap();
+        assert_eq!(fs::read(unpacked.join("a.txt")).unwrap(), text());
+        assert_eq!(fs::read(unpacked.join("sub/b.bin")).unwrap(), noise());
+    }
+
+    #[test]
+    fn corrupt_data_fails_its_checksum() {
+        let scratch = Scratch::new("checksum");
+        let mut bytes = build(("a.obj", "b.png"));
+        let last = toc_offset(&bytes) - 1;
+        bytes[last] ^= 0xff;
+
+        let archive = open(&scratch, &bytes).unwrap();
+        assert!(archive.read("a.obj").is_ok());
+        let error = archive.read("b.png").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(archive.unpack(scratch.path("unpacked")).is_err());
+    }
+
+    #[test]
+    fn paths_that_escape_the_archive_are_rejected() {
+        let scratch = Scratch::new("paths");
+
+        for &(placeholder, path) in &[
+            ("aa/x", "../x"),
+            ("aetc/x", "/etc/x"),
+            ("a/aa/x", "a/../x"),
+            ("a/a/x", "a//x"),
+            ("a/b/x", "a/./x"),
+        ] {
+            let mut bytes = build((placeholder, "b.png"));
+            let toc = toc_offset(&bytes);
+            bytes[toc + 2..toc + 2 + path.len()].copy_from_slice(path.as_bytes());
+            assert_invalid(open(&scratch, &bytes));
+        }
+    }
+
+    #[test]
+    fn sizes_beyond_the_archive_are_rejected() {
+        let scratch = Scratch::new("sizes");
+
+        // more entries than the table of contents could hold
+        let mut bytes = build(("a.obj", "b.png"));
+        bytes[8..12].copy_from_slice(&u32::max_value().to_le_bytes());
+        assert_invalid(open(&scratch, &bytes));
+
+        // data that would overflow the end of the archive
+        let mut bytes = build(("a.obj", "b.png"));
+        patch_first_entry(&mut bytes, 0, &(u64::max_value() - 1).to_le_bytes());
+        assert_invalid(open(&scratch, &bytes));
+
+        let mut bytes = build(("a.obj", "b.png"));
+        patch_first_entry(&mut bytes, 8, &u64::max_value().to_le_bytes());
+        assert_invalid(open(&scratch, &bytes));
+
+        // an unpacked size that deflate couldn't have produced
+        let mut bytes = build(("a.obj", "b.png"));
+        patch_first_entry(&mut bytes, 16, &u64::max_value().to_le_bytes());
+        assert_invalid(open(&scratch, &bytes));
+
+        // a truncated table of contents
+        let bytes = build(("a.obj", "b.png"));
+        assert!(open(&scratch, &bytes[..bytes.len() - 10]).is_err());
+        assert!(open(&scratch, &bytes[..10]).is_err());
+    }
+}
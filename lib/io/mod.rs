@@ -1,4 +1,10 @@
 //! A helper module for common io patterns and functions
+mod archive;
+mod vfs;
+
+pub use self::archive::*;
+pub use self::vfs::*;
+
 use std::fs::File;
 use std::io::{prelude::*, Cursor};
 
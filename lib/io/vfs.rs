@@ -0,0 +1,595 @@
+//! A virtual file system that lets content be loaded from several locations.
+//! Directories, archives and anything else implementing [`Mount`] are mounted under logical roots,
+//! and content is then loaded with logical paths such as `content/Earth/earth.obj`
+//! regardless of where it actually lives.
+use io::archive::Archive;
+use parser::{FromReader, FromReaderWithMode, ParseError, ParseMode, Parsed, Resolver};
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A source of files that can be mounted into a [`Vfs`]
+///
+/// # Remarks
+///
+/// Paths passed to a mount are relative to the mount and always use `/` as a separator.
+pub trait Mount: fmt::Debug + Send + Sync {
+    /// Opens a file for reading
+    ///
+    /// # Arguments
+    /// `path` - the path of the file within the mount
+    fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>>;
+
+    /// returns true when a file or directory exists in the mount
+    ///
+    /// # Arguments
+    /// `path` - the path within the mount
+    fn exists(&self, path: &str) -> bool;
+
+    /// Lists the names of the entries in a directory of the mount
+    ///
+    /// # Arguments
+    /// `directory` - the path of the directory within the mount, empty for the top level
+    fn list(&self, directory: &str) -> io::Result<Vec<String>>;
+
+    /// returns when a file was last modified, or None when the mount can't tell
+    ///
+    /// # Arguments
+    /// `path` - the path of the file within the mount
+    fn modified(&self, _path: &str) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// A mount that reads files from a directory on disk
+#[derive(Clone, Debug)]
+pub struct DirectoryMount {
+    root: PathBuf,
+}
+
+/// A virtual file system made up of mounts under logical roots
+///
+/// # Remarks
+///
+/// When more than one mount contains a path, the one mounted last wins. This allows
+/// content to be overridden by mounting a patch directory over the top of it.
+#[derive(Debug, Default)]
+pub struct Vfs {
+    mounts: Vec<MountPoint>,
+    case_insensitive: bool,
+}
+
+/// A resolver that finds the files referred to by a file in a [`Vfs`]
+/// paths are resolved relative to the directory of that file
+#[derive(Debug)]
+pub struct VfsResolver<'a> {
+    vfs: &'a Vfs,
+    directory: String,
+    opened: RefCell<Vec<String>>,
+}
+
+#[derive(Debug)]
+struct MountPoint {
+    root: String,
+    mount: Box<dyn Mount>,
+}
+
+impl DirectoryMount {
+    /// Creates a mount for a directory
+    ///
+    /// # Arguments
+    /// `root` - the directory on disk
+    pub fn new<P: AsRef<Path>>(root: P) -> DirectoryMount {
+        DirectoryMount {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Mount for DirectoryMount {
+    fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>> {
+        let file = File::open(self.root.join(path))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.root.join(path).exists()
+    }
+
+    fn list(&self, directory: &str) -> io::Result<Vec<String>> {
+        fs::read_dir(self.root.join(directory))?
+            .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        fs::metadata(self.root.join(path))
+            .and_then(|m| m.modified())
+            .ok()
+    }
+}
+
+impl MountPoint {
+    // returns the path relative to this mount point, or None when the path is outside of it
+    fn relative(&self, path: &str, ignore_case: bool) -> Option<String> {
+        let mut parts = path.split('/').filter(|p| !p.is_empty());
+
+        for root_part in self.root.split('/').filter(|p| !p.is_empty()) {
+            match parts.next() {
+                Some(part) if names_match(part, root_part, ignore_case) => (),
+                _ => return None,
+            }
+        }
+
+        Some(parts.collect::<Vec<&str>>().join("/"))
+    }
+}
+
+impl Vfs {
+    /// Creates an empty file system with nothing mounted
+    pub fn new() -> Vfs {
+        Vfs {
+            mounts: Vec::new(),
+            case_insensitive: false,
+        }
+    }
+
+    /// Mounts a source of files under a logical root
+    ///
+    /// # Arguments
+    /// `root` - the logical path the mount appears at, empty to mount at the top level
+    /// `mount` - the source of files
+    pub fn mount<M: Mount + 'static>(&mut self, root: &str, mount: M) {
+        self.mounts.push(MountPoint {
+            root: normalize_path(root),
+            mount: Box::new(mount),
+        });
+    }
+
+    /// Mounts a directory on disk under a logical root
+    ///
+    /// # Arguments
+    /// `root` - the logical path the directory appears at, empty to mount at the top level
+    /// `directory` - the directory on disk
+    pub fn mount_directory<P: AsRef<Path>>(&mut self, root: &str, directory: P) {
+        self.mount(root, DirectoryMount::new(directory));
+    }
+
+    /// Mounts an archive on disk under a logical root
+    ///
+    /// # Arguments
+    /// `root` - the logical path the contents of the archive appear at, empty to mount at the top level
+    /// `archive` - the path of the archive on disk
+    pub fn mount_archive<P: AsRef<Path>>(&mut self, root: &str, archive: P) -> io::Result<()> {
+        self.mount(root, Archive::open(archive)?);
+        Ok(())
+    }
+
+    /// Sets whether paths should be matched ignoring case
+    /// this is useful for content authored on a case-insensitive file system
+    ///
+    /// # Arguments
+    /// `case_insensitive` - true to ignore case when matching paths
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+
+    /// returns true when the path exists in any mount
+    ///
+    /// # Arguments
+    /// `path` - the logical path
+    pub fn exists(&self, path: &str) -> bool {
+        self.locate(path).is_some()
+    }
+
+    /// returns the path of a file as it appears in the file system, or None when it doesn't exist
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    ///
+    /// # Remarks
+    ///
+    /// Every path that refers to the same file has the same canonical path, which makes it
+    /// suitable as a key when caching content.
+    pub fn canonical_path(&self, path: &str) -> Option<String> {
+        self.locate(path)
+            .map(|(point, relative)| join_path(&point.root, &relative))
+    }
+
+    /// returns when a file was last modified, or None when it doesn't exist or its mount can't tell
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    pub fn modified(&self, path: &str) -> Option<SystemTime> {
+        self.locate(path)
+            .and_then(|(point, relative)| point.mount.modified(&relative))
+    }
+
+    /// Opens a file for reading
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    pub fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>> {
+        match self.locate(path) {
+            Some((point, relative)) => point.mount.open(&relative),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} was not found in any mount", path),
+            )),
+        }
+    }
+
+    /// Reads the whole of a file
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let mut contents = Vec::new();
+        self.open(path)?.read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    /// Reads the whole of a file as text
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        let mut contents = String::new();
+        self.open(path)?.read_to_string(&mut contents)?;
+        Ok(contents)
+    }
+
+    /// Creates a resolver for the files referred to by a file
+    ///
+    /// # Arguments
+    /// `file` - the logical path of the file that refers to other files
+    pub fn resolver<'a>(&'a self, file: &str) -> VfsResolver<'a> {
+        let file = normalize_path(file);
+        let directory = match file.rfind('/') {
+            Some(index) => file[..index].to_owned(),
+            None => String::new(),
+        };
+
+        VfsResolver {
+            vfs: self,
+            directory: directory,
+            opened: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Parses a file, any files that it refers to are also loaded from the file system
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    ///
+    /// # Remarks
+    ///
+    /// `T` is the type that parses the file and `U` is what it produces, for example
+    /// `vfs.parse::<Material, _>("content/Earth/earth.mtl")` produces a `Vec<Material>`.
+    pub fn parse<T, U>(&self, path: &str) -> Result<U, ParseError>
+    where
+        T: FromReader<ParseResult = Result<U, ParseError>>,
+    {
+        self.parse_tracked::<T, U>(path).map(|(parsed, _)| parsed)
+    }
+
+    /// Parses a file and returns the paths of every file that was read to parse it,
+    /// starting with the file itself
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    pub fn parse_tracked<T, U>(&self, path: &str) -> Result<(U, Vec<String>), ParseError>
+    where
+        T: FromReader<ParseResult = Result<U, ParseError>>,
+    {
+        let (path, reader) = self.open_for_parse(path)?;
+        let resolver = self.resolver(&path);
+        let parsed = T::from_reader(reader, &path, &resolver)?;
+
+        let mut files = vec![path];
+        files.extend(resolver.opened());
+        Ok((parsed, files))
+    }
+
+    /// Parses a file in the given mode, returning any warnings next to the result
+    ///
+    /// # Arguments
+    /// `path` - the logical path of the file
+    /// `mode` - whether unknown or unsupported statements fail the parse or are skipped
+    pub fn parse_with_mode<T, U>(&self, path: &str, mode: ParseMode) -> Result<Parsed<U>, ParseError>
+    where
+        T: FromReaderWithMode<Output = U>,
+    {
+        let (path, reader) = self.open_for_parse(path)?;
+        T::from_reader_with_mode(reader, &path, &self.resolver(&path), mode)
+    }
+
+    // opens a file to be parsed, returning its normalized path to use in error locations
+    fn open_for_parse(&self, path: &str) -> Result<(String, Box<dyn BufRead>), ParseError> {
+        let path = normalize_path(path);
+        let reader = self
+            .open(&path)
+            .map_err(|e| ParseError::from(e).in_file(&path))?;
+
+        Ok((path, reader))
+    }
+
+    // finds the mount point containing a path, along with the path relative to that mount
+    fn locate(&self, path: &str) -> Option<(&MountPoint, String)> {
+        let path = normalize_path(path);
+
+        for point in self.mounts.iter().rev() {
+            let relative = match point.relative(&path, self.case_insensitive) {
+                Some(relative) => relative,
+                None => continue,
+            };
+
+            if point.mount.exists(&relative) {
+                return Some((point, relative));
+            }
+
+            if self.case_insensitive {
+                if let Some(relative) = find_ignoring_case(&*point.mount, &relative) {
+                    return Some((point, relative));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a> Resolver for VfsResolver<'a> {
+    fn resolve(&self, path: &str) -> String {
+        join_path(&self.directory, path)
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn BufRead>, ParseError> {
+        let path = self.resolve(path);
+        let reader = self
+            .vfs
+            .open(&path)
+            .map_err(|e| ParseError::from(e).in_file(&path))?;
+
+        self.opened.borrow_mut().push(path);
+        Ok(reader)
+    }
+}
+
+impl<'a> VfsResolver<'a> {
+    /// returns the paths of every file that has been opened through the resolver
+    pub fn opened(&self) -> Vec<String> {
+        self.opened.borrow().clone()
+    }
+}
+
+/// Normalizes a logical path so it uses `/` separators with no empty, `.` or `..` parts
+///
+/// # Arguments
+/// `path` - the path to normalize
+///
+/// # Remarks
+///
+/// A `..` at the start of the path is dropped, a path can't refer to anything above the root.
+pub fn normalize_path(path: &str) -> String {
+    let mut parts = Vec::new();
+
+    for part in path.split(|c| c == '/' || c == '\\') {
+        match part {
+            "" | "." => (),
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    parts.join("/")
+}
+
+/// Joins a path onto a directory and normalizes the result
+///
+/// # Arguments
+/// `directory` - the logical directory
+/// `path` - the path relative to the directory
+pub fn join_path(directory: &str, path: &str) -> String {
+    normalize_path(&format!("{}/{}", directory, path))
+}
+
+fn names_match(a: &str, b: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+// walks a path one part at a time, matching each part against the entries of its directory
+fn find_ignoring_case(mount: &dyn Mount, path: &str) -> Option<String> {
+    let mut found = String::new();
+
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        let exact = join_path(&found, part);
+        if mount.exists(&exact) {
+            found = exact;
+            continue;
+        }
+
+        let name = mount
+            .list(&found)
+            .ok()?
+            .into_iter()
+            .find(|name| names_match(name, part, true))?;
+        found = join_path(&found, &name);
+    }
+
+    Some(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::MeshDescriptions;
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+
+    // a mount holding its files in memory, directories are implied by the paths of the files
+    #[derive(Debug, Default)]
+    struct MemoryMount {
+        files: BTreeMap<String, String>,
+    }
+
+    impl MemoryMount {
+        fn with_file(mut self, path: &str, contents: &str) -> MemoryMount {
+            self.files.insert(path.to_owned(), contents.to_owned());
+            self
+        }
+    }
+
+    impl Mount for MemoryMount {
+        fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>> {
+            match self.files.get(path) {
+                Some(contents) => Ok(Box::new(Cursor::new(contents.clone().into_bytes()))),
+                None => Err(io::Error::new(io::ErrorKind::NotFound, path.to_owned())),
+            }
+        }
+
+        fn exists(&self, path: &str) -> bool {
+            path.is_empty()
+                || self
+                    .files
+                    .keys()
+                    .any(|file| file == path || file.starts_with(&format!("{}/", path)))
+        }
+
+        fn list(&self, directory: &str) -> io::Result<Vec<String>> {
+            let prefix = if directory.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", directory)
+            };
+
+            let mut names = self
+                .files
+                .keys()
+                .filter(|file| file.starts_with(&prefix))
+                .filter_map(|file| file[prefix.len()..].split('/').next())
+                .map(|name| name.to_owned())
+                .collect::<Vec<String>>();
+            names.dedup();
+            Ok(names)
+        }
+    }
+
+    #[test]
+    fn the_last_mount_wins() {
+        let mut vfs = Vfs::new();
+        vfs.mount(
+            "content",
+            MemoryMount::default()
+                .with_file("a.txt", "base a")
+                .with_file("b.txt", "base b"),
+        );
+        vfs.mount("content", MemoryMount::default().with_file("a.txt", "patch a"));
+
+        assert_eq!(vfs.read_to_string("content/a.txt").unwrap(), "patch a");
+        assert_eq!(vfs.read_to_string("content/b.txt").unwrap(), "base b");
+    }
+
+    #[test]
+    fn files_are_found_under_their_mount_root() {
+        let mut vfs = Vfs::new();
+        vfs.mount("content/models", MemoryMount::default().with_file("cube.obj", "cube"));
+        vfs.mount("", MemoryMount::default().with_file("readme.txt", "readme"));
+
+        assert_eq!(vfs.read_to_string("content/models/cube.obj").unwrap(), "cube");
+        assert_eq!(vfs.read_to_string("./content//models/../models/cube.obj").unwrap(), "cube");
+        assert_eq!(vfs.read_to_string("readme.txt").unwrap(), "readme");
+        assert!(!vfs.exists("cube.obj"));
+        assert!(!vfs.exists("content/cube.obj"));
+
+        let error = vfs.open("content/models/sphere.obj").err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn paths_are_case_sensitive_by_default() {
+        let mut vfs = Vfs::new();
+        vfs.mount("content", MemoryMount::default().with_file("Earth/earth.obj", "earth"));
+
+        assert!(vfs.exists("content/Earth/earth.obj"));
+        assert!(!vfs.exists("content/earth/earth.obj"));
+        assert!(!vfs.exists("Content/Earth/earth.obj"));
+    }
+
+    #[test]
+    fn case_insensitive_lookup_finds_the_file_as_it_is_named() {
+        let mut vfs = Vfs::new();
+        vfs.set_case_insensitive(true);
+        vfs.mount("content", MemoryMount::default().with_file("Earth/earth.obj", "earth"));
+
+        assert_eq!(vfs.read_to_string("CONTENT/earth/Earth.OBJ").unwrap(), "earth");
+        assert_eq!(
+            vfs.canonical_path("CONTENT/earth/Earth.OBJ"),
+            Some("content/Earth/earth.obj".to_owned())
+        );
+        assert!(!vfs.exists("content/earth/moon.obj"));
+    }
+
+    #[test]
+    fn case_insensitive_lookup_keeps_mount_precedence() {
+        let mut vfs = Vfs::new();
+        vfs.set_case_insensitive(true);
+        vfs.mount("", MemoryMount::default().with_file("a.txt", "base"));
+        vfs.mount("", MemoryMount::default().with_file("A.TXT", "patch"));
+
+        assert_eq!(vfs.read_to_string("a.txt").unwrap(), "patch");
+        assert_eq!(vfs.canonical_path("a.txt"), Some("A.TXT".to_owned()));
+    }
+
+    #[test]
+    fn parse_tracked_records_included_files() {
+        let mut vfs = Vfs::new();
+        vfs.mount(
+            "content",
+            MemoryMount::default()
+                .with_file("models/cube.obj", "mtllib ../materials/cube.mtl\nv 0 0 0\n")
+                .with_file("materials/cube.mtl", "newmtl cube\n"),
+        );
+
+        let (meshes, files) = vfs
+            .parse_tracked::<MeshDescriptions, _>("content/models/cube.obj")
+            .unwrap();
+
+        assert!(meshes.materials.contains_key("cube"));
+        assert_eq!(
+            files,
+            vec![
+                "content/models/cube.obj".to_owned(),
+                "content/materials/cube.mtl".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_missing_file_fails_the_parse_with_its_path() {
+        let vfs = Vfs::new();
+        let error = vfs
+            .parse::<MeshDescriptions, _>("content/./missing.obj")
+            .unwrap_err();
+
+        assert_eq!(error.location.unwrap().file, "content/missing.obj");
+    }
+
+    #[test]
+    fn paths_are_normalized() {
+        assert_eq!(normalize_path("content\\Earth/./earth.obj"), "content/Earth/earth.obj");
+        assert_eq!(normalize_path("/content//Earth/"), "content/Earth");
+        assert_eq!(normalize_path("../content/a/../b.obj"), "content/b.obj");
+        assert_eq!(join_path("content/Earth", "../Moon/moon.obj"), "content/Moon/moon.obj");
+        assert_eq!(join_path("", "earth.obj"), "earth.obj");
+    }
+}
@@ -11,9 +11,11 @@
 
 #[macro_use]
 extern crate glium;
+extern crate flate2;
 extern crate image;
 extern crate regex;
 
+pub mod assets;
 pub mod engine;
 pub mod game;
 pub mod graphics;
@@ -0,0 +1,898 @@
+//! A module containing bounding volumes.
+//! A bounding volume is a simple shape that fully encloses a more complicated one,
+//! they are cheap to test against so are used to quickly rule out work such as
+//! drawing something that is off screen.
+//!
+//! [`Aabb`] is an axis aligned box, [`BoundingSphere`] is a sphere and [`Obb`] is
+//! a box that can be rotated to fit its contents more tightly.
+use math::matrix::Mat4;
+use math::vector::{Vec3, Vector};
+use std::f32;
+use std::fmt;
+
+/// An axis aligned bounding box
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    /// The corner of the box with the smallest points
+    pub min: Vec3,
+    /// The corner of the box with the largest points
+    pub max: Vec3,
+}
+
+/// A bounding sphere
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingSphere {
+    /// The center of the sphere
+    pub center: Vec3,
+    /// The radius of the sphere
+    pub radius: f32,
+}
+
+/// An oriented bounding box
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Obb {
+    /// The center of the box
+    pub center: Vec3,
+    /// The local x, y and z axes of the box, these are unit length and perpendicular
+    pub axes: [Vec3; 3],
+    /// Half of the size of the box along each of its axes
+    pub half_extents: Vec3,
+}
+
+impl Aabb {
+    /// Creates a new box from its corners
+    ///
+    /// # Arguments
+    /// `min` - the corner with the smallest points
+    /// `max` - the corner with the largest points
+    pub fn new(min: Vec3, max: Vec3) -> Aabb {
+        Aabb { min: min, max: max }
+    }
+
+    /// Creates an empty box that contains nothing
+    ///
+    /// # Remarks
+    ///
+    /// Merging anything with an empty box returns the other volume unchanged.
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vec3::from_val(f32::INFINITY),
+            max: Vec3::from_val(f32::NEG_INFINITY),
+        }
+    }
+
+    /// Creates the smallest box that contains all of the points
+    ///
+    /// # Arguments
+    /// `points` - the points to enclose
+    ///
+    /// # Returns
+    /// an empty box when there are no points
+    pub fn from_points<'a, I>(points: I) -> Aabb
+    where
+        I: IntoIterator<Item = &'a Vec3>,
+    {
+        points.into_iter().fold(Aabb::empty(), |acc, p| acc.grow(p))
+    }
+
+    /// returns true when the box contains nothing
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// returns the center of the box
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// returns the size of the box along each axis
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// returns half of the size of the box along each axis
+    pub fn half_extents(&self) -> Vec3 {
+        self.size() * 0.5
+    }
+
+    /// returns the eight corners of the box
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z),
+            Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z),
+            Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z),
+            Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z),
+            Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    /// returns a box that also contains the provided point
+    ///
+    /// # Arguments
+    /// `point` - the point to include
+    pub fn grow(&self, point: &Vec3) -> Aabb {
+        Aabb {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+
+    /// returns the smallest box containing both boxes
+    ///
+    /// # Arguments
+    /// `other` - the box to merge with
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(&other.min),
+            max: self.max.max(&other.max),
+        }
+    }
+
+    /// returns the point inside the box that is closest to the provided point
+    ///
+    /// # Arguments
+    /// `point` - the point to find the closest point to
+    pub fn closest_point(&self, point: &Vec3) -> Vec3 {
+        point.clamp(&self.min, &self.max)
+    }
+
+    /// returns true when the point is inside or on the surface of the box
+    ///
+    /// # Arguments
+    /// `point` - the point to test
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    /// returns true when the other box is entirely inside this box
+    ///
+    /// # Arguments
+    /// `other` - the box to test
+    pub fn contains_aabb(&self, other: &Aabb) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    /// returns true when the sphere is entirely inside this box
+    ///
+    /// # Arguments
+    /// `sphere` - the sphere to test
+    pub fn contains_sphere(&self, sphere: &BoundingSphere) -> bool {
+        let r = Vec3::from_val(sphere.radius);
+        self.contains_aabb(&Aabb::new(sphere.center - r, sphere.center + r))
+    }
+
+    /// returns true when the boxes overlap
+    ///
+    /// # Arguments
+    /// `other` - the box to test
+    pub fn intersects_aabb(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    /// returns true when the box and the sphere overlap
+    ///
+    /// # Arguments
+    /// `sphere` - the sphere to test
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        sphere.intersects_aabb(self)
+    }
+
+    /// returns the box that contains this box after it has been transformed
+    ///
+    /// # Arguments
+    /// `m` - the matrix to transform by
+    ///
+    /// # Remarks
+    ///
+    /// Rotating a box makes it larger as the result must stay aligned to the axes.
+    pub fn transform(&self, m: &Mat4) -> Aabb {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let corners = self.corners();
+        let transformed = corners
+            .iter()
+            .map(|c| m.transform_point(c))
+            .collect::<Vec<Vec3>>();
+
+        Aabb::from_points(&transformed)
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Aabb {
+        Aabb::empty()
+    }
+}
+
+impl fmt::Display for Aabb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "(min: ({},{},{})),(max: ({},{},{}))",
+            self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z
+        )
+    }
+}
+
+impl BoundingSphere {
+    /// Creates a new sphere
+    ///
+    /// # Arguments
+    /// `center` - the center of the sphere
+    /// `radius` - the radius of the sphere
+    pub fn new(center: Vec3, radius: f32) -> BoundingSphere {
+        BoundingSphere {
+            center: center,
+            radius: radius,
+        }
+    }
+
+    /// Creates a sphere that contains all of the points
+    ///
+    /// # Arguments
+    /// `points` - the points to enclose
+    ///
+    /// # Remarks
+    ///
+    /// The sphere is centered on the bounding box of the points so it is not always
+    /// the smallest possible sphere. With no points the sphere has a negative radius
+    /// and contains nothing.
+    pub fn from_points<'a, I>(points: I) -> BoundingSphere
+    where
+        I: IntoIterator<Item = &'a Vec3> + Clone,
+    {
+        let aabb = Aabb::from_points(points.clone());
+        if aabb.is_empty() {
+            return BoundingSphere::new(Vec3::zero(), -1.0);
+        }
+
+        let center = aabb.center();
+        let radius_sq = points
+            .into_iter()
+            .map(|p| (*p - center).length_squared())
+            .fold(0.0, f32::max);
+
+        // squaring the rounded square root can come out just short of the farthest point
+        BoundingSphere::new(center, radius_sq.sqrt() * (1.0 + f32::EPSILON))
+    }
+
+    /// Creates the sphere that contains a box
+    ///
+    /// # Arguments
+    /// `aabb` - the box to enclose
+    pub fn from_aabb(aabb: &Aabb) -> BoundingSphere {
+        BoundingSphere::new(aabb.center(), aabb.half_extents().length())
+    }
+
+    /// returns the box that contains this sphere
+    pub fn to_aabb(&self) -> Aabb {
+        let r = Vec3::from_val(self.radius);
+        Aabb::new(self.center - r, self.center + r)
+    }
+
+    /// returns the smallest sphere containing both spheres
+    ///
+    /// # Arguments
+    /// `other` - the sphere to merge with
+    pub fn merge(&self, other: &BoundingSphere) -> BoundingSphere {
+        if other.radius < 0.0 || self.contains_sphere(other) {
+            return *self;
+        }
+        if self.radius < 0.0 || other.contains_sphere(self) {
+            return *other;
+        }
+
+        let offset = other.center - self.center;
+        let distance = offset.length();
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        let center = self.center + offset * ((radius - self.radius) / distance);
+
+        BoundingSphere::new(center, radius)
+    }
+
+    /// returns true when the point is inside or on the surface of the sphere
+    ///
+    /// # Arguments
+    /// `point` - the point to test
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        (*point - self.center).length_squared() <= self.radius * self.radius
+    }
+
+    /// returns true when the other sphere is entirely inside this sphere
+    ///
+    /// # Arguments
+    /// `other` - the sphere to test
+    pub fn contains_sphere(&self, other: &BoundingSphere) -> bool {
+        (other.center - self.center).length() + other.radius <= self.radius
+    }
+
+    /// returns true when the box is entirely inside this sphere
+    ///
+    /// # Arguments
+    /// `aabb` - the box to test
+    pub fn contains_aabb(&self, aabb: &Aabb) -> bool {
+        aabb.corners().iter().all(|c| self.contains_point(c))
+    }
+
+    /// returns true when the spheres overlap
+    ///
+    /// # Arguments
+    /// `other` - the sphere to test
+    pub fn intersects_sphere(&self, other: &BoundingSphere) -> bool {
+        let radii = self.radius + other.radius;
+        (other.center - self.center).length_squared() <= radii * radii
+    }
+
+    /// returns true when the sphere and the box overlap
+    ///
+    /// # Arguments
+    /// `aabb` - the box to test
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.contains_point(&aabb.closest_point(&self.center))
+    }
+
+    /// returns the sphere that contains this sphere after it has been transformed
+    ///
+    /// # Arguments
+    /// `m` - the matrix to transform by
+    ///
+    /// # Remarks
+    ///
+    /// Non-uniform scales stretch the sphere so the radius is scaled by the largest axis.
+    pub fn transform(&self, m: &Mat4) -> BoundingSphere {
+        let scale = m
+            .transform_vector(&Vec3::new(1.0, 0.0, 0.0))
+            .length_squared()
+            .max(m.transform_vector(&Vec3::new(0.0, 1.0, 0.0)).length_squared())
+            .max(m.transform_vector(&Vec3::new(0.0, 0.0, 1.0)).length_squared())
+            .sqrt();
+
+        BoundingSphere::new(m.transform_point(&self.center), self.radius * scale)
+    }
+}
+
+impl Obb {
+    /// Creates a new box
+    ///
+    /// # Arguments
+    /// `center` - the center of the box
+    /// `axes` - the local axes of the box, these are normalized
+    /// `half_extents` - half of the size of the box along each axis
+    pub fn new(center: Vec3, axes: [Vec3; 3], half_extents: Vec3) -> Obb {
+        Obb {
+            center: center,
+            axes: [axes[0].normalize(), axes[1].normalize(), axes[2].normalize()],
+            half_extents: half_extents,
+        }
+    }
+
+    /// Creates a box matching an axis aligned box
+    ///
+    /// # Arguments
+    /// `aabb` - the box to copy
+    pub fn from_aabb(aabb: &Aabb) -> Obb {
+        Obb::new(
+            aabb.center(),
+            [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+            aabb.half_extents(),
+        )
+    }
+
+    /// Creates a box that contains all of the points
+    /// the box is oriented along the directions the points are most spread out in
+    ///
+    /// # Arguments
+    /// `points` - the points to enclose
+    ///
+    /// # Remarks
+    ///
+    /// The orientation comes from the principal components of the points so it is a close
+    /// fit for most meshes but not always the smallest possible box.
+    pub fn from_points<'a, I>(points: I) -> Obb
+    where
+        I: IntoIterator<Item = &'a Vec3> + Clone,
+    {
+        let (sum, count) = points
+            .clone()
+            .into_iter()
+            .fold((Vec3::zero(), 0), |(sum, count), p| (sum + *p, count + 1));
+
+        if count == 0 {
+            return Obb::from_aabb(&Aabb::new(Vec3::zero(), Vec3::zero()));
+        }
+
+        let mean = sum / count as f32;
+        let mut covariance = [[0f32; 3]; 3];
+        for p in points.clone() {
+            let d = *p - mean;
+            for row in 0..3 {
+                for column in 0..3 {
+                    covariance[row][column] += d[row] * d[column] / count as f32;
+                }
+            }
+        }
+
+        let axes = principal_axes(covariance);
+        Obb::from_points_with_axes(points, axes)
+    }
+
+    /// Creates a box with the provided orientation that contains all of the points
+    ///
+    /// # Arguments
+    /// `points` - the points to enclose
+    /// `axes` - the local axes of the box, these should be perpendicular
+    pub fn from_points_with_axes<'a, I>(points: I, axes: [Vec3; 3]) -> Obb
+    where
+        I: IntoIterator<Item = &'a Vec3>,
+    {
+        let axes = [axes[0].normalize(), axes[1].normalize(), axes[2].normalize()];
+        let local = points
+            .into_iter()
+            .map(|p| Vec3::new(p.dot(&axes[0]), p.dot(&axes[1]), p.dot(&axes[2])))
+            .collect::<Vec<Vec3>>();
+        let bounds = Aabb::from_points(&local);
+
+        if bounds.is_empty() {
+            return Obb::new(Vec3::zero(), axes, Vec3::zero());
+        }
+
+        let c = bounds.center();
+        Obb {
+            center: axes[0] * c.x + axes[1] * c.y + axes[2] * c.z,
+            axes: axes,
+            half_extents: bounds.half_extents(),
+        }
+    }
+
+    /// returns the eight corners of the box
+    pub fn corners(&self) -> [Vec3; 8] {
+        let x = self.axes[0] * self.half_extents.x;
+        let y = self.axes[1] * self.half_extents.y;
+        let z = self.axes[2] * self.half_extents.z;
+        let c = self.center;
+        [
+            c - x - y - z,
+            c + x - y - z,
+            c - x + y - z,
+            c + x + y - z,
+            c - x - y + z,
+            c + x - y + z,
+            c - x + y + z,
+            c + x + y + z,
+        ]
+    }
+
+    /// returns the axis aligned box that contains this box
+    pub fn to_aabb(&self) -> Aabb {
+        Aabb::from_points(&self.corners())
+    }
+
+    /// returns a box containing both boxes
+    /// the result is fitted to the corners of both boxes
+    ///
+    /// # Arguments
+    /// `other` - the box to merge with
+    pub fn merge(&self, other: &Obb) -> Obb {
+        let mut corners = self.corners().to_vec();
+        corners.extend_from_slice(&other.corners());
+        Obb::from_points(&corners)
+    }
+
+    /// returns the point inside the box that is closest to the provided point
+    ///
+    /// # Arguments
+    /// `point` - the point to find the closest point to
+    pub fn closest_point(&self, point: &Vec3) -> Vec3 {
+        let d = *point - self.center;
+        (0..3).fold(self.center, |acc, i| {
+            let distance = d
+                .dot(&self.axes[i])
+                .max(-self.half_extents[i])
+                .min(self.half_extents[i]);
+            acc + self.axes[i] * distance
+        })
+    }
+
+    /// returns true when the point is inside or on the surface of the box
+    ///
+    /// # Arguments
+    /// `point` - the point to test
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        let d = *point - self.center;
+        (0..3).all(|i| d.dot(&self.axes[i]).abs() <= self.half_extents[i])
+    }
+
+    /// returns true when the other box is entirely inside this box
+    ///
+    /// # Arguments
+    /// `other` - the box to test
+    pub fn contains_obb(&self, other: &Obb) -> bool {
+        other.corners().iter().all(|c| self.contains_point(c))
+    }
+
+    /// returns true when the boxes overlap
+    ///
+    /// # Arguments
+    /// `other` - the box to test
+    ///
+    /// # Remarks
+    ///
+    /// This uses the separating axis theorem, the boxes do not overlap if there is any
+    /// axis where their projections don't overlap.
+    pub fn intersects_obb(&self, other: &Obb) -> bool {
+        let mut axes = Vec::with_capacity(15);
+        axes.extend_from_slice(&self.axes);
+        axes.extend_from_slice(&other.axes);
+        for a in self.axes.iter() {
+            for b in other.axes.iter() {
+                let axis = a.cross(b);
+                // parallel edges give no new axis to test
+                if axis.length_squared() > 1e-6 {
+                    axes.push(axis.normalize());
+                }
+            }
+        }
+
+        let offset = other.center - self.center;
+        axes.iter().all(|axis| {
+            let distance = offset.dot(axis).abs();
+            distance <= self.projected_radius(axis) + other.projected_radius(axis)
+        })
+    }
+
+    /// returns true when this box and an axis aligned box overlap
+    ///
+    /// # Arguments
+    /// `aabb` - the box to test
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.intersects_obb(&Obb::from_aabb(aabb))
+    }
+
+    /// returns true when the box and the sphere overlap
+    ///
+    /// # Arguments
+    /// `sphere` - the sphere to test
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        sphere.contains_point(&self.closest_point(&sphere.center))
+    }
+
+    /// returns the box after it has been transformed
+    ///
+    /// # Arguments
+    /// `m` - the matrix to transform by
+    ///
+    /// # Remarks
+    ///
+    /// Shearing transforms can't be represented by a box so the result will not be exact.
+    pub fn transform(&self, m: &Mat4) -> Obb {
+        let x = m.transform_vector(&(self.axes[0] * self.half_extents.x));
+        let y = m.transform_vector(&(self.axes[1] * self.half_extents.y));
+        let z = m.transform_vector(&(self.axes[2] * self.half_extents.z));
+
+        Obb {
+            center: m.transform_point(&self.center),
+            axes: [x.normalize(), y.normalize(), z.normalize()],
+            half_extents: Vec3::new(x.length(), y.length(), z.length()),
+        }
+    }
+
+    fn projected_radius(&self, axis: &Vec3) -> f32 {
+        (0..3).fold(0.0, |acc, i| {
+            acc + self.half_extents[i] * self.axes[i].dot(axis).abs()
+        })
+    }
+}
+
+// Finds the eigenvectors of a symmetric 3x3 matrix using Jacobi rotations,
+// for a covariance matrix these are the directions of greatest spread.
+fn principal_axes(matrix: [[f32; 3]; 3]) -> [Vec3; 3] {
+    let mut a = matrix;
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    for _ in 0..32 {
+        // pick the largest off diagonal element to zero out
+        let (p, q) = {
+            let candidates = [(0, 1), (0, 2), (1, 2)];
+            let mut largest = candidates[0];
+            for &(i, j) in candidates.iter() {
+                if a[i][j].abs() > a[largest.0][largest.1].abs() {
+                    largest = (i, j);
+                }
+            }
+            largest
+        };
+
+        if a[p][q].abs() < 1e-9 {
+            break;
+        }
+
+        let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+        let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+        let c = 1.0 / (t * t + 1.0).sqrt();
+        let s = t * c;
+
+        for k in 0..3 {
+            let (akp, akq) = (a[k][p], a[k][q]);
+            a[k][p] = c * akp - s * akq;
+            a[k][q] = s * akp + c * akq;
+        }
+        for k in 0..3 {
+            let (apk, aqk) = (a[p][k], a[q][k]);
+            a[p][k] = c * apk - s * aqk;
+            a[q][k] = s * apk + c * aqk;
+        }
+        for row in v.iter_mut() {
+            let (vp, vq) = (row[p], row[q]);
+            row[p] = c * vp - s * vq;
+            row[q] = s * vp + c * vq;
+        }
+    }
+
+    let x = Vec3::new(v[0][0], v[1][0], v[2][0]).normalize();
+    let y = Vec3::new(v[0][1], v[1][1], v[2][1]).normalize();
+    [x, y, x.cross(&y).normalize()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::matrix::Matrix;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_vec_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn points() -> Vec<Vec3> {
+        vec![
+            Vec3::new(1.0, -2.0, 3.0),
+            Vec3::new(-1.0, 4.0, 0.0),
+            Vec3::new(2.0, 0.0, -3.0),
+            Vec3::new(0.0, 1.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn volumes_from_points_enclose_every_point() {
+        let points = points();
+
+        let aabb = Aabb::from_points(&points);
+        assert_eq!(aabb, Aabb::new(Vec3::new(-1.0, -2.0, -3.0), Vec3::new(2.0, 4.0, 3.0)));
+        assert_eq!(aabb.center(), Vec3::new(0.5, 1.0, 0.0));
+        assert_eq!(aabb.size(), Vec3::new(3.0, 6.0, 6.0));
+
+        let sphere = BoundingSphere::from_points(&points);
+        assert_eq!(sphere.center, aabb.center());
+        assert!(points.iter().all(|p| sphere.contains_point(p)));
+        // the farthest point is on the surface
+        let farthest = (Vec3::new(1.0, -2.0, 3.0) - aabb.center()).length();
+        assert!((sphere.radius - farthest).abs() < 1e-5);
+
+        let obb = Obb::from_points(&points);
+        assert!(points.iter().all(|p| (obb.closest_point(p) - *p).length() < 1e-4));
+    }
+
+    #[test]
+    fn no_points_give_volumes_that_contain_nothing() {
+        let none: Vec<Vec3> = Vec::new();
+
+        assert!(Aabb::from_points(&none).is_empty());
+        assert!(!Aabb::empty().contains_point(&Vec3::zero()));
+        assert!(BoundingSphere::from_points(&none).radius < 0.0);
+        assert_eq!(Obb::from_points(&none).half_extents, Vec3::zero());
+    }
+
+    #[test]
+    fn merging_with_an_empty_volume_changes_nothing() {
+        let aabb = Aabb::from_points(&points());
+        assert_eq!(aabb.merge(&Aabb::empty()), aabb);
+        assert_eq!(Aabb::empty().merge(&aabb), aabb);
+        assert!(Aabb::empty().merge(&Aabb::empty()).is_empty());
+        assert_eq!(Aabb::empty().transform(&Mat4::rotation_x(1.0)), Aabb::empty());
+
+        let sphere = BoundingSphere::new(Vec3::new(1.0, 2.0, 3.0), 2.0);
+        let empty = BoundingSphere::from_points(&Vec::new());
+        assert_eq!(sphere.merge(&empty), sphere);
+        assert_eq!(empty.merge(&sphere), sphere);
+    }
+
+    #[test]
+    fn merged_volumes_contain_both_volumes() {
+        let a = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Vec3::new(2.0, -1.0, 0.5), Vec3::new(3.0, 0.5, 4.0));
+        let merged = a.merge(&b);
+        assert_eq!(merged, Aabb::new(Vec3::new(0.0, -1.0, 0.0), Vec3::new(3.0, 1.0, 4.0)));
+        assert!(merged.contains_aabb(&a) && merged.contains_aabb(&b));
+
+        let a = BoundingSphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0);
+        let b = BoundingSphere::new(Vec3::new(4.0, 0.0, 0.0), 1.0);
+        let merged = a.merge(&b);
+        assert_vec_close(merged.center, Vec3::new(2.0, 0.0, 0.0));
+        assert!((merged.radius - 3.0).abs() < 1e-5);
+        // a sphere inside the other merges to the larger sphere
+        let inner = BoundingSphere::new(Vec3::new(0.5, 0.0, 0.0), 0.5);
+        assert_eq!(a.merge(&inner), a);
+        assert_eq!(inner.merge(&a), a);
+    }
+
+    #[test]
+    fn points_on_the_surface_are_contained() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        assert!(aabb.contains_point(&Vec3::new(1.0, -1.0, 0.0)));
+        assert!(aabb.contains_point(&Vec3::new(1.0, 1.0, 1.0)));
+        assert!(!aabb.contains_point(&Vec3::new(1.0001, 0.0, 0.0)));
+
+        let sphere = BoundingSphere::new(Vec3::zero(), 2.0);
+        assert!(sphere.contains_point(&Vec3::new(0.0, -2.0, 0.0)));
+        assert!(!sphere.contains_point(&Vec3::new(0.0, -2.001, 0.0)));
+
+        let obb = Obb::from_aabb(&aabb).transform(&Mat4::rotation_z(0.5));
+        assert!(obb.contains_point(&obb.corners()[5]));
+        assert!(!obb.contains_point(&(obb.corners()[5] * 1.001)));
+    }
+
+    #[test]
+    fn volumes_touching_the_boundary_are_contained() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        assert!(aabb.contains_aabb(&aabb));
+        assert!(aabb.contains_aabb(&Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0))));
+        assert!(!aabb.contains_aabb(&Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.5, 1.0, 1.0))));
+        assert!(aabb.contains_sphere(&BoundingSphere::new(Vec3::zero(), 1.0)));
+        assert!(!aabb.contains_sphere(&BoundingSphere::new(Vec3::new(0.5, 0.0, 0.0), 1.0)));
+
+        let sphere = BoundingSphere::new(Vec3::zero(), 3.0f32.sqrt());
+        assert!(sphere.contains_aabb(&Aabb::new(Vec3::new(-0.999, -0.999, -0.999), Vec3::new(0.999, 0.999, 0.999))));
+        assert!(!sphere.contains_aabb(&Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.1, 1.0, 1.0))));
+        assert!(sphere.contains_sphere(&BoundingSphere::new(Vec3::new(0.7, 0.0, 0.0), 1.0)));
+        assert!(!sphere.contains_sphere(&BoundingSphere::new(Vec3::new(0.8, 0.0, 0.0), 1.0)));
+
+        let obb = Obb::from_aabb(&aabb);
+        assert!(obb.contains_obb(&obb));
+        assert!(!obb.contains_obb(&obb.transform(&Mat4::rotation_y(0.3))));
+    }
+
+    #[test]
+    fn touching_volumes_intersect() {
+        let a = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let touching = Aabb::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+        let apart = Aabb::new(Vec3::new(1.01, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+        assert!(a.intersects_aabb(&touching) && touching.intersects_aabb(&a));
+        assert!(!a.intersects_aabb(&apart));
+
+        let sphere = BoundingSphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0);
+        assert!(sphere.intersects_sphere(&BoundingSphere::new(Vec3::new(2.0, 0.0, 0.0), 1.0)));
+        assert!(!sphere.intersects_sphere(&BoundingSphere::new(Vec3::new(2.01, 0.0, 0.0), 1.0)));
+
+        let a = Obb::from_aabb(&a);
+        assert!(a.intersects_aabb(&touching));
+        assert!(!a.intersects_aabb(&apart));
+        // rotated 45 degrees the box reaches past the corner of the other box
+        let diamond = Obb::new(
+            Vec3::new(2.2, 0.5, 0.5),
+            [Vec3::new(1.0, 1.0, 0.0), Vec3::new(-1.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)],
+            Vec3::new(0.5, 0.5, 0.5),
+        );
+        assert!(!a.intersects_obb(&diamond));
+        let diamond = Obb { center: Vec3::new(1.6, 0.5, 0.5), ..diamond };
+        assert!(a.intersects_obb(&diamond) && diamond.intersects_obb(&a));
+    }
+
+    #[test]
+    fn spheres_and_boxes_overlap_by_the_closest_point() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+        // overlapping a face and an edge, and missing a corner that it reaches along each axis
+        let face = BoundingSphere::new(Vec3::new(1.5, 0.0, 0.0), 0.6);
+        let edge = BoundingSphere::new(Vec3::new(2.0, 2.0, 0.0), 1.5);
+        let corner = BoundingSphere::new(Vec3::new(2.0, 2.0, 2.0), 1.5);
+        assert!(aabb.intersects_sphere(&face) && face.intersects_aabb(&aabb));
+        assert!(aabb.intersects_sphere(&edge));
+        assert!(!aabb.intersects_sphere(&corner) && !corner.intersects_aabb(&aabb));
+        assert!(!aabb.intersects_sphere(&BoundingSphere::new(Vec3::new(0.0, -1.7, 0.0), 0.6)));
+        // a sphere inside the box overlaps it
+        assert!(aabb.intersects_sphere(&BoundingSphere::new(Vec3::zero(), 0.1)));
+
+        let obb = Obb::from_aabb(&aabb);
+        assert!(obb.intersects_sphere(&face));
+        assert!(!obb.intersects_sphere(&corner));
+    }
+
+    #[test]
+    fn a_quarter_turn_swaps_the_sides_of_a_box() {
+        let aabb = Aabb::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(3.0, 1.0, 4.0));
+        let rotated = aabb.transform(&Mat4::rotation_y(FRAC_PI_2));
+
+        // a right handed quarter turn around y takes +x to -z and +z to +x
+        assert_vec_close(rotated.min, Vec3::new(0.0, 0.0, -3.0));
+        assert_vec_close(rotated.max, Vec3::new(4.0, 1.0, -1.0));
+
+        let moved = aabb.transform(&Mat4::translation(&Vec3::new(1.0, 2.0, 3.0)));
+        assert_eq!(moved, Aabb::new(Vec3::new(2.0, 2.0, 3.0), Vec3::new(4.0, 3.0, 7.0)));
+    }
+
+    #[test]
+    fn rotating_a_box_by_less_than_a_quarter_turn_grows_it() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let rotated = aabb.transform(&Mat4::rotation_z(FRAC_PI_2 / 2.0));
+        let half = 2.0f32.sqrt();
+
+        assert_vec_close(rotated.max, Vec3::new(half, half, 1.0));
+        assert!(rotated.contains_aabb(&aabb));
+    }
+
+    #[test]
+    fn transformed_spheres_use_the_largest_scale() {
+        let sphere = BoundingSphere::new(Vec3::new(1.0, 0.0, 0.0), 2.0);
+        let m = Mat4::trs(
+            &Vec3::new(0.0, 5.0, 0.0),
+            &Mat4::rotation_z(FRAC_PI_2),
+            &Vec3::new(1.0, 3.0, 2.0),
+        );
+        let transformed = sphere.transform(&m);
+
+        assert_vec_close(transformed.center, Vec3::new(0.0, 6.0, 0.0));
+        assert!((transformed.radius - 6.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn a_rotated_box_is_recovered_from_its_corners() {
+        let rotation = Mat4::rotation(&Vec3::new(1.0, 2.0, 3.0), 0.8);
+        let expected = Obb::new(
+            Vec3::new(4.0, -2.0, 1.0),
+            [
+                rotation.transform_vector(&Vec3::new(1.0, 0.0, 0.0)),
+                rotation.transform_vector(&Vec3::new(0.0, 1.0, 0.0)),
+                rotation.transform_vector(&Vec3::new(0.0, 0.0, 1.0)),
+            ],
+            Vec3::new(3.0, 2.0, 0.5),
+        );
+
+        let obb = Obb::from_points(&expected.corners());
+        assert_vec_close(obb.center, expected.center);
+
+        // the axes come back in any order and either direction
+        for i in 0..3 {
+            let matching = (0..3)
+                .find(|&j| (obb.axes[j].dot(&expected.axes[i]).abs() - 1.0).abs() < 1e-3)
+                .expect("every axis of the box is recovered");
+            assert!((obb.half_extents[matching] - expected.half_extents[i]).abs() < 1e-3);
+        }
+        assert!((obb.axes[0].cross(&obb.axes[1]).dot(&obb.axes[2]) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn transformed_boxes_keep_their_shape() {
+        let obb = Obb::from_aabb(&Aabb::new(Vec3::new(-1.0, -2.0, -3.0), Vec3::new(1.0, 2.0, 3.0)));
+        let m = Mat4::trs(
+            &Vec3::new(1.0, 0.0, 0.0),
+            &Mat4::rotation_x(FRAC_PI_2),
+            &Vec3::new(2.0, 1.0, 1.0),
+        );
+        let transformed = obb.transform(&m);
+
+        assert_vec_close(transformed.center, Vec3::new(1.0, 0.0, 0.0));
+        assert_vec_close(transformed.half_extents, Vec3::new(2.0, 2.0, 3.0));
+        assert_vec_close(transformed.axes[1], Vec3::new(0.0, 0.0, 1.0));
+        assert_vec_close(transformed.to_aabb().max, Vec3::new(3.0, 3.0, 2.0));
+    }
+}
@@ -0,0 +1,225 @@
+//! A module containing a view Frustum.
+//! The frustum is the volume of space that a camera can see, anything entirely outside
+//! of it can be skipped when drawing.
+use math::bounds::{Aabb, BoundingSphere};
+use math::matrix::Mat4;
+use math::plane::Plane;
+use math::vector::Vec3;
+
+/// The result of testing a volume against a frustum
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Containment {
+    /// The volume is entirely inside the frustum
+    Inside,
+    /// The volume is entirely outside the frustum
+    Outside,
+    /// The volume is partly inside the frustum
+    Intersecting,
+}
+
+/// A view frustum represented by six planes that all face inwards
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frustum {
+    /// The planes of the frustum in the order left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum from a view projection matrix
+    ///
+    /// # Arguments
+    /// `view_projection` - the projection matrix multiplied by the view matrix,
+    /// such as `Matrix::perspective(..) * Matrix::view(..)`
+    ///
+    /// # Remarks
+    ///
+    /// This expects depth in the -1 to 1 range produced by `Matrix::perspective`. Projections
+    /// without a far plane return a far plane that everything is in front of.
+    pub fn from_matrix(view_projection: &Mat4) -> Frustum {
+        let r0 = view_projection.row(0);
+        let r1 = view_projection.row(1);
+        let r2 = view_projection.row(2);
+        let r3 = view_projection.row(3);
+
+        Frustum {
+            planes: [
+                Plane::from_coefficients(&(r3 + r0)),
+                Plane::from_coefficients(&(r3 - r0)),
+                Plane::from_coefficients(&(r3 + r1)),
+                Plane::from_coefficients(&(r3 - r1)),
+                Plane::from_coefficients(&(r3 + r2)),
+                Plane::from_coefficients(&(r3 - r2)),
+            ],
+        }
+    }
+
+    /// returns true when the point is inside the frustum
+    ///
+    /// # Arguments
+    /// `point` - the point to test
+    pub fn contains_point(&self, point: &Vec3) -> bool {
+        self.planes.iter().all(|p| p.distance_to(point) >= 0.0)
+    }
+
+    /// Classifies a sphere against the frustum
+    ///
+    /// # Arguments
+    /// `sphere` - the sphere to test
+    pub fn classify_sphere(&self, sphere: &BoundingSphere) -> Containment {
+        let mut result = Containment::Inside;
+
+        for plane in self.planes.iter() {
+            let distance = plane.distance_to(&sphere.center);
+            if distance < -sphere.radius {
+                return Containment::Outside;
+            }
+            if distance < sphere.radius {
+                result = Containment::Intersecting;
+            }
+        }
+
+        result
+    }
+
+    /// Classifies an axis aligned box against the frustum
+    ///
+    /// # Arguments
+    /// `aabb` - the box to test
+    ///
+    /// # Remarks
+    ///
+    /// This is conservative, a large box near a corner of the frustum may be reported as
+    /// intersecting when it is actually outside.
+    pub fn classify_aabb(&self, aabb: &Aabb) -> Containment {
+        if aabb.is_empty() {
+            return Containment::Outside;
+        }
+
+        let mut result = Containment::Inside;
+
+        for plane in self.planes.iter() {
+            // the corners furthest along and against the plane normal
+            let mut positive = aabb.min;
+            let mut negative = aabb.max;
+            for axis in 0..3 {
+                if plane.normal[axis] >= 0.0 {
+                    positive[axis] = aabb.max[axis];
+                    negative[axis] = aabb.min[axis];
+                }
+            }
+
+            if plane.distance_to(&positive) < 0.0 {
+                return Containment::Outside;
+            }
+            if plane.distance_to(&negative) < 0.0 {
+                result = Containment::Intersecting;
+            }
+        }
+
+        result
+    }
+
+    /// returns true when any part of the sphere may be inside the frustum
+    ///
+    /// # Arguments
+    /// `sphere` - the sphere to test
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.classify_sphere(sphere) != Containment::Outside
+    }
+
+    /// returns true when any part of the box may be inside the frustum
+    ///
+    /// # Arguments
+    /// `aabb` - the box to test
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.classify_aabb(aabb) != Containment::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::matrix::Matrix;
+    use math::vector::Vector;
+    use std::f32::consts::FRAC_PI_2;
+
+    // a camera at z = -10 looking along +z, seeing from z = -9 to z = 90
+    fn frustum() -> Frustum {
+        let projection = Mat4::perspective((800, 600), FRAC_PI_2, (1.0, 100.0));
+        let view = Mat4::view(
+            &Vec3::new(0.0, 0.0, -10.0),
+            &Vec3::new(0.0, 0.0, 1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        Frustum::from_matrix(&(projection * view))
+    }
+
+    #[test]
+    fn points_in_front_of_the_camera_are_inside() {
+        let frustum = frustum();
+
+        assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, 0.0)));
+        assert!(frustum.contains_point(&Vec3::new(5.0, -5.0, 0.0)));
+        assert!(frustum.contains_point(&Vec3::new(0.0, 0.0, 89.0)));
+    }
+
+    #[test]
+    fn points_behind_beside_or_beyond_the_camera_are_outside() {
+        let frustum = frustum();
+
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -20.0)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, -9.5)));
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 0.0, 91.0)));
+        // 10 units away the view is 10 units high and, at 4:3, 13.3 units wide
+        assert!(!frustum.contains_point(&Vec3::new(0.0, 10.5, 0.0)));
+        assert!(frustum.contains_point(&Vec3::new(13.0, 0.0, 0.0)));
+        assert!(!frustum.contains_point(&Vec3::new(-13.6, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn the_planes_face_inwards() {
+        let center = Vec3::new(0.0, 0.0, 40.0);
+
+        for plane in frustum().planes.iter() {
+            assert!((plane.normal.length() - 1.0).abs() < 1e-4);
+            assert!(plane.distance_to(&center) > 0.0);
+        }
+        // the near plane is one unit in front of the camera
+        assert!(frustum().planes[4].distance_to(&Vec3::new(0.0, 0.0, -9.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn spheres_are_classified_by_the_planes_they_cross() {
+        let frustum = frustum();
+
+        let inside = BoundingSphere::new(Vec3::new(0.0, 0.0, 10.0), 2.0);
+        let straddling_near = BoundingSphere::new(Vec3::new(0.0, 0.0, -9.0), 0.5);
+        let straddling_side = BoundingSphere::new(Vec3::new(0.0, 10.0, 0.0), 1.0);
+        let behind = BoundingSphere::new(Vec3::new(0.0, 0.0, -15.0), 2.0);
+
+        assert_eq!(frustum.classify_sphere(&inside), Containment::Inside);
+        assert_eq!(frustum.classify_sphere(&straddling_near), Containment::Intersecting);
+        assert_eq!(frustum.classify_sphere(&straddling_side), Containment::Intersecting);
+        assert_eq!(frustum.classify_sphere(&behind), Containment::Outside);
+        assert!(frustum.intersects_sphere(&straddling_near));
+        assert!(!frustum.intersects_sphere(&behind));
+    }
+
+    #[test]
+    fn boxes_are_classified_by_the_planes_they_cross() {
+        let frustum = frustum();
+
+        let inside = Aabb::new(Vec3::new(-1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 2.0));
+        let straddling_far = Aabb::new(Vec3::new(-1.0, -1.0, 85.0), Vec3::new(1.0, 1.0, 95.0));
+        let off_to_the_side = Aabb::new(Vec3::new(50.0, -1.0, -1.0), Vec3::new(60.0, 1.0, 1.0));
+        let around_the_camera = Aabb::new(Vec3::new(-1.0, -1.0, -11.0), Vec3::new(1.0, 1.0, -8.0));
+
+        assert_eq!(frustum.classify_aabb(&inside), Containment::Inside);
+        assert_eq!(frustum.classify_aabb(&straddling_far), Containment::Intersecting);
+        assert_eq!(frustum.classify_aabb(&off_to_the_side), Containment::Outside);
+        assert_eq!(frustum.classify_aabb(&around_the_camera), Containment::Intersecting);
+        assert_eq!(frustum.classify_aabb(&Aabb::empty()), Containment::Outside);
+        assert!(!frustum.intersects_aabb(&off_to_the_side));
+    }
+}
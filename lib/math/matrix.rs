@@ -1,10 +1,43 @@
 //! A collection of types and functions for representing Matrices
-use math::vector::Vec3;
+//!
+//! # Conventions
+//!
+//! Matrices are stored in column-major order, which is the order OpenGL (and glium) expect.
+//! The element at `(row, column)` lives at index `column * 4 + row` and
+//! [`Matrix::to_array`] returns the four columns in order, ready to be handed to a `uniform!`.
+//!
+//! Vectors are treated as columns so transforming a vector is written `matrix * vector`,
+//! and in `a * b` the transform `b` is applied first.
+//!
+//! [`Mat3`] follows the same conventions with element `(row, column)` at index `column * 3 + row`.
+use math::vector::{Vec2, Vec3, Vec4, Vector};
+use std::ops::{Index, IndexMut, Mul};
 
 // todo: Missing examples
 
-/// Represents a 4x4 matrix as an array of 16 floats.
-pub type Mat4x4 = [f32; 16];
+/// A 4x4 matrix stored as 16 floats in column-major order.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4 {
+    /// The elements of the matrix, element `(row, column)` is at index `column * 4 + row`
+    pub elements: [f32; 16],
+}
+
+/// A 3x3 matrix stored as 9 floats in column-major order.
+///
+/// This is used both for the rotation and scale part of a [`Mat4`], such as a normal matrix,
+/// and as a 2D affine transform where the third column holds the translation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat3 {
+    /// The elements of the matrix, element `(row, column)` is at index `column * 3 + row`
+    pub elements: [f32; 9],
+}
+
+/// Represents a 4x4 matrix.
+///
+/// # Remarks
+///
+/// This is kept as an alias of [`Mat4`] so existing code continues to compile.
+pub type Mat4x4 = Mat4;
 
 /// A trait that represents the functionality for a matrix
 pub trait Matrix {
@@ -21,6 +54,73 @@ pub trait Matrix {
     /// # Returns
     /// a Matrix
     fn perspective(dimensions: (u32, u32), fov: f32, z: (f32, f32)) -> Self;
+    /// Creates a perspective matrix with a reversed depth range
+    ///
+    /// # Arguments
+    ///
+    /// `dimensions` - a tuple containing the width and height of the provided dimensions
+    /// `fov` - the field of view for the view
+    /// `z` - a tuple containing the near and far z planes
+    ///
+    /// # Remarks
+    ///
+    /// The near plane is mapped to a depth of 1 and the far plane to 0, which spreads floating
+    /// point precision more evenly across large scenes. The depth buffer must be cleared to 0
+    /// and drawn with `DepthTest::IfMore`.
+    ///
+    /// The precision is only gained when clip space depth runs from 0 to 1, so draw with
+    /// `clip_control_depth: ClipControlDepth::ZeroToOne` in the `DrawParameters`
+    /// (`glClipControl`, which needs OpenGL 4.5 or `ARB_clip_control`). With OpenGL's default
+    /// range of -1 to 1 the depth is remapped to the same precision as [`Matrix::perspective`]
+    /// and half of the depth buffer goes unused.
+    fn perspective_reversed(dimensions: (u32, u32), fov: f32, z: (f32, f32)) -> Self;
+    /// Creates a perspective matrix without a far plane
+    ///
+    /// # Arguments
+    ///
+    /// `dimensions` - a tuple containing the width and height of the provided dimensions
+    /// `fov` - the field of view for the view
+    /// `near` - the near z plane
+    ///
+    /// # Remarks
+    ///
+    /// Points approach a depth of 1 as they move infinitely far away so nothing is clipped
+    /// for being too distant.
+    fn perspective_infinite(dimensions: (u32, u32), fov: f32, near: f32) -> Self;
+    /// Creates a perspective matrix without a far plane and with a reversed depth range
+    ///
+    /// # Arguments
+    ///
+    /// `dimensions` - a tuple containing the width and height of the provided dimensions
+    /// `fov` - the field of view for the view
+    /// `near` - the near z plane
+    ///
+    /// # Remarks
+    ///
+    /// The near plane is mapped to a depth of 1 and points approach 0 as they move infinitely
+    /// far away. As with [`Matrix::perspective_reversed`] the depth buffer must be cleared
+    /// to 0, drawn with `DepthTest::IfMore` and clip space depth must run from 0 to 1.
+    fn perspective_infinite_reversed(dimensions: (u32, u32), fov: f32, near: f32) -> Self;
+    /// Creates an orthographic matrix
+    ///
+    /// # Arguments
+    ///
+    /// `left`, `right` - the x coordinates mapped to the left and right edges of the view
+    /// `bottom`, `top` - the y coordinates mapped to the bottom and top edges of the view
+    /// `near`, `far` - the near and far z planes
+    fn ortho(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self;
+    /// Creates an orthographic matrix where one unit is one pixel
+    ///
+    /// # Arguments
+    ///
+    /// `dimensions` - a tuple containing the width and height of the provided dimensions
+    /// `z` - a tuple containing the near and far z planes
+    ///
+    /// # Remarks
+    ///
+    /// The origin is the top left corner of the view and y increases downwards,
+    /// matching window and UI coordinates.
+    fn ortho_pixels(dimensions: (u32, u32), z: (f32, f32)) -> Self;
     /// Creates a view matrix
     ///
     /// # Arguments
@@ -29,46 +129,341 @@ pub trait Matrix {
     /// `direction` - The direction the camera is facing
     /// `up` - The orientation of the camnera
     fn view(position: &Vec3, direction: &Vec3, up: &Vec3) -> Self;
+    /// Creates a view matrix that looks from one point towards another
+    ///
+    /// # Arguments
+    ///
+    /// `position` - The position of the "camera"
+    /// `target` - The point the camera is looking at
+    /// `up` - The orientation of the camera
+    fn look_at(position: &Vec3, target: &Vec3, up: &Vec3) -> Self;
+    /// Creates a translation matrix
+    ///
+    /// # Arguments
+    ///
+    /// `translation` - the offset to move by
+    fn translation(translation: &Vec3) -> Self;
+    /// Creates a matrix that rotates around the x axis
+    ///
+    /// # Arguments
+    ///
+    /// `angle` - the angle in radians, positive angles follow the right hand rule
+    fn rotation_x(angle: f32) -> Self;
+    /// Creates a matrix that rotates around the y axis
+    ///
+    /// # Arguments
+    ///
+    /// `angle` - the angle in radians, positive angles follow the right hand rule
+    fn rotation_y(angle: f32) -> Self;
+    /// Creates a matrix that rotates around the z axis
+    ///
+    /// # Arguments
+    ///
+    /// `angle` - the angle in radians, positive angles follow the right hand rule
+    fn rotation_z(angle: f32) -> Self;
+    /// Creates a matrix that rotates around an arbitrary axis
+    ///
+    /// # Arguments
+    ///
+    /// `axis` - the axis to rotate around, this does not need to be normalized
+    /// `angle` - the angle in radians, positive angles follow the right hand rule
+    fn rotation(axis: &Vec3, angle: f32) -> Self;
+    /// Creates a scale matrix
+    ///
+    /// # Arguments
+    ///
+    /// `scale` - the scale factor along each axis
+    fn scale(scale: &Vec3) -> Self;
+    /// Creates a matrix that scales, then rotates and then translates
+    ///
+    /// # Arguments
+    ///
+    /// `translation` - the offset to move by
+    /// `rotation` - a rotation matrix, such as one created by [`Matrix::rotation`]
+    /// `scale` - the scale factor along each axis
+    fn trs(translation: &Vec3, rotation: &Self, scale: &Vec3) -> Self;
     /// Converts the matrix into 4 slices of 4 point arrays
+    ///
+    /// # Remarks
+    ///
+    /// Each inner array is a column of the matrix.
     fn to_array(&self) -> [[f32; 4]; 4];
 }
 
-impl Matrix for Mat4x4 {
-    fn identity() -> Mat4x4 {
-        [
+impl Mat4 {
+    /// Creates a matrix from 16 elements in column-major order
+    ///
+    /// # Arguments
+    /// `elements` - the elements of the matrix, column by column
+    pub fn new(elements: [f32; 16]) -> Mat4 {
+        Mat4 { elements: elements }
+    }
+
+    /// Creates a matrix from its four columns
+    ///
+    /// # Arguments
+    /// `c0`, `c1`, `c2`, `c3` - the columns of the matrix from left to right
+    pub fn from_columns(c0: Vec4, c1: Vec4, c2: Vec4, c3: Vec4) -> Mat4 {
+        Mat4 {
+            elements: [
+                c0.x, c0.y, c0.z, c0.w, c1.x, c1.y, c1.z, c1.w, c2.x, c2.y, c2.z, c2.w, c3.x,
+                c3.y, c3.z, c3.w,
+            ],
+        }
+    }
+
+    /// Creates a matrix from its four rows
+    ///
+    /// # Arguments
+    /// `r0`, `r1`, `r2`, `r3` - the rows of the matrix from top to bottom
+    pub fn from_rows(r0: Vec4, r1: Vec4, r2: Vec4, r3: Vec4) -> Mat4 {
+        Mat4::from_columns(r0, r1, r2, r3).transpose()
+    }
+
+    /// Gets the element at the given row and column
+    ///
+    /// # Panics
+    /// when either `row` or `column` is greater than 3
+    pub fn get(&self, row: usize, column: usize) -> f32 {
+        assert!(row < 4 && column < 4, "Index out of range");
+        self.elements[column * 4 + row]
+    }
+
+    /// Gets a column of the matrix as a vector
+    ///
+    /// # Panics
+    /// when `index` is greater than 3
+    pub fn column(&self, index: usize) -> Vec4 {
+        Vec4::new(
+            self.get(0, index),
+            self.get(1, index),
+            self.get(2, index),
+            self.get(3, index),
+        )
+    }
+
+    /// Gets a row of the matrix as a vector
+    ///
+    /// # Panics
+    /// when `index` is greater than 3
+    pub fn row(&self, index: usize) -> Vec4 {
+        Vec4::new(
+            self.get(index, 0),
+            self.get(index, 1),
+            self.get(index, 2),
+            self.get(index, 3),
+        )
+    }
+
+    /// Transforms a point, this applies the translation of the matrix
+    ///
+    /// # Arguments
+    /// `point` - the point to transform
+    ///
+    /// # Remarks
+    ///
+    /// When the matrix contains a projection the result is divided by `w`.
+    pub fn transform_point(&self, point: &Vec3) -> Vec3 {
+        let v = *self * Vec4::new(point.x, point.y, point.z, 1.0);
+        if v.w == 1.0 || v.w == 0.0 {
+            v.xyz()
+        } else {
+            v.xyz() / v.w
+        }
+    }
+
+    /// Transforms a direction, this ignores the translation of the matrix
+    ///
+    /// # Arguments
+    /// `vector` - the direction to transform
+    pub fn transform_vector(&self, vector: &Vec3) -> Vec3 {
+        (*self * Vec4::new(vector.x, vector.y, vector.z, 0.0)).xyz()
+    }
+
+    /// Returns the transpose of the matrix, swapping its rows and columns
+    pub fn transpose(&self) -> Mat4 {
+        let m = &self.elements;
+        Mat4 {
+            elements: [
+                m[0], m[4], m[8], m[12], m[1], m[5], m[9], m[13], m[2], m[6], m[10], m[14], m[3],
+                m[7], m[11], m[15],
+            ],
+        }
+    }
+
+    /// Returns the determinant of the matrix
+    pub fn determinant(&self) -> f32 {
+        let (_, det) = self.cofactors();
+        det
+    }
+
+    /// Returns the inverse of the matrix
+    ///
+    /// # Returns
+    /// `None` when the matrix is singular (its determinant is zero) and cannot be inverted
+    pub fn inverse(&self) -> Option<Mat4> {
+        let (cofactors, det) = self.cofactors();
+
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let mut elements = [0f32; 16];
+        for (e, c) in elements.iter_mut().zip(cofactors.iter()) {
+            *e = c * inv_det;
+        }
+
+        Some(Mat4 { elements: elements })
+    }
+
+    // Computes the transposed cofactor (adjugate) matrix along with the determinant.
+    // The expansion is the same regardless of storage order since inverse(Mᵀ) == inverse(M)ᵀ.
+    fn cofactors(&self) -> ([f32; 16], f32) {
+        let m = &self.elements;
+        let mut inv = [0f32; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14]
+            + m[13] * m[6] * m[11]
+            - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14]
+            - m[12] * m[6] * m[11]
+            + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13]
+            + m[12] * m[5] * m[11]
+            - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13]
+            - m[12] * m[5] * m[10]
+            + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14]
+            - m[13] * m[2] * m[11]
+            + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14]
+            + m[12] * m[2] * m[11]
+            - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13]
+            - m[12] * m[1] * m[11]
+            + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13]
+            + m[12] * m[1] * m[10]
+            - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14]
+            + m[13] * m[2] * m[7]
+            - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14]
+            - m[12] * m[2] * m[7]
+            + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13]
+            + m[12] * m[1] * m[7]
+            - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13]
+            - m[12] * m[1] * m[6]
+            + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10]
+            - m[9] * m[2] * m[7]
+            + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10]
+            + m[8] * m[2] * m[7]
+            - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9]
+            - m[8] * m[1] * m[7]
+            + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9]
+            + m[8] * m[1] * m[6]
+            - m[8] * m[2] * m[5];
+
+        let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+
+        (inv, det)
+    }
+}
+
+impl Matrix for Mat4 {
+    fn identity() -> Mat4 {
+        Mat4::new([
             1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
-        ]
+        ])
     }
 
-    fn perspective(dimensions: (u32, u32), fov: f32, z: (f32, f32)) -> Mat4x4 {
-        let (width, height) = dimensions;
-        let aspect_ratio = height as f32 / width as f32;
+    fn perspective(dimensions: (u32, u32), fov: f32, z: (f32, f32)) -> Mat4 {
+        let (near, far) = z;
+
+        perspective_with_depth(
+            dimensions,
+            fov,
+            (far + near) / (far - near),
+            -(2.0 * far * near) / (far - near),
+        )
+    }
 
+    fn perspective_reversed(dimensions: (u32, u32), fov: f32, z: (f32, f32)) -> Mat4 {
         let (near, far) = z;
 
-        let f = 1.0 / (fov / 2.0).tan();
+        perspective_with_depth(
+            dimensions,
+            fov,
+            -near / (far - near),
+            (far * near) / (far - near),
+        )
+    }
 
-        [
-            f * aspect_ratio,
-            0.0,
-            0.0,
+    fn perspective_infinite(dimensions: (u32, u32), fov: f32, near: f32) -> Mat4 {
+        perspective_with_depth(dimensions, fov, 1.0, -2.0 * near)
+    }
+
+    fn perspective_infinite_reversed(dimensions: (u32, u32), fov: f32, near: f32) -> Mat4 {
+        perspective_with_depth(dimensions, fov, 0.0, near)
+    }
+
+    fn ortho(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+        let width = right - left;
+        let height = top - bottom;
+        let depth = far - near;
+
+        Mat4::new([
+            2.0 / width,
             0.0,
             0.0,
-            f,
             0.0,
             0.0,
+            2.0 / height,
             0.0,
             0.0,
-            (far + near) / (far - near),
-            1.0,
             0.0,
             0.0,
-            -(2.0 * far * near) / (far - near),
+            2.0 / depth,
             0.0,
-        ]
+            -(right + left) / width,
+            -(top + bottom) / height,
+            -(far + near) / depth,
+            1.0,
+        ])
     }
 
-    fn view(position: &Vec3, direction: &Vec3, up: &Vec3) -> Mat4x4 {
+    fn ortho_pixels(dimensions: (u32, u32), z: (f32, f32)) -> Mat4 {
+        let (width, height) = dimensions;
+        let (near, far) = z;
+
+        Mat4::ortho(0.0, width as f32, height as f32, 0.0, near, far)
+    }
+
+    fn view(position: &Vec3, direction: &Vec3, up: &Vec3) -> Mat4 {
         let f = {
             let f = direction;
             let len = f[0] * f[0] + f[1] * f[1] + f[2] * f[2];
@@ -100,10 +495,95 @@ impl Matrix for Mat4x4 {
             -position[0] * f[0] - position[1] * f[1] - position[2] * f[2],
         ];
 
-        [
+        Mat4::new([
             s_norm[0], u[0], f[0], 0.0, s_norm[1], u[1], f[1], 0.0, s_norm[2], u[2], f[2], 0.0,
             p[0], p[1], p[2], 1.0,
-        ]
+        ])
+    }
+
+    fn look_at(position: &Vec3, target: &Vec3, up: &Vec3) -> Mat4 {
+        Mat4::view(position, &(*target - *position), up)
+    }
+
+    fn translation(translation: &Vec3) -> Mat4 {
+        Mat4::new([
+            1.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            0.0,
+            translation.x,
+            translation.y,
+            translation.z,
+            1.0,
+        ])
+    }
+
+    fn rotation_x(angle: f32) -> Mat4 {
+        let (sin, cos) = angle.sin_cos();
+
+        Mat4::new([
+            1.0, 0.0, 0.0, 0.0, 0.0, cos, sin, 0.0, 0.0, -sin, cos, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ])
+    }
+
+    fn rotation_y(angle: f32) -> Mat4 {
+        let (sin, cos) = angle.sin_cos();
+
+        Mat4::new([
+            cos, 0.0, -sin, 0.0, 0.0, 1.0, 0.0, 0.0, sin, 0.0, cos, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ])
+    }
+
+    fn rotation_z(angle: f32) -> Mat4 {
+        let (sin, cos) = angle.sin_cos();
+
+        Mat4::new([
+            cos, sin, 0.0, 0.0, -sin, cos, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ])
+    }
+
+    fn rotation(axis: &Vec3, angle: f32) -> Mat4 {
+        let Vec3 { x, y, z } = axis.normalize();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+
+        Mat4::new([
+            t * x * x + cos,
+            t * x * y + sin * z,
+            t * x * z - sin * y,
+            0.0,
+            t * x * y - sin * z,
+            t * y * y + cos,
+            t * y * z + sin * x,
+            0.0,
+            t * x * z + sin * y,
+            t * y * z - sin * x,
+            t * z * z + cos,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ])
+    }
+
+    fn scale(scale: &Vec3) -> Mat4 {
+        Mat4::new([
+            scale.x, 0.0, 0.0, 0.0, 0.0, scale.y, 0.0, 0.0, 0.0, 0.0, scale.z, 0.0, 0.0, 0.0, 0.0,
+            1.0,
+        ])
+    }
+
+    fn trs(translation: &Vec3, rotation: &Mat4, scale: &Vec3) -> Mat4 {
+        Mat4::translation(translation) * *rotation * Mat4::scale(scale)
     }
 
     fn to_array(&self) -> [[f32; 4]; 4] {
@@ -115,3 +595,760 @@ impl Matrix for Mat4x4 {
         ]
     }
 }
+
+// All of the perspective projections share the same x, y and w rows and only
+// differ in how z is mapped into the depth range: depth = z_scale + z_offset / z
+fn perspective_with_depth(dimensions: (u32, u32), fov: f32, z_scale: f32, z_offset: f32) -> Mat4 {
+    let (width, height) = dimensions;
+    let aspect_ratio = height as f32 / width as f32;
+
+    let f = 1.0 / (fov / 2.0).tan();
+
+    Mat4::new([
+        f * aspect_ratio,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        f,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        z_scale,
+        1.0,
+        0.0,
+        0.0,
+        z_offset,
+        0.0,
+    ])
+}
+
+impl Default for Mat4 {
+    fn default() -> Mat4 {
+        Mat4::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut elements = [0f32; 16];
+
+        for column in 0..4 {
+            for row in 0..4 {
+                elements[column * 4 + row] = (0..4)
+                    .map(|k| self.get(row, k) * rhs.get(k, column))
+                    .sum();
+            }
+        }
+
+        Mat4 { elements: elements }
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+
+    fn mul(self, rhs: Vec4) -> Vec4 {
+        let m = &self.elements;
+        Vec4 {
+            x: m[0] * rhs.x + m[4] * rhs.y + m[8] * rhs.z + m[12] * rhs.w,
+            y: m[1] * rhs.x + m[5] * rhs.y + m[9] * rhs.z + m[13] * rhs.w,
+            z: m[2] * rhs.x + m[6] * rhs.y + m[10] * rhs.z + m[14] * rhs.w,
+            w: m[3] * rhs.x + m[7] * rhs.y + m[11] * rhs.z + m[15] * rhs.w,
+        }
+    }
+}
+
+impl Index<usize> for Mat4 {
+    type Output = f32;
+
+    fn index(&self, index: usize) -> &f32 {
+        &self.elements[index]
+    }
+}
+
+impl IndexMut<usize> for Mat4 {
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        &mut self.elements[index]
+    }
+}
+
+impl From<[f32; 16]> for Mat4 {
+    fn from(elements: [f32; 16]) -> Mat4 {
+        Mat4::new(elements)
+    }
+}
+
+impl From<Mat4> for [f32; 16] {
+    fn from(m: Mat4) -> [f32; 16] {
+        m.elements
+    }
+}
+
+impl From<[[f32; 4]; 4]> for Mat4 {
+    fn from(columns: [[f32; 4]; 4]) -> Mat4 {
+        Mat4::from_columns(
+            Vec4::from(columns[0]),
+            Vec4::from(columns[1]),
+            Vec4::from(columns[2]),
+            Vec4::from(columns[3]),
+        )
+    }
+}
+
+impl From<Mat4> for [[f32; 4]; 4] {
+    fn from(m: Mat4) -> [[f32; 4]; 4] {
+        m.to_array()
+    }
+}
+
+impl Mat3 {
+    /// Creates a matrix from 9 elements in column-major order
+    ///
+    /// # Arguments
+    /// `elements` - the elements of the matrix, column by column
+    pub fn new(elements: [f32; 9]) -> Mat3 {
+        Mat3 { elements: elements }
+    }
+
+    /// Creates an identity matrix
+    pub fn identity() -> Mat3 {
+        Mat3::new([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+    }
+
+    /// Creates a matrix from its three columns
+    ///
+    /// # Arguments
+    /// `c0`, `c1`, `c2` - the columns of the matrix from left to right
+    pub fn from_columns(c0: Vec3, c1: Vec3, c2: Vec3) -> Mat3 {
+        Mat3::new([c0.x, c0.y, c0.z, c1.x, c1.y, c1.z, c2.x, c2.y, c2.z])
+    }
+
+    /// Creates a matrix from the upper left 3x3 of a 4x4 matrix
+    /// this keeps the rotation and scale but drops the translation and projection
+    ///
+    /// # Arguments
+    /// `m` - the matrix to take the elements from
+    pub fn from_mat4(m: &Mat4) -> Mat3 {
+        let m = &m.elements;
+        Mat3::new([m[0], m[1], m[2], m[4], m[5], m[6], m[8], m[9], m[10]])
+    }
+
+    /// Creates a matrix that transforms normals by the provided model view matrix
+    ///
+    /// # Arguments
+    /// `modelview` - the matrix that the positions are transformed by
+    ///
+    /// # Returns
+    /// `None` when the matrix is singular and cannot be inverted
+    pub fn normal_matrix(modelview: &Mat4) -> Option<Mat3> {
+        Mat3::from_mat4(modelview).inverse_transpose()
+    }
+
+    /// Creates a 2D translation matrix
+    ///
+    /// # Arguments
+    /// `translation` - the offset to move by
+    pub fn translation_2d(translation: &Vec2) -> Mat3 {
+        Mat3::new([
+            1.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            0.0,
+            translation.x,
+            translation.y,
+            1.0,
+        ])
+    }
+
+    /// Creates a 2D rotation matrix
+    ///
+    /// # Arguments
+    /// `angle` - the angle in radians, positive angles rotate from the x axis towards the y axis
+    pub fn rotation_2d(angle: f32) -> Mat3 {
+        let (sin, cos) = angle.sin_cos();
+
+        Mat3::new([cos, sin, 0.0, -sin, cos, 0.0, 0.0, 0.0, 1.0])
+    }
+
+    /// Creates a 2D scale matrix
+    ///
+    /// # Arguments
+    /// `scale` - the scale factor along each axis
+    pub fn scale_2d(scale: &Vec2) -> Mat3 {
+        Mat3::new([scale.x, 0.0, 0.0, 0.0, scale.y, 0.0, 0.0, 0.0, 1.0])
+    }
+
+    /// Transforms a 2D point, this applies the translation of the matrix
+    ///
+    /// # Arguments
+    /// `point` - the point to transform
+    pub fn transform_point_2d(&self, point: &Vec2) -> Vec2 {
+        let v = *self * Vec3::new(point.x, point.y, 1.0);
+        Vec2::new(v.x, v.y)
+    }
+
+    /// Transforms a 2D direction, this ignores the translation of the matrix
+    ///
+    /// # Arguments
+    /// `vector` - the direction to transform
+    pub fn transform_vector_2d(&self, vector: &Vec2) -> Vec2 {
+        let v = *self * Vec3::new(vector.x, vector.y, 0.0);
+        Vec2::new(v.x, v.y)
+    }
+
+    /// Gets the element at the given row and column
+    ///
+    /// # Panics
+    /// when either `row` or `column` is greater than 2
+    pub fn get(&self, row: usize, column: usize) -> f32 {
+        assert!(row < 3 && column < 3, "Index out of range");
+        self.elements[column * 3 + row]
+    }
+
+    /// Gets a column of the matrix as a vector
+    ///
+    /// # Panics
+    /// when `index` is greater than 2
+    pub fn column(&self, index: usize) -> Vec3 {
+        Vec3::new(self.get(0, index), self.get(1, index), self.get(2, index))
+    }
+
+    /// Returns the transpose of the matrix, swapping its rows and columns
+    pub fn transpose(&self) -> Mat3 {
+        let m = &self.elements;
+        Mat3::new([m[0], m[3], m[6], m[1], m[4], m[7], m[2], m[5], m[8]])
+    }
+
+    /// Returns the determinant of the matrix
+    pub fn determinant(&self) -> f32 {
+        let c0 = self.column(0);
+        let c1 = self.column(1);
+        let c2 = self.column(2);
+
+        c0.dot(&c1.cross(&c2))
+    }
+
+    /// Returns the inverse of the matrix
+    ///
+    /// # Returns
+    /// `None` when the matrix is singular (its determinant is zero) and cannot be inverted
+    pub fn inverse(&self) -> Option<Mat3> {
+        self.inverse_transpose().map(|m| m.transpose())
+    }
+
+    /// Returns the transpose of the inverse of the matrix
+    /// when built from a model view matrix this is the matrix used to transform normals
+    ///
+    /// # Returns
+    /// `None` when the matrix is singular (its determinant is zero) and cannot be inverted
+    pub fn inverse_transpose(&self) -> Option<Mat3> {
+        let c0 = self.column(0);
+        let c1 = self.column(1);
+        let c2 = self.column(2);
+
+        let det = c0.dot(&c1.cross(&c2));
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+
+        // the columns of the inverse transpose are the cross products of the
+        // other two columns, scaled by the determinant
+        let inv_det = 1.0 / det;
+        Some(Mat3::from_columns(
+            c1.cross(&c2) * inv_det,
+            c2.cross(&c0) * inv_det,
+            c0.cross(&c1) * inv_det,
+        ))
+    }
+
+    /// Converts the matrix into 3 slices of 3 point arrays
+    ///
+    /// # Remarks
+    ///
+    /// Each inner array is a column of the matrix.
+    pub fn to_array(&self) -> [[f32; 3]; 3] {
+        [
+            [self[0], self[1], self[2]],
+            [self[3], self[4], self[5]],
+            [self[6], self[7], self[8]],
+        ]
+    }
+}
+
+impl Default for Mat3 {
+    fn default() -> Mat3 {
+        Mat3::identity()
+    }
+}
+
+impl Mul for Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, rhs: Mat3) -> Mat3 {
+        let mut elements = [0f32; 9];
+
+        for column in 0..3 {
+            for row in 0..3 {
+                elements[column * 3 + row] = (0..3)
+                    .map(|k| self.get(row, k) * rhs.get(k, column))
+                    .sum();
+            }
+        }
+
+        Mat3 { elements: elements }
+    }
+}
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        let m = &self.elements;
+        Vec3 {
+            x: m[0] * rhs.x + m[3] * rhs.y + m[6] * rhs.z,
+            y: m[1] * rhs.x + m[4] * rhs.y + m[7] * rhs.z,
+            z: m[2] * rhs.x + m[5] * rhs.y + m[8] * rhs.z,
+        }
+    }
+}
+
+impl Index<usize> for Mat3 {
+    type Output = f32;
+
+    fn index(&self, index: usize) -> &f32 {
+        &self.elements[index]
+    }
+}
+
+impl IndexMut<usize> for Mat3 {
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        &mut self.elements[index]
+    }
+}
+
+impl From<Mat4> for Mat3 {
+    fn from(m: Mat4) -> Mat3 {
+        Mat3::from_mat4(&m)
+    }
+}
+
+impl From<Mat3> for [[f32; 3]; 3] {
+    fn from(m: Mat3) -> [[f32; 3]; 3] {
+        m.to_array()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Mat4, b: &Mat4) {
+        for (x, y) in a.elements.iter().zip(b.elements.iter()) {
+            assert!((x - y).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    // a matrix with every element different, element (row, column) is `row * 4 + column`
+    fn counting() -> Mat4 {
+        Mat4::from_rows(
+            Vec4::new(0.0, 1.0, 2.0, 3.0),
+            Vec4::new(4.0, 5.0, 6.0, 7.0),
+            Vec4::new(8.0, 9.0, 10.0, 11.0),
+            Vec4::new(12.0, 13.0, 14.0, 15.0),
+        )
+    }
+
+    #[test]
+    fn to_array_returns_columns() {
+        let m = counting();
+
+        assert_eq!(m.get(1, 2), 6.0);
+        assert_eq!(m[2 * 4 + 1], 6.0);
+        assert_eq!(m.row(1), Vec4::new(4.0, 5.0, 6.0, 7.0));
+        assert_eq!(m.column(1), Vec4::new(1.0, 5.0, 9.0, 13.0));
+        assert_eq!(
+            m.to_array(),
+            [
+                [0.0, 4.0, 8.0, 12.0],
+                [1.0, 5.0, 9.0, 13.0],
+                [2.0, 6.0, 10.0, 14.0],
+                [3.0, 7.0, 11.0, 15.0],
+            ]
+        );
+        assert_eq!(Mat4::from(m.to_array()), m);
+    }
+
+    #[test]
+    fn translation_is_in_the_last_column() {
+        let m = Mat4::translation(&Vec3::new(1.0, 2.0, 3.0));
+
+        assert_eq!(m.to_array()[3], [1.0, 2.0, 3.0, 1.0]);
+        assert_eq!(m.transform_point(&Vec3::zero()), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(m.transform_vector(&Vec3::new(1.0, 0.0, 0.0)), Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn the_right_hand_matrix_is_applied_first() {
+        let translate = Mat4::translation(&Vec3::new(1.0, 0.0, 0.0));
+        let scale = Mat4::scale(&Vec3::from_val(2.0));
+        let point = Vec3::new(1.0, 1.0, 1.0);
+
+        assert_eq!((translate * scale).transform_point(&point), Vec3::new(3.0, 2.0, 2.0));
+        assert_eq!((scale * translate).transform_point(&point), Vec3::new(4.0, 2.0, 2.0));
+        assert_eq!(
+            (translate * scale).transform_point(&point),
+            translate.transform_point(&scale.transform_point(&point))
+        );
+    }
+
+    fn assert_vec_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn quarter_turns_follow_the_right_hand_rule() {
+        use std::f32::consts::FRAC_PI_2;
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let y = Vec3::new(0.0, 1.0, 0.0);
+        let z = Vec3::new(0.0, 0.0, 1.0);
+
+        assert_vec_close(Mat4::rotation_x(FRAC_PI_2).transform_vector(&y), z);
+        assert_vec_close(Mat4::rotation_x(FRAC_PI_2).transform_vector(&z), -y);
+        // in the left handed view space, where +z is forwards, a positive turn around y
+        // takes +x (right) to -z (backwards) and +z (forwards) to +x
+        assert_vec_close(Mat4::rotation_y(FRAC_PI_2).transform_vector(&x), -z);
+        assert_vec_close(Mat4::rotation_y(FRAC_PI_2).transform_vector(&z), x);
+        assert_vec_close(Mat4::rotation_z(FRAC_PI_2).transform_vector(&x), y);
+        assert_vec_close(Mat4::rotation_z(FRAC_PI_2).transform_vector(&y), -x);
+
+        // rotations don't move the origin or change lengths
+        let m = Mat4::rotation_y(1.0);
+        assert_vec_close(m.transform_point(&Vec3::zero()), Vec3::zero());
+        assert!((m.transform_vector(&Vec3::new(1.0, 2.0, 3.0)).length() - 14.0f32.sqrt()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rotation_around_an_axis_matches_the_rotations_around_each_axis() {
+        for &angle in &[0.0, 0.4, -1.3, 3.0] {
+            assert_close(&Mat4::rotation(&Vec3::new(1.0, 0.0, 0.0), angle), &Mat4::rotation_x(angle));
+            assert_close(&Mat4::rotation(&Vec3::new(0.0, 1.0, 0.0), angle), &Mat4::rotation_y(angle));
+            assert_close(&Mat4::rotation(&Vec3::new(0.0, 0.0, 1.0), angle), &Mat4::rotation_z(angle));
+            // the axis is normalized
+            assert_close(&Mat4::rotation(&Vec3::new(0.0, 3.0, 0.0), angle), &Mat4::rotation_y(angle));
+        }
+
+        // a third of a turn around the diagonal cycles the axes
+        let m = Mat4::rotation(&Vec3::new(1.0, 1.0, 1.0), 2.0 * ::std::f32::consts::PI / 3.0);
+        assert_vec_close(m.transform_vector(&Vec3::new(1.0, 0.0, 0.0)), Vec3::new(0.0, 1.0, 0.0));
+        assert_vec_close(m.transform_vector(&Vec3::new(0.0, 1.0, 0.0)), Vec3::new(0.0, 0.0, 1.0));
+        // the axis itself doesn't move
+        assert_vec_close(m.transform_vector(&Vec3::new(2.0, 2.0, 2.0)), Vec3::new(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn trs_scales_then_rotates_then_translates() {
+        let translation = Vec3::new(3.0, -2.0, 5.0);
+        let rotation = Mat4::rotation_z(::std::f32::consts::FRAC_PI_2);
+        let scale = Vec3::new(2.0, 3.0, 4.0);
+        let m = Mat4::trs(&translation, &rotation, &scale);
+
+        assert_close(
+            &m,
+            &(Mat4::translation(&translation) * rotation * Mat4::scale(&scale)),
+        );
+        // (1, 1, 1) is scaled to (2, 3, 4), turned to (-3, 2, 4) and moved to (0, 0, 9)
+        assert_vec_close(m.transform_point(&Vec3::new(1.0, 1.0, 1.0)), Vec3::new(0.0, 0.0, 9.0));
+        assert_eq!(Mat4::scale(&Vec3::new(2.0, 3.0, 4.0)).transform_point(&Vec3::from_val(1.0)), scale);
+    }
+
+    #[test]
+    fn look_at_faces_the_target() {
+        let eye = Vec3::new(1.0, 2.0, 3.0);
+        let target = Vec3::new(-2.0, 0.0, 7.0);
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let m = Mat4::look_at(&eye, &target, &up);
+
+        assert_close(&m, &Mat4::view(&eye, &(target - eye), &up));
+        // the eye is the origin of the view and the target is straight ahead along +z
+        assert_vec_close(m.transform_point(&eye), Vec3::zero());
+        assert_vec_close(
+            m.transform_point(&target),
+            Vec3::new(0.0, 0.0, (target - eye).length()),
+        );
+        // up stays up when looking along the horizon
+        let level = Mat4::look_at(&Vec3::zero(), &Vec3::new(5.0, 0.0, 0.0), &up);
+        assert_vec_close(level.transform_vector(&up), up);
+    }
+
+    #[test]
+    fn multiplying_matrices() {
+        let m = counting();
+
+        assert_eq!(m * Mat4::identity(), m);
+        assert_eq!(Mat4::identity() * m, m);
+        // element (row, column) is the dot product of the row of one and the column of the other
+        let product = m * m.transpose();
+        for row in 0..4 {
+            for column in 0..4 {
+                assert_eq!(product.get(row, column), m.row(row).dot(&m.row(column)));
+            }
+        }
+    }
+
+    #[test]
+    fn multiplying_a_vector() {
+        let v = counting() * Vec4::new(1.0, 0.0, -1.0, 2.0);
+        assert_eq!(v, Vec4::new(4.0, 12.0, 20.0, 28.0));
+
+        let x = Vec4::new(1.0, 0.0, 0.0, 0.0);
+        let rotated = Mat4::rotation_z(::std::f32::consts::FRAC_PI_2) * x;
+        assert!((rotated - Vec4::new(0.0, 1.0, 0.0, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let m = counting();
+        let t = m.transpose();
+
+        for i in 0..4 {
+            assert_eq!(t.row(i), m.column(i));
+        }
+        assert_eq!(t.transpose(), m);
+        assert_eq!(Mat4::from_columns(m.row(0), m.row(1), m.row(2), m.row(3)), t);
+    }
+
+    #[test]
+    fn inverse_round_trips_to_identity() {
+        let m = Mat4::trs(
+            &Vec3::new(3.0, -2.0, 5.0),
+            &Mat4::rotation(&Vec3::new(1.0, 2.0, 3.0).normalize(), 0.7),
+            &Vec3::new(2.0, 0.5, 4.0),
+        );
+        let inverse = m.inverse().unwrap();
+
+        assert_close(&(m * inverse), &Mat4::identity());
+        assert_close(&(inverse * m), &Mat4::identity());
+        assert!((m.determinant() - 4.0).abs() < 1e-4);
+        assert_eq!(Mat4::identity().inverse(), Some(Mat4::identity()));
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert_eq!(counting().determinant(), 0.0);
+        assert_eq!(counting().inverse(), None);
+        assert_eq!(Mat4::scale(&Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+        assert_eq!(Mat4::new([0.0; 16]).inverse(), None);
+    }
+
+    // projects a point in view space and returns it in normalized device coordinates
+    fn project(m: &Mat4, x: f32, y: f32, z: f32) -> Vec3 {
+        let clip = *m * Vec4::new(x, y, z, 1.0);
+        Vec3::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w)
+    }
+
+    fn assert_depth(m: &Mat4, z: f32, depth: f32) {
+        let projected = project(m, 0.0, 0.0, z);
+        assert!(
+            (projected.z - depth).abs() < 1e-4,
+            "z {} projected to {} not {}",
+            z,
+            projected.z,
+            depth
+        );
+    }
+
+    const FOV: f32 = std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn perspective_maps_near_and_far_to_minus_one_and_one() {
+        let m = Mat4::perspective((800, 600), FOV, (1.0, 100.0));
+
+        assert_depth(&m, 1.0, -1.0);
+        assert_depth(&m, 100.0, 1.0);
+        // depth = (f + n) / (f - n) - 2fn / ((f - n) z)
+        assert_depth(&m, 2.0, 101.0 / 99.0 - 100.0 / 99.0);
+    }
+
+    #[test]
+    fn perspective_maps_the_edges_of_the_view() {
+        let m = Mat4::perspective((800, 600), FOV, (1.0, 100.0));
+
+        // with a 90 degree field of view the top edge is as far up as the point is away
+        let top = project(&m, 0.0, 10.0, 10.0);
+        assert!((top.y - 1.0).abs() < 1e-5);
+        // the width is scaled by the aspect ratio
+        let right = project(&m, 10.0 * 800.0 / 600.0, 0.0, 10.0);
+        assert!((right.x - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn infinite_perspective_approaches_one() {
+        let m = Mat4::perspective_infinite((800, 600), FOV, 1.0);
+
+        assert_depth(&m, 1.0, -1.0);
+        assert_depth(&m, 2.0, 0.0);
+        assert_depth(&m, 1.0e6, 1.0);
+        assert!(project(&m, 0.0, 0.0, 1.0e6).z < 1.0);
+    }
+
+    #[test]
+    fn reversed_perspective_maps_near_and_far_to_one_and_zero() {
+        let m = Mat4::perspective_reversed((800, 600), FOV, (1.0, 100.0));
+
+        assert_depth(&m, 1.0, 1.0);
+        assert_depth(&m, 100.0, 0.0);
+        // depth = n (f - z) / ((f - n) z)
+        assert_depth(&m, 2.0, 98.0 / 198.0);
+    }
+
+    #[test]
+    fn infinite_reversed_perspective_approaches_zero() {
+        let m = Mat4::perspective_infinite_reversed((800, 600), FOV, 0.5);
+
+        assert_depth(&m, 0.5, 1.0);
+        assert_depth(&m, 2.0, 0.25);
+        assert_depth(&m, 1.0e6, 0.0);
+        assert!(project(&m, 0.0, 0.0, 1.0e6).z > 0.0);
+    }
+
+    #[test]
+    fn reversed_perspectives_share_the_view_with_perspective() {
+        let perspective = Mat4::perspective((800, 600), FOV, (1.0, 100.0));
+        let reversed = Mat4::perspective_reversed((800, 600), FOV, (1.0, 100.0));
+        let infinite = Mat4::perspective_infinite_reversed((800, 600), FOV, 1.0);
+
+        for m in &[reversed, infinite] {
+            let a = project(&perspective, 3.0, -4.0, 10.0);
+            let b = project(m, 3.0, -4.0, 10.0);
+            assert!((a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn ortho_maps_the_box_to_the_unit_cube() {
+        let m = Mat4::ortho(-2.0, 6.0, -1.0, 3.0, 1.0, 11.0);
+
+        assert_eq!(project(&m, -2.0, -1.0, 1.0), Vec3::new(-1.0, -1.0, -1.0));
+        assert_eq!(project(&m, 6.0, 3.0, 11.0), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(project(&m, 2.0, 1.0, 6.0), Vec3::new(0.0, 0.0, 0.0));
+
+        // pixel coordinates have y going down the screen
+        let pixels = Mat4::ortho_pixels((800, 600), (0.0, 1.0));
+        assert_eq!(project(&pixels, 0.0, 0.0, 0.0), Vec3::new(-1.0, 1.0, -1.0));
+        assert_eq!(project(&pixels, 800.0, 600.0, 1.0), Vec3::new(1.0, -1.0, 1.0));
+    }
+
+    fn assert_mat3_close(a: &Mat3, b: &Mat3) {
+        for i in 0..9 {
+            assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn assert_vec2_close(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn mat3_to_array_returns_columns() {
+        let m = Mat3::from_columns(
+            Vec3::new(0.0, 1.0, 2.0),
+            Vec3::new(3.0, 4.0, 5.0),
+            Vec3::new(6.0, 7.0, 8.0),
+        );
+
+        assert_eq!(m.get(2, 1), 5.0);
+        assert_eq!(m.column(2), Vec3::new(6.0, 7.0, 8.0));
+        assert_eq!(m.to_array(), [[0.0, 1.0, 2.0], [3.0, 4.0, 5.0], [6.0, 7.0, 8.0]]);
+        assert_eq!(<[[f32; 3]; 3]>::from(m), m.to_array());
+        assert_eq!(m.transpose().to_array()[0], [0.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn mat3_from_mat4_keeps_the_upper_left() {
+        let m = Mat3::from_mat4(&counting());
+
+        assert_eq!(m.to_array(), [[0.0, 4.0, 8.0], [1.0, 5.0, 9.0], [2.0, 6.0, 10.0]]);
+        // the translation is dropped
+        let moved = Mat4::trs(
+            &Vec3::new(5.0, 6.0, 7.0),
+            &Mat4::rotation_y(0.5),
+            &Vec3::new(1.0, 2.0, 3.0),
+        );
+        let v = Vec3::new(1.0, -1.0, 2.0);
+        assert_vec_close(Mat3::from(moved) * v, moved.transform_vector(&v));
+    }
+
+    #[test]
+    fn mat3_inverse_round_trips_to_identity() {
+        let m = Mat3::from_mat4(&Mat4::trs(
+            &Vec3::zero(),
+            &Mat4::rotation(&Vec3::new(1.0, -2.0, 0.5), 0.9),
+            &Vec3::new(2.0, 0.5, 3.0),
+        ));
+        let inverse = m.inverse().unwrap();
+
+        assert_mat3_close(&(m * inverse), &Mat3::identity());
+        assert_mat3_close(&(inverse * m), &Mat3::identity());
+        assert!((m.determinant() - 3.0).abs() < 1e-4);
+        assert_mat3_close(&m.inverse_transpose().unwrap(), &inverse.transpose());
+    }
+
+    #[test]
+    fn the_normal_matrix_keeps_normals_perpendicular_to_the_surface() {
+        let modelview = Mat4::trs(
+            &Vec3::new(1.0, 2.0, 3.0),
+            &Mat4::rotation(&Vec3::new(0.3, 1.0, -0.2), 0.7),
+            &Vec3::new(4.0, 1.0, 0.25),
+        );
+        let normal_matrix = Mat3::normal_matrix(&modelview).unwrap();
+
+        // a slanted surface, its normal and a tangent along it
+        let normal = Vec3::new(1.0, 1.0, 1.0).normalize();
+        let tangent = Vec3::new(1.0, -1.0, 0.0).normalize();
+        assert_eq!(normal.dot(&tangent), 0.0);
+
+        let transformed_tangent = modelview.transform_vector(&tangent);
+        let transformed_normal = normal_matrix * normal;
+        assert!(transformed_normal.normalize().dot(&transformed_tangent.normalize()).abs() < 1e-5);
+
+        // transforming the normal like a position would leave it slanted
+        let wrong = modelview.transform_vector(&normal);
+        assert!(wrong.normalize().dot(&transformed_tangent.normalize()).abs() > 0.1);
+    }
+
+    #[test]
+    fn singular_matrices_have_no_normal_matrix() {
+        let flattened = Mat4::scale(&Vec3::new(1.0, 0.0, 1.0));
+
+        assert_eq!(Mat3::normal_matrix(&flattened), None);
+        assert_eq!(Mat3::from_mat4(&flattened).inverse(), None);
+        assert_eq!(Mat3::from_mat4(&counting()).inverse(), None);
+        assert_eq!(Mat3::normal_matrix(&Mat4::identity()), Some(Mat3::identity()));
+    }
+
+    #[test]
+    fn transforms_2d_apply_the_right_hand_matrix_first() {
+        let translate = Mat3::translation_2d(&Vec2::new(10.0, 0.0));
+        let rotate = Mat3::rotation_2d(::std::f32::consts::FRAC_PI_2);
+        let scale = Mat3::scale_2d(&Vec2::new(2.0, 3.0));
+        let point = Vec2::new(1.0, 1.0);
+
+        // scaled to (2, 3), turned to (-3, 2) and moved to (7, 2)
+        let m = translate * rotate * scale;
+        assert_vec2_close(m.transform_point_2d(&point), Vec2::new(7.0, 2.0));
+        // moved to (11, 1), turned to (-1, 11) and scaled to (-2, 33)
+        let m = scale * rotate * translate;
+        assert_vec2_close(m.transform_point_2d(&point), Vec2::new(-2.0, 33.0));
+
+        // directions aren't moved
+        let m = translate * rotate;
+        assert_vec2_close(m.transform_vector_2d(&Vec2::new(1.0, 0.0)), Vec2::new(0.0, 1.0));
+        assert_vec2_close(translate.transform_point_2d(&Vec2::zero()), Vec2::new(10.0, 0.0));
+    }
+}
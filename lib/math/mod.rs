@@ -1,8 +1,20 @@
 //! A collection of data types and functions that allow for mathematical functions
 //! This module contains things that are used in graphcis programming like vectors and matrices and 
 //! functionality for manipulating their data.
+mod bounds;
+mod frustum;
 mod matrix;
+mod plane;
+mod polygon;
+mod quaternion;
+mod ray;
 mod vector;
 
+pub use self::bounds::*;
+pub use self::frustum::*;
 pub use self::matrix::*;
+pub use self::plane::*;
+pub use self::polygon::*;
+pub use self::quaternion::*;
+pub use self::ray::*;
 pub use self::vector::*;
@@ -0,0 +1,88 @@
+//! A module containing a Plane type.
+//! A plane is an infinite flat surface that splits space in two, a point is either
+//! in front of the plane (the side the normal points to), behind it or on it.
+use math::vector::{Vec3, Vec4, Vector};
+
+/// A plane represented by its normal and its distance from the origin
+/// every point `p` on the plane satisfies `normal.dot(p) + distance == 0`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+    /// The unit length direction the plane faces
+    pub normal: Vec3,
+    /// The signed distance from the plane to the origin along the normal
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a new plane
+    ///
+    /// # Arguments
+    /// `normal` - the direction the plane faces, this is normalized
+    /// `distance` - the signed distance from the plane to the origin
+    pub fn new(normal: Vec3, distance: f32) -> Plane {
+        Plane::from_coefficients(&Vec4::new(normal.x, normal.y, normal.z, distance))
+    }
+
+    /// Creates a plane from the coefficients of the plane equation `ax + by + cz + d = 0`
+    ///
+    /// # Arguments
+    /// `coefficients` - the `a`, `b`, `c` and `d` of the equation
+    ///
+    /// # Remarks
+    ///
+    /// The coefficients are scaled so that the normal is unit length.
+    pub fn from_coefficients(coefficients: &Vec4) -> Plane {
+        let normal = coefficients.xyz();
+        let len = normal.length();
+        if len == 0.0 {
+            return Plane {
+                normal: normal,
+                distance: coefficients.w,
+            };
+        }
+
+        Plane {
+            normal: normal / len,
+            distance: coefficients.w / len,
+        }
+    }
+
+    /// Creates a plane that passes through a point
+    ///
+    /// # Arguments
+    /// `normal` - the direction the plane faces, this is normalized
+    /// `point` - a point on the plane
+    pub fn from_point_normal(point: &Vec3, normal: &Vec3) -> Plane {
+        let normal = normal.normalize();
+        Plane {
+            normal: normal,
+            distance: -normal.dot(point),
+        }
+    }
+
+    /// Creates a plane that passes through three points
+    /// the plane faces the side the points wind counter-clockwise around
+    ///
+    /// # Arguments
+    /// `a`, `b`, `c` - the points on the plane
+    pub fn from_points(a: &Vec3, b: &Vec3, c: &Vec3) -> Plane {
+        Plane::from_point_normal(a, &(*b - *a).cross(&(*c - *a)))
+    }
+
+    /// returns the signed distance from the plane to a point
+    /// the distance is positive in front of the plane and negative behind it
+    ///
+    /// # Arguments
+    /// `point` - the point to measure to
+    pub fn distance_to(&self, point: &Vec3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+
+    /// returns the point on the plane closest to the provided point
+    ///
+    /// # Arguments
+    /// `point` - the point to project onto the plane
+    pub fn project(&self, point: &Vec3) -> Vec3 {
+        *point - self.normal * self.distance_to(point)
+    }
+}
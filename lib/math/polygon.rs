@@ -0,0 +1,276 @@
+//! A module for splitting polygons into triangles.
+//! Convex polygons are split into a fan around one of their corners and concave polygons are
+//! split by ear clipping, either way the triangles keep the winding of the polygon.
+use math::vector::{Vec3, Vector};
+
+/// How a polygon was split into triangles
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Triangulation {
+    /// The polygon was already a triangle
+    Triangle,
+    /// The polygon is convex and was split into a fan around one of its corners
+    Fan,
+    /// The polygon is concave, or convex with too many collinear corners for a fan,
+    /// and was split by ear clipping
+    EarClipping,
+    /// The polygon has fewer than three corners so there are no triangles
+    Degenerate,
+}
+
+/// Splits a polygon into triangles that keep its winding
+///
+/// # Arguments
+/// `corners` - the corners of the polygon in order
+///
+/// # Returns
+/// the indices into `corners` of each triangle, along with how the polygon was split
+///
+/// # Remarks
+///
+/// The polygon is expected to be roughly planar. The fan of a convex polygon is centred on a
+/// corner that isn't next to any collinear corners, so none of its triangles are flat, and
+/// when there is no such corner the polygon is clipped instead. A collinear corner is only
+/// clipped when no other corner can be, which gives a triangle with no area. When part of a
+/// polygon can't be clipped, such as a polygon that intersects itself, whatever remains is
+/// split into a fan.
+pub fn triangulate(corners: &[Vec3]) -> (Vec<[usize; 3]>, Triangulation) {
+    match corners.len() {
+        0..=2 => return (Vec::new(), Triangulation::Degenerate),
+        3 => return (vec![[0, 1, 2]], Triangulation::Triangle),
+        _ => (),
+    }
+
+    let normal = polygon_normal(corners);
+    let count = corners.len();
+    let turns = (0..count)
+        .map(|i| {
+            let (a, b, c) = (
+                corners[(i + count - 1) % count],
+                corners[i],
+                corners[(i + 1) % count],
+            );
+            turn(&a, &b, &c, &normal)
+        })
+        .collect::<Vec<f32>>();
+
+    if turns.iter().all(|&t| t >= 0.0) {
+        // a fan triangle is flat when its apex is on the same line as one of its edges,
+        // which can only happen when the apex or a corner next to it is collinear
+        let apex = (0..count).find(|&i| {
+            turns[(i + count - 1) % count] > 0.0 && turns[i] > 0.0 && turns[(i + 1) % count] > 0.0
+        });
+
+        if let Some(apex) = apex {
+            let order = (apex..count).chain(0..apex).collect::<Vec<usize>>();
+            return (fan(&order), Triangulation::Fan);
+        }
+    }
+
+    (clip_ears(corners, &normal), Triangulation::EarClipping)
+}
+
+// the normal of a polygon using Newell's method, its length is twice the area of the polygon
+fn polygon_normal(corners: &[Vec3]) -> Vec3 {
+    let mut normal = Vec3::zero();
+
+    for (i, a) in corners.iter().enumerate() {
+        let b = corners[(i + 1) % corners.len()];
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+
+    normal
+}
+
+// positive when a -> b -> c turns the same way as the polygon winds around `normal`
+fn turn(a: &Vec3, b: &Vec3, c: &Vec3, normal: &Vec3) -> f32 {
+    (*b - *a).cross(&(*c - *b)).dot(normal)
+}
+
+fn fan(corners: &[usize]) -> Vec<[usize; 3]> {
+    (1..corners.len() - 1)
+        .map(|i| [corners[0], corners[i], corners[i + 1]])
+        .collect()
+}
+
+fn clip_ears(corners: &[Vec3], normal: &Vec3) -> Vec<[usize; 3]> {
+    let mut remaining = (0..corners.len()).collect::<Vec<usize>>();
+    let mut triangles = Vec::with_capacity(corners.len() - 2);
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let triangle = |i: usize| {
+            [
+                remaining[(i + count - 1) % count],
+                remaining[i],
+                remaining[(i + 1) % count],
+            ]
+        };
+        let ear = (0..count)
+            .find(|&i| is_ear(corners, &remaining, triangle(i), normal, false))
+            .or_else(|| (0..count).find(|&i| is_ear(corners, &remaining, triangle(i), normal, true)));
+
+        match ear {
+            Some(i) => {
+                triangles.push(triangle(i));
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    triangles.extend(fan(&remaining));
+    triangles
+}
+
+// an ear is a corner that turns the way the polygon winds with no other corner inside it,
+// a corner that doesn't turn at all is only an ear when `collinear` is true
+fn is_ear(
+    corners: &[Vec3],
+    remaining: &[usize],
+    triangle: [usize; 3],
+    normal: &Vec3,
+    collinear: bool,
+) -> bool {
+    let (a, b, c) = (
+        corners[triangle[0]],
+        corners[triangle[1]],
+        corners[triangle[2]],
+    );
+
+    let bend = turn(&a, &b, &c, normal);
+    if bend < 0.0 || (bend == 0.0 && !collinear) {
+        return false;
+    }
+
+    remaining
+        .iter()
+        .map(|&i| corners[i])
+        .filter(|p| *p != a && *p != b && *p != c)
+        .all(|p| {
+            turn(&a, &b, &p, normal) < 0.0
+                || turn(&b, &c, &p, normal) < 0.0
+                || turn(&c, &a, &p, normal) < 0.0
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(points: &[(f32, f32)]) -> Vec<Vec3> {
+        points.iter().map(|&(x, y)| Vec3::new(x, y, 0.0)).collect()
+    }
+
+    // twice the signed area of a triangle, positive when it winds counter-clockwise
+    fn area(corners: &[Vec3], triangle: &[usize; 3]) -> f32 {
+        let (a, b, c) = (corners[triangle[0]], corners[triangle[1]], corners[triangle[2]]);
+        (b - a).cross(&(c - a)).z
+    }
+
+    // checks the triangles cover the polygon without overlapping and keep its winding
+    fn assert_covers(corners: &[Vec3], triangles: &[[usize; 3]]) {
+        let expected = polygon_normal(corners).z;
+        let areas = triangles.iter().map(|t| area(corners, t)).collect::<Vec<f32>>();
+
+        assert_eq!(triangles.len(), corners.len() - 2);
+        assert!(areas.iter().all(|a| a * expected >= 0.0), "{:?}", areas);
+        let total = areas.iter().map(|a| a.abs()).sum::<f32>();
+        assert!((total - expected.abs()).abs() < 1e-5, "{} != {}", total, expected);
+    }
+
+    #[test]
+    fn convex_quad_is_a_fan() {
+        let square = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        let (triangles, triangulation) = triangulate(&square);
+
+        assert_eq!(triangulation, Triangulation::Fan);
+        assert_covers(&square, &triangles);
+    }
+
+    #[test]
+    fn concave_shapes_are_ear_clipped() {
+        let l = polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]);
+        let arrow = polygon(&[(0.0, 0.0), (2.0, 1.0), (0.0, 2.0), (1.0, 1.0)]);
+
+        for shape in &[l, arrow] {
+            let (triangles, triangulation) = triangulate(shape);
+            assert_eq!(triangulation, Triangulation::EarClipping);
+            assert_covers(shape, &triangles);
+        }
+    }
+
+    #[test]
+    fn winding_is_kept() {
+        let mut l = polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]);
+        let mut square = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        l.reverse();
+        square.reverse();
+
+        for shape in &[l, square] {
+            let (triangles, _) = triangulate(shape);
+            assert!(triangles.iter().all(|t| area(shape, t) < 0.0));
+            assert_covers(shape, &triangles);
+        }
+    }
+
+    #[test]
+    fn collinear_corners_of_a_convex_polygon_make_no_flat_triangles() {
+        // a square with a corner half way along its bottom edge, at each position in turn
+        let points = [(0.0, 0.0), (0.5, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        for start in 0..points.len() {
+            let rotated = (0..points.len())
+                .map(|i| points[(start + i) % points.len()])
+                .collect::<Vec<(f32, f32)>>();
+            let shape = polygon(&rotated);
+            let (triangles, triangulation) = triangulate(&shape);
+
+            assert_eq!(triangulation, Triangulation::Fan);
+            assert!(triangles.iter().all(|t| area(&shape, t) > 0.0));
+            assert_covers(&shape, &triangles);
+        }
+    }
+
+    #[test]
+    fn convex_polygon_without_a_fan_apex_is_clipped() {
+        // a triangle with a corner half way along each edge, every corner is collinear or next
+        // to one that is
+        let shape = polygon(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (1.0, 1.0), (0.0, 2.0), (0.0, 1.0)]);
+        let (triangles, triangulation) = triangulate(&shape);
+
+        assert_eq!(triangulation, Triangulation::EarClipping);
+        assert!(triangles.iter().all(|t| area(&shape, t) > 0.0));
+        assert_covers(&shape, &triangles);
+    }
+
+    #[test]
+    fn collinear_corners_of_a_concave_polygon_are_clipped() {
+        // an L with a corner half way along each edge
+        let shape = polygon(&[
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 0.5),
+            (2.0, 1.0),
+            (1.5, 1.0),
+            (1.0, 1.0),
+            (1.0, 1.5),
+            (1.0, 2.0),
+            (0.5, 2.0),
+            (0.0, 2.0),
+            (0.0, 1.0),
+        ]);
+        let (triangles, triangulation) = triangulate(&shape);
+
+        assert_eq!(triangulation, Triangulation::EarClipping);
+        assert_covers(&shape, &triangles);
+    }
+
+    #[test]
+    fn fewer_than_three_corners_is_degenerate() {
+        let (triangles, triangulation) = triangulate(&polygon(&[(0.0, 0.0), (1.0, 0.0)]));
+        assert!(triangles.is_empty());
+        assert_eq!(triangulation, Triangulation::Degenerate);
+    }
+}
@@ -0,0 +1,501 @@
+//! A module containing a Quaternion type for representing rotations.
+//!
+//! Quaternions avoid the gimbal lock that comes with composing Euler angles and can be
+//! smoothly interpolated, which makes them suited to camera and object orientation.
+//! They follow the same right hand rule as `Matrix::rotation` so converting between
+//! the two gives the same rotation.
+use math::matrix::Mat4;
+use math::vector::{Vec3, Vec4, Vector};
+use std::ops::Mul;
+
+/// A struct representing a quaternion as an imaginary vector (x, y, z) and real part (w)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quat {
+    /// The first imaginary point
+    pub x: f32,
+    /// The second imaginary point
+    pub y: f32,
+    /// The third imaginary point
+    pub z: f32,
+    /// The real point
+    pub w: f32,
+}
+
+impl Quat {
+    /// Creates a new quaternion from its points
+    ///
+    /// # Remarks
+    ///
+    /// Only unit length quaternions represent a rotation, see [`Quat::normalize`]
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quat {
+        Quat {
+            x: x,
+            y: y,
+            z: z,
+            w: w,
+        }
+    }
+
+    /// returns a quaternion that represents no rotation
+    pub fn identity() -> Quat {
+        Quat::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Creates a quaternion that rotates around an axis
+    ///
+    /// # Arguments
+    /// `axis` - the axis to rotate around, this does not need to be normalized
+    /// `angle` - the angle in radians, positive angles follow the right hand rule
+    pub fn from_axis_angle(axis: &Vec3, angle: f32) -> Quat {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+
+        Quat::new(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    /// Creates a quaternion from Euler angles
+    ///
+    /// # Arguments
+    /// `euler` - the angles in radians, `x` is the pitch, `y` is the yaw and `z` is the roll
+    ///
+    /// # Remarks
+    ///
+    /// The roll is applied first, followed by the pitch and then the yaw.
+    pub fn from_euler(euler: &Vec3) -> Quat {
+        let pitch = Quat::from_axis_angle(&Vec3::new(1.0, 0.0, 0.0), euler.x);
+        let yaw = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), euler.y);
+        let roll = Quat::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), euler.z);
+
+        yaw * pitch * roll
+    }
+
+    /// Converts the quaternion into Euler angles
+    ///
+    /// # Returns
+    /// the angles in radians, in the same layout as accepted by [`Quat::from_euler`]
+    ///
+    /// # Remarks
+    ///
+    /// When the pitch is straight up or down the yaw and roll rotate around the same axis,
+    /// in that case the roll is returned as zero.
+    pub fn to_euler(&self) -> Vec3 {
+        let Quat { x, y, z, w } = self.normalize();
+
+        let m12 = 2.0 * (y * z - w * x);
+        let pitch = (-m12).max(-1.0).min(1.0).asin();
+
+        if m12.abs() > 0.999_999 {
+            let m00 = 1.0 - 2.0 * (y * y + z * z);
+            let m20 = 2.0 * (x * z - w * y);
+            Vec3::new(pitch, (-m20).atan2(m00), 0.0)
+        } else {
+            let m02 = 2.0 * (x * z + w * y);
+            let m22 = 1.0 - 2.0 * (x * x + y * y);
+            let m10 = 2.0 * (x * y + w * z);
+            let m11 = 1.0 - 2.0 * (x * x + z * z);
+            Vec3::new(pitch, m02.atan2(m22), m10.atan2(m11))
+        }
+    }
+
+    /// Creates a quaternion from the rotation part of a matrix
+    ///
+    /// # Arguments
+    /// `m` - a matrix containing a rotation, any scale should be removed beforehand
+    pub fn from_mat4(m: &Mat4) -> Quat {
+        let (m00, m11, m22) = (m.get(0, 0), m.get(1, 1), m.get(2, 2));
+        let trace = m00 + m11 + m22;
+
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quat::new(
+                (m.get(2, 1) - m.get(1, 2)) / s,
+                (m.get(0, 2) - m.get(2, 0)) / s,
+                (m.get(1, 0) - m.get(0, 1)) / s,
+                0.25 * s,
+            )
+        } else if m00 > m11 && m00 > m22 {
+            let s = (1.0 + m00 - m11 - m22).sqrt() * 2.0;
+            Quat::new(
+                0.25 * s,
+                (m.get(0, 1) + m.get(1, 0)) / s,
+                (m.get(0, 2) + m.get(2, 0)) / s,
+                (m.get(2, 1) - m.get(1, 2)) / s,
+            )
+        } else if m11 > m22 {
+            let s = (1.0 + m11 - m00 - m22).sqrt() * 2.0;
+            Quat::new(
+                (m.get(0, 1) + m.get(1, 0)) / s,
+                0.25 * s,
+                (m.get(1, 2) + m.get(2, 1)) / s,
+                (m.get(0, 2) - m.get(2, 0)) / s,
+            )
+        } else {
+            let s = (1.0 + m22 - m00 - m11).sqrt() * 2.0;
+            Quat::new(
+                (m.get(0, 2) + m.get(2, 0)) / s,
+                (m.get(1, 2) + m.get(2, 1)) / s,
+                0.25 * s,
+                (m.get(1, 0) - m.get(0, 1)) / s,
+            )
+        };
+
+        q.normalize()
+    }
+
+    /// Converts the quaternion into a rotation matrix
+    pub fn to_mat4(&self) -> Mat4 {
+        let Quat { x, y, z, w } = *self;
+
+        Mat4::from_columns(
+            Vec4::new(
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y + w * z),
+                2.0 * (x * z - w * y),
+                0.0,
+            ),
+            Vec4::new(
+                2.0 * (x * y - w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z + w * x),
+                0.0,
+            ),
+            Vec4::new(
+                2.0 * (x * z + w * y),
+                2.0 * (y * z - w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// returns the dot product of this quaternion and another
+    pub fn dot(&self, other: &Quat) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    /// returns the length of the quaternion
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// returns the quaternion scaled to unit length
+    ///
+    /// # Remarks
+    ///
+    /// A zero length quaternion is returned as the identity.
+    pub fn normalize(&self) -> Quat {
+        let len = self.length();
+        if len == 0.0 {
+            Quat::identity()
+        } else {
+            Quat::new(self.x / len, self.y / len, self.z / len, self.w / len)
+        }
+    }
+
+    /// returns the conjugate of the quaternion
+    /// for a unit quaternion this is the opposite rotation
+    pub fn conjugate(&self) -> Quat {
+        Quat::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// returns the inverse of the quaternion
+    ///
+    /// # Returns
+    /// `None` when the quaternion has zero length
+    pub fn inverse(&self) -> Option<Quat> {
+        let len_sq = self.dot(self);
+        if len_sq == 0.0 {
+            return None;
+        }
+
+        let c = self.conjugate();
+        Some(Quat::new(c.x / len_sq, c.y / len_sq, c.z / len_sq, c.w / len_sq))
+    }
+
+    /// Rotates a vector by this quaternion
+    ///
+    /// # Arguments
+    /// `v` - the vector to rotate
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = u.cross(v) * 2.0;
+
+        *v + t * self.w + u.cross(&t)
+    }
+
+    /// Normalized linear interpolation between this quaternion and another
+    /// this is cheaper than [`Quat::slerp`] but does not rotate at a constant speed
+    ///
+    /// # Arguments
+    /// `other` - the quaternion to interpolate towards
+    /// `t` - the amount to interpolate, 0 returns this quaternion and 1 returns `other`
+    pub fn nlerp(&self, other: &Quat, t: f32) -> Quat {
+        let other = self.shortest_path_to(other);
+
+        Quat::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+            self.z + (other.z - self.z) * t,
+            self.w + (other.w - self.w) * t,
+        )
+        .normalize()
+    }
+
+    /// Spherical linear interpolation between this quaternion and another
+    /// the rotation happens at a constant speed and takes the shortest path
+    ///
+    /// # Arguments
+    /// `other` - the quaternion to interpolate towards
+    /// `t` - the amount to interpolate, 0 returns this quaternion and 1 returns `other`
+    pub fn slerp(&self, other: &Quat, t: f32) -> Quat {
+        let other = self.shortest_path_to(other);
+        let cos_theta = self.dot(&other).min(1.0);
+
+        // the quaternions are almost identical so the sine below would approach zero
+        if cos_theta > 0.9995 {
+            return self.nlerp(&other, t);
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+
+        Quat::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+    }
+
+    // q and -q are the same rotation, picking the one closest to self avoids
+    // interpolating the long way round.
+    fn shortest_path_to(&self, other: &Quat) -> Quat {
+        if self.dot(other) < 0.0 {
+            Quat::new(-other.x, -other.y, -other.z, -other.w)
+        } else {
+            *other
+        }
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Quat {
+        Quat::identity()
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+
+    fn mul(self, rhs: Quat) -> Quat {
+        Quat {
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        }
+    }
+}
+
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        self.rotate(&rhs)
+    }
+}
+
+impl From<Quat> for Mat4 {
+    fn from(q: Quat) -> Mat4 {
+        q.to_mat4()
+    }
+}
+
+impl From<Mat4> for Quat {
+    fn from(m: Mat4) -> Quat {
+        Quat::from_mat4(&m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::matrix::Matrix;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn assert_vec_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn assert_mat_close(a: &Mat4, b: &Mat4) {
+        for i in 0..16 {
+            assert!((a[i] - b[i]).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    // q and -q are the same rotation
+    fn assert_same_rotation(a: &Quat, b: &Quat) {
+        assert!((a.dot(b).abs() - 1.0).abs() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn axes() -> Vec<(Vec3, f32)> {
+        vec![
+            (Vec3::new(1.0, 0.0, 0.0), 0.3),
+            (Vec3::new(0.0, 1.0, 0.0), FRAC_PI_2),
+            (Vec3::new(0.0, 0.0, 1.0), -2.0),
+            (Vec3::new(1.0, 2.0, 3.0), 1.2),
+            (Vec3::new(-3.0, 0.5, 1.0), PI),
+            (Vec3::new(0.2, -1.0, 0.4), 3.0),
+        ]
+    }
+
+    #[test]
+    fn axis_angle_matches_the_rotation_matrix() {
+        for (axis, angle) in axes() {
+            let q = Quat::from_axis_angle(&axis, angle);
+
+            assert!((q.length() - 1.0).abs() < 1e-5);
+            assert_mat_close(&q.to_mat4(), &Mat4::rotation(&axis, angle));
+            assert_mat_close(&Mat4::from(q), &Mat4::rotation(&axis, angle));
+        }
+    }
+
+    #[test]
+    fn a_matrix_converts_back_to_the_same_rotation() {
+        for (axis, angle) in axes() {
+            let q = Quat::from_axis_angle(&axis, angle);
+
+            assert_same_rotation(&Quat::from_mat4(&q.to_mat4()), &q);
+            assert_same_rotation(&Quat::from(Mat4::rotation(&axis, angle)), &q);
+        }
+    }
+
+    #[test]
+    fn rotate_matches_the_matrix_transform() {
+        let v = Vec3::new(0.5, -2.0, 3.0);
+
+        for (axis, angle) in axes() {
+            let q = Quat::from_axis_angle(&axis, angle);
+
+            assert_vec_close(q.rotate(&v), Mat4::rotation(&axis, angle).transform_vector(&v));
+            assert_vec_close(q * v, q.rotate(&v));
+        }
+
+        // a quarter turn around y follows the right hand rule
+        let q = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), FRAC_PI_2);
+        assert_vec_close(q.rotate(&Vec3::new(0.0, 0.0, 1.0)), Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn the_right_hand_rotation_is_applied_first() {
+        let a = Quat::from_axis_angle(&Vec3::new(1.0, 2.0, 3.0), 0.7);
+        let b = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, -1.0), 1.9);
+        let v = Vec3::new(1.0, -1.0, 2.0);
+
+        assert_vec_close((a * b).rotate(&v), a.rotate(&b.rotate(&v)));
+        assert_mat_close(&(a * b).to_mat4(), &(a.to_mat4() * b.to_mat4()));
+    }
+
+    #[test]
+    fn a_quaternion_times_its_inverse_is_the_identity() {
+        for (axis, angle) in axes() {
+            let q = Quat::from_axis_angle(&axis, angle);
+
+            assert_same_rotation(&(q * q.inverse().unwrap()), &Quat::identity());
+            assert_same_rotation(&q.conjugate(), &q.inverse().unwrap());
+        }
+
+        // the inverse of a quaternion that isn't unit length isn't its conjugate
+        let q = Quat::new(0.0, 0.0, 2.0, 0.0);
+        let product = q * q.inverse().unwrap();
+        assert!((product.w - 1.0).abs() < 1e-6 && product.z.abs() < 1e-6);
+        assert_eq!(Quat::new(0.0, 0.0, 0.0, 0.0).inverse(), None);
+        assert_eq!(Quat::new(0.0, 0.0, 0.0, 0.0).normalize(), Quat::identity());
+    }
+
+    #[test]
+    fn euler_angles_round_trip() {
+        for &euler in &[
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.3, 0.0, 0.0),
+            Vec3::new(0.0, -1.1, 0.0),
+            Vec3::new(0.0, 0.0, 2.5),
+            Vec3::new(0.4, -0.8, 1.2),
+            Vec3::new(-1.5, 3.0, -0.1),
+        ] {
+            let q = Quat::from_euler(&euler);
+
+            assert_vec_close(q.to_euler(), euler);
+            assert_same_rotation(&Quat::from_euler(&q.to_euler()), &q);
+        }
+    }
+
+    #[test]
+    fn euler_angles_are_applied_roll_pitch_then_yaw() {
+        let euler = Vec3::new(0.4, -0.8, 1.2);
+        let expected =
+            Mat4::rotation_y(euler.y) * Mat4::rotation_x(euler.x) * Mat4::rotation_z(euler.z);
+
+        assert_mat_close(&Quat::from_euler(&euler).to_mat4(), &expected);
+    }
+
+    #[test]
+    fn euler_angles_round_trip_near_straight_up_and_down() {
+        for &pitch in &[FRAC_PI_2 - 1e-3, FRAC_PI_2, -FRAC_PI_2, -(FRAC_PI_2 - 1e-3)] {
+            let q = Quat::from_euler(&Vec3::new(pitch, 0.6, 0.3));
+            let euler = q.to_euler();
+
+            // the yaw and roll share an axis straight up or down, the rotation is still kept
+            assert!((euler.x - pitch).abs() < 1e-2, "{:?}", euler);
+            assert_same_rotation(&Quat::from_euler(&euler), &q);
+        }
+
+        let straight_up = Quat::from_euler(&Vec3::new(FRAC_PI_2, 0.6, 0.3)).to_euler();
+        assert_eq!(straight_up.z, 0.0);
+    }
+
+    #[test]
+    fn slerp_returns_the_end_points() {
+        let a = Quat::from_axis_angle(&Vec3::new(1.0, 2.0, 3.0), 0.7);
+        let b = Quat::from_axis_angle(&Vec3::new(0.0, 1.0, -1.0), 1.9);
+
+        assert_same_rotation(&a.slerp(&b, 0.0), &a);
+        assert_same_rotation(&a.slerp(&b, 1.0), &b);
+        assert_same_rotation(&a.nlerp(&b, 0.0), &a);
+        assert_same_rotation(&a.nlerp(&b, 1.0), &b);
+    }
+
+    #[test]
+    fn slerp_rotates_at_a_constant_speed() {
+        let axis = Vec3::new(0.0, 0.0, 1.0);
+        let a = Quat::from_axis_angle(&axis, 0.0);
+        let b = Quat::from_axis_angle(&axis, 2.0);
+
+        for &t in &[0.25, 0.5, 0.9] {
+            assert_same_rotation(&a.slerp(&b, t), &Quat::from_axis_angle(&axis, 2.0 * t));
+        }
+        // nlerp takes the same path but not at the same speed
+        assert_same_rotation(&a.nlerp(&b, 0.5), &Quat::from_axis_angle(&axis, 1.0));
+    }
+
+    #[test]
+    fn slerp_takes_the_shortest_arc() {
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let a = Quat::from_axis_angle(&axis, 0.2);
+        // the same rotation as 0.4 radians but on the far side of the sphere
+        let b = Quat::from_axis_angle(&axis, 0.4 - 2.0 * PI);
+        assert!(a.dot(&b) < 0.0);
+
+        let halfway = Quat::from_axis_angle(&axis, 0.3);
+        assert_same_rotation(&a.slerp(&b, 0.5), &halfway);
+        assert_same_rotation(&a.nlerp(&b, 0.5), &halfway);
+
+        // the long way round would pass through the opposite rotation
+        let c = Quat::from_axis_angle(&axis, 3.0);
+        let short = a.slerp(&c, 0.5);
+        assert_same_rotation(&short, &Quat::from_axis_angle(&axis, 1.6));
+    }
+}
@@ -0,0 +1,476 @@
+//! A module containing a Ray type and intersection queries.
+//! A ray starts at an origin and extends infinitely in one direction, casting one
+//! into a scene is used for things like mouse picking and line of sight checks.
+use math::bounds::{Aabb, BoundingSphere};
+use math::matrix::Mat4;
+use math::plane::Plane;
+use math::vector::{Vec2, Vec3, Vec4, Vector};
+use std::f32;
+
+/// A ray with an origin and a direction
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ray {
+    /// The point the ray starts from
+    pub origin: Vec3,
+    /// The unit length direction of the ray
+    pub direction: Vec3,
+}
+
+/// The result of a ray hitting something
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    /// The distance along the ray to the hit
+    pub distance: f32,
+    /// The point that was hit
+    pub point: Vec3,
+    /// The unit length surface normal at the hit
+    pub normal: Vec3,
+    /// The barycentric coordinates of the hit when a triangle was hit
+    /// these are the weights of the first, second and third points of the triangle
+    pub barycentric: Option<Vec3>,
+}
+
+impl Ray {
+    /// Creates a new ray
+    ///
+    /// # Arguments
+    /// `origin` - the point the ray starts from
+    /// `direction` - the direction of the ray, this is normalized
+    pub fn new(origin: Vec3, direction: Vec3) -> Ray {
+        Ray {
+            origin: origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Creates a ray that passes through a point on the screen, such as the mouse position
+    ///
+    /// # Arguments
+    /// `position` - the position on the screen in pixels, from the top left corner
+    /// `dimensions` - a tuple containing the width and height of the screen
+    /// `view_projection` - the projection matrix multiplied by the view matrix
+    /// `depth_range` - the depths that the projection maps the near and far planes to
+    ///
+    /// # Returns
+    /// `None` when the view projection matrix can't be inverted
+    ///
+    /// # Remarks
+    ///
+    /// The depth range is `(-1.0, 1.0)` for `Mat4::perspective`, `Mat4::perspective_infinite`
+    /// and `Mat4::ortho`, and `(1.0, 0.0)` for the reversed perspectives which are drawn with
+    /// a clip space depth from 0 to 1.
+    pub fn from_screen(
+        position: &Vec2,
+        dimensions: (u32, u32),
+        view_projection: &Mat4,
+        depth_range: (f32, f32),
+    ) -> Option<Ray> {
+        let (width, height) = dimensions;
+        let x = 2.0 * position.x / width as f32 - 1.0;
+        let y = 1.0 - 2.0 * position.y / height as f32;
+
+        view_projection.inverse().map(|inverse| {
+            let unproject = |z: f32| {
+                let p = inverse * Vec4::new(x, y, z, 1.0);
+                p.xyz() / p.w
+            };
+            // without a far plane the far depth is infinitely far away,
+            // so the direction comes from halfway through the depth range instead
+            let (near, far) = depth_range;
+            let start = unproject(near);
+            let through = unproject((near + far) * 0.5);
+
+            Ray::new(start, through - start)
+        })
+    }
+
+    /// returns the point at a distance along the ray
+    ///
+    /// # Arguments
+    /// `distance` - how far along the ray the point is
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Tests the ray against a triangle using the Möller–Trumbore algorithm
+    ///
+    /// # Arguments
+    /// `a`, `b`, `c` - the points of the triangle
+    ///
+    /// # Remarks
+    ///
+    /// Both sides of the triangle can be hit. The normal faces the side that the points
+    /// wind counter-clockwise around, the same as [`Plane::from_points`].
+    pub fn intersect_triangle(&self, a: &Vec3, b: &Vec3, c: &Vec3) -> Option<RayHit> {
+        let edge1 = *b - *a;
+        let edge2 = *c - *a;
+        let p = self.direction.cross(&edge2);
+        let det = edge1.dot(&p);
+
+        // the ray is parallel to the triangle, the determinant grows with the area of the
+        // triangle so the test is relative to its edges to keep small triangles hittable
+        if det.abs() <= f32::EPSILON * edge1.length() * edge2.length() {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let t_vec = self.origin - *a;
+        let u = t_vec.dot(&p) * inv_det;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+
+        let q = t_vec.cross(&edge1);
+        let v = self.direction.dot(&q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge2.dot(&q) * inv_det;
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(RayHit {
+            distance: distance,
+            point: self.at(distance),
+            normal: edge1.cross(&edge2).normalize(),
+            barycentric: Some(Vec3::new(1.0 - u - v, u, v)),
+        })
+    }
+
+    /// Tests the ray against an axis aligned box using the slab method
+    ///
+    /// # Arguments
+    /// `aabb` - the box to test
+    ///
+    /// # Remarks
+    ///
+    /// When the ray starts inside the box the hit is where the ray leaves the box.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<RayHit> {
+        let mut t_min = f32::NEG_INFINITY;
+        let mut t_max = f32::INFINITY;
+        let mut enter_axis = 0;
+        let mut exit_axis = 0;
+
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+
+            if direction == 0.0 {
+                if origin < aabb.min[axis] || origin > aabb.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv = 1.0 / direction;
+            let t1 = (aabb.min[axis] - origin) * inv;
+            let t2 = (aabb.max[axis] - origin) * inv;
+            let (near, far) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+
+            if near > t_min {
+                t_min = near;
+                enter_axis = axis;
+            }
+            if far < t_max {
+                t_max = far;
+                exit_axis = axis;
+            }
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        if t_max < 0.0 {
+            return None;
+        }
+
+        let (distance, axis, sign) = if t_min >= 0.0 {
+            (t_min, enter_axis, -self.direction[enter_axis].signum())
+        } else {
+            (t_max, exit_axis, self.direction[exit_axis].signum())
+        };
+
+        let mut normal = Vec3::zero();
+        normal[axis] = sign;
+
+        Some(RayHit {
+            distance: distance,
+            point: self.at(distance),
+            normal: normal,
+            barycentric: None,
+        })
+    }
+
+    /// Tests the ray against a sphere
+    ///
+    /// # Arguments
+    /// `sphere` - the sphere to test
+    ///
+    /// # Remarks
+    ///
+    /// When the ray starts inside the sphere the hit is where the ray leaves the sphere.
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<RayHit> {
+        let offset = self.origin - sphere.center;
+        let b = offset.dot(&self.direction);
+        let c = offset.length_squared() - sphere.radius * sphere.radius;
+        let discriminant = b * b - c;
+
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let distance = if -b - root >= 0.0 {
+            -b - root
+        } else if -b + root >= 0.0 {
+            -b + root
+        } else {
+            return None;
+        };
+
+        let point = self.at(distance);
+        Some(RayHit {
+            distance: distance,
+            point: point,
+            normal: (point - sphere.center).normalize(),
+            barycentric: None,
+        })
+    }
+
+    /// Tests the ray against a plane
+    ///
+    /// # Arguments
+    /// `plane` - the plane to test
+    ///
+    /// # Remarks
+    ///
+    /// Both sides of the plane can be hit, the normal returned is the normal of the plane.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<RayHit> {
+        let denominator = plane.normal.dot(&self.direction);
+
+        // the ray is parallel to the plane
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let distance = -plane.distance_to(&self.origin) / denominator;
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(RayHit {
+            distance: distance,
+            point: self.at(distance),
+            normal: plane.normal,
+            barycentric: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use math::matrix::Matrix;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_vec_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    fn triangle() -> (Vec3, Vec3, Vec3) {
+        (
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn a_ray_hits_a_triangle_with_barycentric_coordinates() {
+        let (a, b, c) = triangle();
+        let ray = Ray::new(Vec3::new(0.25, 0.5, -2.0), Vec3::new(0.0, 0.0, 1.0));
+        let hit = ray.intersect_triangle(&a, &b, &c).unwrap();
+
+        assert!((hit.distance - 2.0).abs() < 1e-6);
+        assert_vec_close(hit.point, Vec3::new(0.25, 0.5, 0.0));
+        assert_vec_close(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+        let weights = hit.barycentric.unwrap();
+        assert_vec_close(weights, Vec3::new(0.25, 0.25, 0.5));
+        assert_vec_close(a * weights.x + b * weights.y + c * weights.z, hit.point);
+    }
+
+    #[test]
+    fn the_back_of_a_triangle_can_be_hit() {
+        let (a, b, c) = triangle();
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = ray.intersect_triangle(&a, &b, &c).unwrap();
+
+        assert!((hit.distance - 3.0).abs() < 1e-6);
+        // the normal still faces the side the points wind counter-clockwise around
+        assert_vec_close(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn a_ray_misses_a_triangle_beside_behind_or_parallel_to_it() {
+        let (a, b, c) = triangle();
+        let beside = Ray::new(Vec3::new(0.75, 0.75, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        let behind = Ray::new(Vec3::new(0.25, 0.25, 1.0), Vec3::new(0.0, 0.0, 1.0));
+        let parallel = Ray::new(Vec3::new(-1.0, 0.25, 0.0), Vec3::new(1.0, 0.0, 0.0));
+
+        assert_eq!(beside.intersect_triangle(&a, &b, &c), None);
+        assert_eq!(behind.intersect_triangle(&a, &b, &c), None);
+        assert_eq!(parallel.intersect_triangle(&a, &b, &c), None);
+        // a triangle with no area can't be hit
+        assert_eq!(beside.intersect_triangle(&a, &b, &(b * 2.0)), None);
+    }
+
+    #[test]
+    fn small_triangles_can_be_hit() {
+        for &size in &[1e-3, 1e-4, 1e-5, 1e-6] {
+            let (a, b, c) = triangle();
+            let (a, b, c) = (a * size, b * size, c * size);
+            let ray = Ray::new(Vec3::new(0.25 * size, 0.25 * size, -1.0), Vec3::new(0.0, 0.0, 1.0));
+            let hit = ray.intersect_triangle(&a, &b, &c);
+
+            assert!(hit.is_some(), "missed a triangle of size {}", size);
+            assert_vec_close(hit.unwrap().barycentric.unwrap(), Vec3::new(0.5, 0.25, 0.25));
+        }
+    }
+
+    #[test]
+    fn a_ray_hits_the_nearest_side_of_a_box() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Vec3::new(0.5, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let hit = ray.intersect_aabb(&aabb).unwrap();
+
+        assert!((hit.distance - 4.0).abs() < 1e-6);
+        assert_vec_close(hit.point, Vec3::new(0.5, -1.0, 0.0));
+        assert_vec_close(hit.normal, Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(hit.barycentric, None);
+
+        let diagonal = Ray::new(Vec3::new(-3.0, -2.5, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        let hit = diagonal.intersect_aabb(&aabb).unwrap();
+        assert_vec_close(hit.point, Vec3::new(-1.0, -0.5, 0.0));
+        assert_vec_close(hit.normal, Vec3::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn a_ray_from_inside_a_box_hits_where_it_leaves() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = ray.intersect_aabb(&aabb).unwrap();
+
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+        assert_vec_close(hit.point, Vec3::new(0.0, 0.5, -1.0));
+        assert_vec_close(hit.normal, Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn a_ray_misses_a_box_beside_or_behind_it() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let beside = Ray::new(Vec3::new(2.0, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let behind = Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let past_a_corner = Ray::new(Vec3::new(-3.0, 0.5, 0.0), Vec3::new(1.0, 1.0, 0.0));
+
+        assert_eq!(beside.intersect_aabb(&aabb), None);
+        assert_eq!(behind.intersect_aabb(&aabb), None);
+        assert_eq!(past_a_corner.intersect_aabb(&aabb), None);
+    }
+
+    #[test]
+    fn a_ray_hits_the_near_side_of_a_sphere() {
+        let sphere = BoundingSphere::new(Vec3::new(0.0, 0.0, 5.0), 2.0);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let hit = ray.intersect_sphere(&sphere).unwrap();
+
+        assert!((hit.distance - 3.0).abs() < 1e-6);
+        assert_vec_close(hit.normal, Vec3::new(0.0, 0.0, -1.0));
+
+        let inside = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(1.0, 0.0, 0.0));
+        let hit = inside.intersect_sphere(&sphere).unwrap();
+        assert!((hit.distance - 2.0).abs() < 1e-6);
+        assert_vec_close(hit.point, Vec3::new(2.0, 0.0, 5.0));
+        assert_vec_close(hit.normal, Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn a_ray_misses_a_sphere_beside_or_behind_it() {
+        let sphere = BoundingSphere::new(Vec3::new(0.0, 0.0, 5.0), 2.0);
+        let beside = Ray::new(Vec3::new(2.1, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let behind = Ray::new(Vec3::new(0.0, 0.0, 8.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(beside.intersect_sphere(&sphere), None);
+        assert_eq!(behind.intersect_sphere(&sphere), None);
+    }
+
+    #[test]
+    fn a_ray_hits_either_side_of_a_plane() {
+        let plane = Plane::from_point_normal(&Vec3::new(0.0, 2.0, 0.0), &Vec3::new(0.0, 1.0, 0.0));
+        let below = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 1.0));
+        let above = Ray::new(Vec3::new(1.0, 4.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+
+        let hit = below.intersect_plane(&plane).unwrap();
+        assert_vec_close(hit.point, Vec3::new(1.0, 2.0, 2.0));
+        assert!((hit.distance - 8.0f32.sqrt()).abs() < 1e-5);
+        assert_vec_close(hit.normal, plane.normal);
+        assert!((above.intersect_plane(&plane).unwrap().distance - 2.0).abs() < 1e-6);
+
+        let away = Ray::new(Vec3::new(1.0, 4.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let parallel = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(away.intersect_plane(&plane), None);
+        assert_eq!(parallel.intersect_plane(&plane), None);
+    }
+
+    // checks that the ray passes back through the pixel it was cast from
+    fn assert_through_pixel(ray: &Ray, view_projection: &Mat4, ndc: Vec2) {
+        for &distance in &[1.0, 10.0, 50.0] {
+            let point = ray.at(distance);
+            let clip = *view_projection * Vec4::new(point.x, point.y, point.z, 1.0);
+            let projected = Vec2::new(clip.x / clip.w, clip.y / clip.w);
+            assert!((projected - ndc).length() < 1e-3, "{:?} != {:?}", projected, ndc);
+        }
+    }
+
+    #[test]
+    fn a_ray_from_the_screen_passes_through_the_pixel() {
+        let eye = Vec3::new(1.0, 2.0, -3.0);
+        let view = Mat4::view(&eye, &Vec3::new(0.0, 0.0, 1.0), &Vec3::new(0.0, 1.0, 0.0));
+        let dimensions = (800, 600);
+
+        let projections = [
+            (Mat4::perspective(dimensions, FRAC_PI_2, (0.5, 100.0)), (-1.0, 1.0)),
+            (Mat4::perspective_infinite(dimensions, FRAC_PI_2, 0.5), (-1.0, 1.0)),
+            (Mat4::perspective_reversed(dimensions, FRAC_PI_2, (0.5, 100.0)), (1.0, 0.0)),
+            (Mat4::perspective_infinite_reversed(dimensions, FRAC_PI_2, 0.5), (1.0, 0.0)),
+        ];
+
+        for &(projection, depth_range) in projections.iter() {
+            let view_projection = projection * view;
+
+            let center = Ray::from_screen(&Vec2::new(400.0, 300.0), dimensions, &view_projection, depth_range)
+                .unwrap();
+            assert_vec_close(center.origin, eye + Vec3::new(0.0, 0.0, 0.5));
+            assert_vec_close(center.direction, Vec3::new(0.0, 0.0, 1.0));
+
+            let corner = Ray::from_screen(&Vec2::new(0.0, 0.0), dimensions, &view_projection, depth_range)
+                .unwrap();
+            assert_through_pixel(&corner, &view_projection, Vec2::new(-1.0, 1.0));
+            let pixel = Ray::from_screen(&Vec2::new(600.0, 450.0), dimensions, &view_projection, depth_range)
+                .unwrap();
+            assert_through_pixel(&pixel, &view_projection, Vec2::new(0.5, -0.5));
+        }
+    }
+
+    #[test]
+    fn rays_from_an_orthographic_screen_are_parallel() {
+        let view_projection = Mat4::ortho(-4.0, 4.0, -3.0, 3.0, 1.0, 10.0);
+        let ray = Ray::from_screen(&Vec2::new(0.0, 600.0), (800, 600), &view_projection, (-1.0, 1.0))
+            .unwrap();
+
+        assert_vec_close(ray.origin, Vec3::new(-4.0, -3.0, 1.0));
+        assert_vec_close(ray.direction, Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(Ray::from_screen(&Vec2::zero(), (800, 600), &Mat4::new([0.0; 16]), (-1.0, 1.0)), None);
+    }
+}
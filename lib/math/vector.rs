@@ -4,12 +4,14 @@
 //! An example is a Vec2 which is an object representing 2 points (x and y)
 //! or a vec3 and object representing 3 points (x,y and z)
 use glium::vertex::{Attribute, AttributeType};
-use std::ops::Index;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
 // todo: Missing examples
 
 /// A struct representing a 2 point vector
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec2 {
     /// Represents the first point in the vec2
     pub x: f32,
@@ -18,7 +20,7 @@ pub struct Vec2 {
 }
 
 /// A struct representing a 3 point vector
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec3 {
     /// Represents the first point in the vec3
     pub x: f32,
@@ -29,7 +31,7 @@ pub struct Vec3 {
 }
 
 /// A strict representing a 4 point vector
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec4 {
     /// Represents the first point in the vec4
     pub x: f32,
@@ -41,8 +43,52 @@ pub struct Vec4 {
     pub w: f32,
 }
 
+impl Vec2 {
+    /// Creates a new vec2 from its points
+    pub fn new(x: f32, y: f32) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+}
+
+impl Vec3 {
+    /// Creates a new vec3 from its points
+    pub fn new(x: f32, y: f32, z: f32) -> Vec3 {
+        Vec3 { x: x, y: y, z: z }
+    }
+
+    /// returns the cross product of this vector and another
+    /// the result is perpendicular to both vectors
+    ///
+    /// # Arguments
+    /// `other` - the right hand side of the cross product
+    pub fn cross(&self, other: &Vec3) -> Vec3 {
+        Vec3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+}
+
+impl Vec4 {
+    /// Creates a new vec4 from its points
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Vec4 {
+        Vec4 {
+            x: x,
+            y: y,
+            z: z,
+            w: w,
+        }
+    }
+
+    /// returns the first three points as a vec3, dropping `w`
+    pub fn xyz(&self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+}
+
 /// A collection of functions for Vectors
-pub trait Vector {
+pub trait Vector: Sized + Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self> {
     /// returns an empty vector
     fn zero() -> Self;
 
@@ -51,6 +97,67 @@ pub trait Vector {
     /// # Arguments
     /// `val` - the value for each point
     fn from_val(val: f32) -> Self;
+
+    /// returns the dot product of this vector and another
+    ///
+    /// # Arguments
+    /// `other` - the vector to take the dot product with
+    fn dot(&self, other: &Self) -> f32;
+
+    /// returns a vector containing the smallest of each point of the two vectors
+    ///
+    /// # Arguments
+    /// `other` - the vector to compare against
+    fn min(&self, other: &Self) -> Self;
+
+    /// returns a vector containing the largest of each point of the two vectors
+    ///
+    /// # Arguments
+    /// `other` - the vector to compare against
+    fn max(&self, other: &Self) -> Self;
+
+    /// returns the squared length of the vector
+    /// this avoids the square root when only comparing lengths
+    fn length_squared(&self) -> f32 {
+        self.dot(self)
+    }
+
+    /// returns the length (magnitude) of the vector
+    fn length(&self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    /// returns a vector pointing in the same direction with a length of 1
+    ///
+    /// # Remarks
+    ///
+    /// A zero length vector has no direction so is returned unchanged.
+    fn normalize(&self) -> Self {
+        let len = self.length();
+        if len == 0.0 {
+            *self
+        } else {
+            *self * (1.0 / len)
+        }
+    }
+
+    /// Linearly interpolates between this vector and another
+    ///
+    /// # Arguments
+    /// `other` - the vector to interpolate towards
+    /// `t` - the amount to interpolate, 0 returns this vector and 1 returns `other`
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    /// returns a vector with each point restricted to the range of the provided vectors
+    ///
+    /// # Arguments
+    /// `min` - the lower bound for each point
+    /// `max` - the upper bound for each point
+    fn clamp(&self, min: &Self, max: &Self) -> Self {
+        self.max(min).min(max)
+    }
 }
 
 /// A collection of functions for parsing vectors
@@ -81,6 +188,26 @@ impl Vector for Vec3 {
             z: val,
         }
     }
+
+    fn dot(&self, other: &Vec3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    fn min(&self, other: &Vec3) -> Vec3 {
+        Vec3 {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+        }
+    }
+
+    fn max(&self, other: &Vec3) -> Vec3 {
+        Vec3 {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+        }
+    }
 }
 
 impl ParseVector for Vec3 {
@@ -123,6 +250,28 @@ impl Vector for Vec4 {
             w: val,
         }
     }
+
+    fn dot(&self, other: &Vec4) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    fn min(&self, other: &Vec4) -> Vec4 {
+        Vec4 {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            z: self.z.min(other.z),
+            w: self.w.min(other.w),
+        }
+    }
+
+    fn max(&self, other: &Vec4) -> Vec4 {
+        Vec4 {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            z: self.z.max(other.z),
+            w: self.w.max(other.w),
+        }
+    }
 }
 
 impl ParseVector for Vec4 {
@@ -186,6 +335,24 @@ impl Vector for Vec2 {
     fn from_val(val: f32) -> Vec2 {
         Vec2 { x: val, y: val }
     }
+
+    fn dot(&self, other: &Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    fn min(&self, other: &Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+        }
+    }
+
+    fn max(&self, other: &Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+        }
+    }
 }
 
 impl ParseVector for Vec2 {
@@ -251,6 +418,173 @@ impl Index<usize> for Vec4 {
     }
 }
 
+impl IndexMut<usize> for Vec2 {
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            _ => panic!("Index out of range"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Vec3 {
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Index out of range"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Vec4 {
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!("Index out of range"),
+        }
+    }
+}
+
+// Every vector type shares the same component-wise operators and conversions,
+// so they are generated here rather than written out three times.
+macro_rules! impl_vector_ops {
+    ($vec:ident, $len:expr, $tuple:ty, $($field:ident : $idx:tt),+) => {
+        impl Add for $vec {
+            type Output = $vec;
+
+            fn add(self, rhs: $vec) -> $vec {
+                $vec { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl Sub for $vec {
+            type Output = $vec;
+
+            fn sub(self, rhs: $vec) -> $vec {
+                $vec { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl Mul for $vec {
+            type Output = $vec;
+
+            fn mul(self, rhs: $vec) -> $vec {
+                $vec { $($field: self.$field * rhs.$field),+ }
+            }
+        }
+
+        impl Mul<f32> for $vec {
+            type Output = $vec;
+
+            fn mul(self, rhs: f32) -> $vec {
+                $vec { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl Mul<$vec> for f32 {
+            type Output = $vec;
+
+            fn mul(self, rhs: $vec) -> $vec {
+                rhs * self
+            }
+        }
+
+        impl Div for $vec {
+            type Output = $vec;
+
+            fn div(self, rhs: $vec) -> $vec {
+                $vec { $($field: self.$field / rhs.$field),+ }
+            }
+        }
+
+        impl Div<f32> for $vec {
+            type Output = $vec;
+
+            fn div(self, rhs: f32) -> $vec {
+                $vec { $($field: self.$field / rhs),+ }
+            }
+        }
+
+        impl Neg for $vec {
+            type Output = $vec;
+
+            fn neg(self) -> $vec {
+                $vec { $($field: -self.$field),+ }
+            }
+        }
+
+        impl AddAssign for $vec {
+            fn add_assign(&mut self, rhs: $vec) {
+                $(self.$field += rhs.$field;)+
+            }
+        }
+
+        impl SubAssign for $vec {
+            fn sub_assign(&mut self, rhs: $vec) {
+                $(self.$field -= rhs.$field;)+
+            }
+        }
+
+        impl MulAssign for $vec {
+            fn mul_assign(&mut self, rhs: $vec) {
+                $(self.$field *= rhs.$field;)+
+            }
+        }
+
+        impl MulAssign<f32> for $vec {
+            fn mul_assign(&mut self, rhs: f32) {
+                $(self.$field *= rhs;)+
+            }
+        }
+
+        impl DivAssign for $vec {
+            fn div_assign(&mut self, rhs: $vec) {
+                $(self.$field /= rhs.$field;)+
+            }
+        }
+
+        impl DivAssign<f32> for $vec {
+            fn div_assign(&mut self, rhs: f32) {
+                $(self.$field /= rhs;)+
+            }
+        }
+
+        impl From<[f32; $len]> for $vec {
+            fn from(arr: [f32; $len]) -> $vec {
+                $vec { $($field: arr[$idx]),+ }
+            }
+        }
+
+        impl From<$vec> for [f32; $len] {
+            fn from(v: $vec) -> [f32; $len] {
+                [$(v.$field),+]
+            }
+        }
+
+        impl From<$tuple> for $vec {
+            fn from(t: $tuple) -> $vec {
+                $vec { $($field: t.$idx),+ }
+            }
+        }
+
+        impl From<$vec> for $tuple {
+            fn from(v: $vec) -> $tuple {
+                ($(v.$field),+)
+            }
+        }
+    };
+}
+
+impl_vector_ops!(Vec2, 2, (f32, f32), x: 0, y: 1);
+impl_vector_ops!(Vec3, 3, (f32, f32, f32), x: 0, y: 1, z: 2);
+impl_vector_ops!(Vec4, 4, (f32, f32, f32, f32), x: 0, y: 1, z: 2, w: 3);
+
 unsafe impl Attribute for Vec2 {
     fn get_type() -> AttributeType {
         AttributeType::F32F32
@@ -268,3 +602,138 @@ unsafe impl Attribute for Vec4 {
         AttributeType::F32F32F32F32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fixed sequence of vectors with components between -10 and 10
+    fn samples() -> Vec<Vec3> {
+        let mut state = 0x2545_f491u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % 20_001) as f32 / 1000.0 - 10.0
+        };
+
+        (0..200).map(|_| Vec3::new(next(), next(), next())).collect()
+    }
+
+    #[test]
+    fn cross_product_is_orthogonal_to_both_vectors() {
+        let samples = samples();
+        for pair in samples.chunks(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let cross = a.cross(&b);
+            let scale = a.length() * b.length();
+
+            assert!(cross.dot(&a).abs() <= 1e-5 * scale * a.length(), "{:?} {:?}", a, b);
+            assert!(cross.dot(&b).abs() <= 1e-5 * scale * b.length(), "{:?} {:?}", a, b);
+            assert_eq!(b.cross(&a), -cross);
+        }
+    }
+
+    #[test]
+    fn cross_product_follows_the_right_hand_rule() {
+        let (x, y, z) = (
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+
+        assert_eq!(x.cross(&y), z);
+        assert_eq!(y.cross(&z), x);
+        assert_eq!(z.cross(&x), y);
+        assert_eq!(x.cross(&x), Vec3::zero());
+    }
+
+    #[test]
+    fn normalize_gives_unit_length() {
+        for v in samples() {
+            let n = v.normalize();
+            assert!((n.length() - 1.0).abs() < 1e-6, "{:?}", v);
+            assert!(n.dot(&v) > 0.0);
+        }
+
+        let v4 = Vec4::new(3.0, 0.0, 4.0, 0.0).normalize();
+        assert_eq!(v4, Vec4::new(0.6, 0.0, 0.8, 0.0));
+        assert_eq!(Vec2::new(0.0, -2.0).normalize(), Vec2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn normalizing_zero_leaves_it_unchanged() {
+        assert_eq!(Vec3::zero().normalize(), Vec3::zero());
+        assert_eq!(Vec4::zero().normalize(), Vec4::zero());
+    }
+
+    #[test]
+    fn operators_are_component_wise() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, -5.0, 6.0);
+
+        assert_eq!(a + b, Vec3::new(5.0, -3.0, 9.0));
+        assert_eq!(a - b, Vec3::new(-3.0, 7.0, -3.0));
+        assert_eq!(a * b, Vec3::new(4.0, -10.0, 18.0));
+        assert_eq!(b / a, Vec3::new(4.0, -2.5, 2.0));
+        assert_eq!(a * 2.0, Vec3::new(2.0, 4.0, 6.0));
+        assert_eq!(2.0 * a, a * 2.0);
+        assert_eq!(a / 2.0, Vec3::new(0.5, 1.0, 1.5));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+        assert_eq!(a.dot(&b), 12.0);
+    }
+
+    #[test]
+    fn assignment_operators_match_their_operators() {
+        let a = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        let b = Vec4::new(2.0, 4.0, -1.0, 0.5);
+
+        let mut v = a;
+        v += b;
+        assert_eq!(v, a + b);
+        v -= b;
+        assert_eq!(v, a);
+        v *= b;
+        assert_eq!(v, a * b);
+        v /= b;
+        assert_eq!(v, a);
+        v *= 3.0;
+        assert_eq!(v, a * 3.0);
+        v /= 3.0;
+        assert_eq!(v, a);
+    }
+
+    #[test]
+    fn min_max_clamp_and_lerp() {
+        let a = Vec3::new(-1.0, 5.0, 2.0);
+        let b = Vec3::new(3.0, 0.0, 2.0);
+
+        assert_eq!(a.min(&b), Vec3::new(-1.0, 0.0, 2.0));
+        assert_eq!(a.max(&b), Vec3::new(3.0, 5.0, 2.0));
+        assert_eq!(a.clamp(&Vec3::zero(), &Vec3::from_val(1.0)), Vec3::new(0.0, 1.0, 1.0));
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Vec3::new(1.0, 2.5, 2.0));
+    }
+
+    #[test]
+    fn conversions_and_indexing_use_component_order() {
+        let mut v = Vec4::from([1.0, 2.0, 3.0, 4.0]);
+
+        assert_eq!(v, Vec4::from((1.0, 2.0, 3.0, 4.0)));
+        assert_eq!(<[f32; 4]>::from(v), [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!((v[0], v[1], v[2], v[3]), (1.0, 2.0, 3.0, 4.0));
+        v[3] = 0.0;
+        assert_eq!(v.w, 0.0);
+        assert_eq!(v.xyz(), Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn parse_vectors() {
+        assert_eq!(Vec3::from_str("1 -2 3.5"), Ok(Vec3::new(1.0, -2.0, 3.5)));
+        assert_eq!(Vec4::from_str("1 2 3"), Ok(Vec4::new(1.0, 2.0, 3.0, 1.0)));
+        assert_eq!(Vec2::from_str(" 1   2 "), Ok(Vec2::new(1.0, 2.0)));
+        assert!(Vec3::from_str("1 x 3").is_err());
+        assert!(Vec3::from_str("1 2").is_err());
+    }
+}
@@ -1,33 +1,721 @@
 //! This module represents functionality for parsing files
 //! within this engine there are many types that can be loaded from
 //! the file system and the common behaviours and types can be represented here.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor};
+use std::path::{Path, PathBuf};
+
+/// The kind of problem that stopped a file from being parsed
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    /// A value that should be a number could not be parsed as one
+    BadNumber,
+    /// An index refers to an element that does not exist
+    IndexOutOfRange,
+    /// The file, or a file that it includes, could not be found
+    MissingFile,
+    /// The statement is not recognised or not supported
+    UnsupportedStatement,
+    /// The statement is missing values or has values that are not allowed
+    MalformedStatement,
+    /// Reading the file failed
+    Io(io::Error),
+}
+
+/// A position within a file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// The path of the file
+    pub file: String,
+    /// The line number starting from 1, this is 0 when the problem is not on a specific line
+    pub line: usize,
+    /// The column starting from 1, this is 0 when the problem is not at a specific token
+    pub column: usize,
+}
+
+/// An error encountered while parsing a file
+#[derive(Debug)]
+pub struct ParseError {
+    /// What went wrong
+    pub kind: ParseErrorKind,
+    /// A description of the problem
+    pub message: String,
+    /// The token that caused the problem, if there is one
+    pub token: Option<String>,
+    /// Where the problem is, this is `None` until the error has been attached to a file
+    pub location: Option<Location>,
+    /// The statements that included the file with the problem, nearest first.
+    /// For example an obj file that loads a broken mtl file through `mtllib`.
+    pub include_chain: Vec<Location>,
+    // the byte offset of the token within its line, which gives the column once the error is
+    // attached to that line
+    token_offset: Option<usize>,
+}
+
+/// A whitespace separated piece of a line, along with where it starts in that line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// The text of the token
+    pub text: &'a str,
+    /// The byte offset of the token from the start of its line
+    pub offset: usize,
+}
+
+/// An iterator over the whitespace separated tokens of a line, see [`tokenize`]
+#[derive(Clone, Debug)]
+pub struct Tokens<'a> {
+    line: &'a str,
+    position: usize,
+}
+
+/// How a parser reacts to statements that it doesn't recognise or support
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Any unknown or unsupported statement fails the parse, useful for validating content
+    Strict,
+    /// Unknown or unsupported statements are skipped and reported as warnings
+    Lenient,
+}
+
+/// A problem that was skipped over while parsing leniently.
+/// It carries the same kind, token and location that strict parsing would have failed with.
+pub type ParseWarning = ParseError;
+
+/// The result of a parse along with any warnings collected on the way
 #[derive(Debug)]
-/// A helper enum to represent parsing errors
-pub enum ParseError {
-    /// The token being parsed is unrecognised.
-    /// The string should represent the token
-    UnknownToken(String),
-    /// A general error has been encountered that there is no
-    /// specific error for.
-    GeneralError(String),
+pub struct Parsed<T> {
+    /// What was parsed
+    pub value: T,
+    /// The statements that were skipped, in the order they were found
+    pub warnings: Vec<ParseWarning>,
 }
 
 /// A trait that exposes a parse like behaviour
-/// intended 
+/// intended
 pub trait FromFile {
-    /// Represents the result of the parse 
+    /// Represents the result of the parse
     /// This should handle both a succesful or unsuccessful parse
     type ParseResult;
 
     /// a method to handle parsing something from a file provided
-    /// 
+    ///
     /// # Arguments
-    /// 
+    ///
     /// `file` - the path to the file to parse
-    /// 
+    ///
     /// # Remarks
-    /// 
-    /// It's recommended that when implementing this trait, the ParseResult is 
+    ///
+    /// It's recommended that when implementing this trait, the ParseResult is
     /// `Result<T, ParseError>`
     fn from_file(file: &str) -> Self::ParseResult;
 }
+
+/// A trait that exposes parsing from any buffered reader, such as a file,
+/// an archive entry or a string held in memory
+pub trait FromReader {
+    /// Represents the result of the parse
+    /// This should handle both a succesful or unsuccessful parse
+    type ParseResult;
+
+    /// a method to handle parsing something from a reader
+    ///
+    /// # Arguments
+    ///
+    /// `reader` - the source to read from
+    /// `name` - the name of the source, used as the file in error locations
+    /// `resolver` - used to find any other files that the source refers to
+    fn from_reader<R: BufRead>(reader: R, name: &str, resolver: &dyn Resolver) -> Self::ParseResult;
+
+    /// a method to handle parsing something from a string
+    ///
+    /// # Arguments
+    ///
+    /// `source` - the text to parse
+    /// `name` - the name of the source, used as the file in error locations
+    /// `resolver` - used to find any other files that the source refers to
+    fn from_source(source: &str, name: &str, resolver: &dyn Resolver) -> Self::ParseResult {
+        Self::from_reader(source.as_bytes(), name, resolver)
+    }
+}
+
+/// A trait that exposes parsing from a reader in either [`ParseMode`],
+/// with the warnings from a lenient parse returned next to the result
+pub trait FromReaderWithMode {
+    /// The type that is produced by the parse
+    type Output;
+
+    /// a method to handle parsing something from a reader
+    ///
+    /// # Arguments
+    ///
+    /// `reader` - the source to read from
+    /// `name` - the name of the source, used as the file in error and warning locations
+    /// `resolver` - used to find any other files that the source refers to
+    /// `mode` - whether unknown or unsupported statements fail the parse or are skipped
+    fn from_reader_with_mode<R: BufRead>(
+        reader: R,
+        name: &str,
+        resolver: &dyn Resolver,
+        mode: ParseMode,
+    ) -> Result<Parsed<Self::Output>, ParseError>;
+
+    /// a method to handle parsing something from a file
+    ///
+    /// # Arguments
+    ///
+    /// `file` - the path to the file to parse
+    /// `mode` - whether unknown or unsupported statements fail the parse or are skipped
+    fn from_file_with_mode(file: &str, mode: ParseMode) -> Result<Parsed<Self::Output>, ParseError> {
+        let reader = File::open(file).map_err(|e| ParseError::from(e).in_file(file))?;
+
+        Self::from_reader_with_mode(BufReader::new(reader), file, &FileResolver::for_file(file), mode)
+    }
+}
+
+/// A trait for finding the files that another file refers to,
+/// such as the `mtllib` of an obj file or the texture maps of a material
+pub trait Resolver {
+    /// Resolves a path written inside a file into the path it refers to
+    ///
+    /// # Arguments
+    /// `path` - the path as written in the file
+    fn resolve(&self, path: &str) -> String;
+
+    /// Opens a file that is referred to by another file
+    ///
+    /// # Arguments
+    /// `path` - the path as written in the file
+    fn open(&self, path: &str) -> Result<Box<dyn BufRead>, ParseError>;
+}
+
+/// A resolver that finds files on disk relative to a directory
+#[derive(Clone, Debug)]
+pub struct FileResolver {
+    directory: PathBuf,
+}
+
+/// A resolver that finds files in a collection of strings held in memory
+#[derive(Clone, Debug, Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, String>,
+}
+
+impl FileResolver {
+    /// Creates a resolver that resolves paths relative to a directory
+    ///
+    /// # Arguments
+    /// `directory` - the directory that paths are relative to
+    pub fn new<P: AsRef<Path>>(directory: P) -> FileResolver {
+        FileResolver {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Creates a resolver that resolves paths relative to the directory containing a file
+    ///
+    /// # Arguments
+    /// `file` - the path of the file
+    pub fn for_file(file: &str) -> FileResolver {
+        match Path::new(file).parent() {
+            Some(directory) => FileResolver::new(directory),
+            None => FileResolver::new("."),
+        }
+    }
+}
+
+impl Resolver for FileResolver {
+    fn resolve(&self, path: &str) -> String {
+        self.directory.join(path).to_string_lossy().into_owned()
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn BufRead>, ParseError> {
+        let path = self.resolve(path);
+        let file = File::open(&path).map_err(|e| ParseError::from(e).in_file(&path))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+impl MemoryResolver {
+    /// Creates an empty resolver
+    pub fn new() -> MemoryResolver {
+        MemoryResolver {
+            files: HashMap::new(),
+        }
+    }
+
+    /// Adds a file to the resolver
+    ///
+    /// # Arguments
+    /// `path` - the path that the file is referred to by
+    /// `contents` - the contents of the file
+    pub fn with_file(mut self, path: &str, contents: &str) -> MemoryResolver {
+        self.files.insert(path.to_owned(), contents.to_owned());
+        self
+    }
+}
+
+impl Resolver for MemoryResolver {
+    fn resolve(&self, path: &str) -> String {
+        path.to_owned()
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn BufRead>, ParseError> {
+        match self.files.get(path) {
+            Some(contents) => Ok(Box::new(Cursor::new(contents.clone().into_bytes()))),
+            None => Err(
+                ParseError::new(ParseErrorKind::MissingFile, "file not found").in_file(path),
+            ),
+        }
+    }
+}
+
+impl ParseMode {
+    /// Decides whether the result of parsing a statement stops the parse
+    ///
+    /// # Arguments
+    /// `result` - the result of parsing the statement
+    /// `warnings` - where a skipped statement is recorded
+    ///
+    /// # Remarks
+    ///
+    /// Only unsupported statements are skipped when lenient, a malformed statement
+    /// or a missing file still fails the parse.
+    pub fn recover(
+        self,
+        result: Result<(), ParseError>,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<(), ParseError> {
+        let error = match result {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+
+        match (self, &error.kind) {
+            (ParseMode::Lenient, &ParseErrorKind::UnsupportedStatement) => (),
+            _ => return Err(error),
+        }
+
+        warnings.push(error);
+        Ok(())
+    }
+}
+
+impl Default for ParseMode {
+    fn default() -> ParseMode {
+        ParseMode::Strict
+    }
+}
+
+impl Location {
+    /// Creates a new location
+    ///
+    /// # Arguments
+    /// `file` - the path of the file
+    /// `line` - the line number starting from 1
+    /// `column` - the column starting from 1
+    pub fn new(file: &str, line: usize, column: usize) -> Location {
+        Location {
+            file: file.to_owned(),
+            line: line,
+            column: column,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (0, _) => write!(f, "{}", self.file),
+            (line, 0) => write!(f, "{}:{}", self.file, line),
+            (line, column) => write!(f, "{}:{}:{}", self.file, line, column),
+        }
+    }
+}
+
+impl ParseError {
+    /// Creates a new error that isn't attached to a file yet
+    ///
+    /// # Arguments
+    /// `kind` - what went wrong
+    /// `message` - a description of the problem
+    pub fn new(kind: ParseErrorKind, message: &str) -> ParseError {
+        ParseError {
+            kind: kind,
+            message: message.to_owned(),
+            token: None,
+            location: None,
+            include_chain: Vec::new(),
+            token_offset: None,
+        }
+    }
+
+    /// Sets the token that caused the problem
+    ///
+    /// # Arguments
+    /// `token` - the token, its offset gives the column once the error is attached to a line,
+    /// see [`ParseError::at`]
+    pub fn with_token(mut self, token: Token) -> ParseError {
+        self.token = Some(token.text.to_owned());
+        self.token_offset = Some(token.offset);
+        self
+    }
+
+    /// Attaches the error to a line of a file
+    ///
+    /// # Arguments
+    /// `file` - the path of the file
+    /// `line` - the line number starting from 1
+    ///
+    /// # Remarks
+    ///
+    /// The column is where the error's token starts within the line, or 0 when there is no
+    /// token. An error that already has a location, such as one from an included file, is
+    /// unchanged.
+    pub fn at(mut self, file: &str, line: usize) -> ParseError {
+        if self.location.is_none() {
+            let column = self.token_offset.map_or(0, |offset| offset + 1);
+            self.location = Some(Location::new(file, line, column));
+        }
+        self
+    }
+
+    /// Attaches the error to a file without a specific line
+    ///
+    /// # Arguments
+    /// `file` - the path of the file
+    pub fn in_file(mut self, file: &str) -> ParseError {
+        if self.location.is_none() {
+            self.location = Some(Location::new(file, 0, 0));
+        }
+        self
+    }
+
+    /// Records that the file with the problem was included by another file
+    ///
+    /// # Arguments
+    /// `location` - the location of the statement that included the file
+    pub fn included_from(mut self, location: Location) -> ParseError {
+        self.include_chain.push(location);
+        self
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::BadNumber => write!(f, "bad number"),
+            ParseErrorKind::IndexOutOfRange => write!(f, "index out of range"),
+            ParseErrorKind::MissingFile => write!(f, "missing file"),
+            ParseErrorKind::UnsupportedStatement => write!(f, "unsupported statement"),
+            ParseErrorKind::MalformedStatement => write!(f, "malformed statement"),
+            ParseErrorKind::Io(e) => write!(f, "io error ({})", e),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref location) = self.location {
+            write!(f, "{}: ", location)?;
+        }
+
+        write!(f, "{}: {}", self.kind, self.message)?;
+
+        if let Some(ref token) = self.token {
+            write!(f, " `{}`", token)?;
+        }
+
+        for location in self.include_chain.iter() {
+            write!(f, "\n\tincluded from {}", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            ParseErrorKind::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> ParseError {
+        match e.kind() {
+            io::ErrorKind::NotFound => ParseError::new(ParseErrorKind::MissingFile, "file not found"),
+            _ => {
+                let message = format!("{}", e);
+                ParseError::new(ParseErrorKind::Io(e), &message)
+            }
+        }
+    }
+}
+
+impl<'a> Token<'a> {
+    /// Creates a token
+    ///
+    /// # Arguments
+    /// `text` - the text of the token
+    /// `offset` - the byte offset of the token from the start of its line
+    pub fn new(text: &'a str, offset: usize) -> Token<'a> {
+        Token {
+            text: text,
+            offset: offset,
+        }
+    }
+
+    /// returns the text from the start of this token to the end of another token on the same
+    /// line, as a single token
+    ///
+    /// # Arguments
+    /// `line` - the line both tokens were split from
+    /// `last` - the token to end at, this should not come before this token
+    pub fn through(&self, line: &'a str, last: &Token) -> Token<'a> {
+        Token::new(&line[self.offset..last.offset + last.text.len()], self.offset)
+    }
+}
+
+impl<'a> fmt::Display for Token<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Splits a line into its whitespace separated tokens, keeping where each one starts
+///
+/// # Arguments
+/// `line` - the line to split
+pub fn tokenize<'a>(line: &'a str) -> Tokens<'a> {
+    Tokens {
+        line: line,
+        position: 0,
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.line[self.position..];
+        let start = self.position + rest.find(|c: char| !c.is_whitespace())?;
+        let end = self.line[start..]
+            .find(char::is_whitespace)
+            .map_or(self.line.len(), |length| start + length);
+
+        self.position = end;
+        Some(Token::new(&self.line[start..end], start))
+    }
+}
+
+/// Parses a token as a float
+///
+/// # Arguments
+/// `token` - the token to parse
+pub fn parse_f32(token: Token) -> Result<f32, ParseError> {
+    token.text.parse::<f32>().map_err(|_| {
+        ParseError::new(ParseErrorKind::BadNumber, "expected a number").with_token(token)
+    })
+}
+
+/// Parses every token as a float
+///
+/// # Arguments
+/// `tokens` - the tokens to parse
+pub fn parse_floats(tokens: &[Token]) -> Result<Vec<f32>, ParseError> {
+    tokens.iter().map(|&t| parse_f32(t)).collect()
+}
+
+/// Gets the first argument of a statement
+///
+/// # Arguments
+/// `statement` - the statement, used in the error message
+/// `args` - the arguments that followed the statement
+pub fn first_arg<'a>(statement: Token, args: &[Token<'a>]) -> Result<Token<'a>, ParseError> {
+    match args.first() {
+        Some(&arg) => Ok(arg),
+        None => Err(ParseError::new(
+            ParseErrorKind::MalformedStatement,
+            &format!("`{}` is missing a value", statement),
+        )
+        .with_token(statement)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::{Material, MeshDescriptions};
+    use std::env;
+    use std::fs;
+    use std::io::Read;
+    use std::process;
+
+    fn read(mut reader: Box<dyn BufRead>) -> String {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    fn obj_error(source: &str, resolver: &MemoryResolver) -> ParseError {
+        MeshDescriptions::from_source(source, "test.obj", resolver).unwrap_err()
+    }
+
+    #[test]
+    fn error_is_located_at_its_line_and_column() {
+        let error = obj_error("v 0 0 0\n\nvt 0 x 0\n", &MemoryResolver::new());
+
+        assert!(match error.kind {
+            ParseErrorKind::BadNumber => true,
+            _ => false,
+        });
+        assert_eq!(error.token, Some("x".to_owned()));
+        assert_eq!(error.location, Some(Location::new("test.obj", 3, 6)));
+        assert!(error.include_chain.is_empty());
+    }
+
+    #[test]
+    fn column_is_the_token_that_failed_when_it_is_repeated() {
+        // the value of `s` is the same text as the statement
+        let error = obj_error("s s\n", &MemoryResolver::new());
+        assert_eq!(error.location, Some(Location::new("test.obj", 1, 3)));
+
+        let resolver = MemoryResolver::new();
+        let error = Material::from_source("newmtl m\n  d   d\n", "test.mtl", &resolver).unwrap_err();
+        assert_eq!(error.location, Some(Location::new("test.mtl", 2, 7)));
+    }
+
+    #[test]
+    fn errors_in_included_files_record_where_they_were_included() {
+        let resolver = MemoryResolver::new().with_file("broken.mtl", "newmtl m\nKd 1 x 1\n");
+        let error = obj_error("v 0 0 0\nmtllib broken.mtl\n", &resolver);
+
+        assert_eq!(error.location, Some(Location::new("broken.mtl", 2, 6)));
+        assert_eq!(error.include_chain, vec![Location::new("test.obj", 2, 1)]);
+        assert_eq!(
+            error.to_string(),
+            "broken.mtl:2:6: bad number: expected a number `x`\n\tincluded from test.obj:2:1"
+        );
+    }
+
+    #[test]
+    fn missing_included_file_is_reported() {
+        let error = obj_error("mtllib missing.mtl\n", &MemoryResolver::new());
+
+        assert!(match error.kind {
+            ParseErrorKind::MissingFile => true,
+            _ => false,
+        });
+        assert_eq!(error.location, Some(Location::new("missing.mtl", 0, 0)));
+        assert_eq!(error.include_chain, vec![Location::new("test.obj", 1, 1)]);
+    }
+
+    #[test]
+    fn an_attached_error_keeps_its_location() {
+        let error = ParseError::new(ParseErrorKind::MalformedStatement, "bad")
+            .with_token(Token::new("token", 0))
+            .in_file("inner.mtl")
+            .at("outer.obj", 4);
+
+        assert_eq!(error.location, Some(Location::new("inner.mtl", 0, 0)));
+    }
+
+    #[test]
+    fn tokens_keep_their_byte_offsets() {
+        let line = "  usemtl\tstone é  wall ";
+        let tokens = tokenize(line).collect::<Vec<Token>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new("usemtl", 2),
+                Token::new("stone", 9),
+                Token::new("é", 15),
+                Token::new("wall", 19),
+            ]
+        );
+        assert_eq!(tokens[1].through(line, &tokens[3]).text, "stone é  wall");
+        assert!(tokenize(" \t ").next().is_none());
+    }
+
+    #[test]
+    fn column_is_after_the_token_offset() {
+        let error = ParseError::new(ParseErrorKind::BadNumber, "bad")
+            .with_token(Token::new("x", 5))
+            .at("a.obj", 3);
+        assert_eq!(error.location, Some(Location::new("a.obj", 3, 6)));
+
+        let error = ParseError::new(ParseErrorKind::BadNumber, "bad").at("a.obj", 3);
+        assert_eq!(error.location, Some(Location::new("a.obj", 3, 0)));
+    }
+
+    #[test]
+    fn location_display_leaves_out_unknown_parts() {
+        assert_eq!(Location::new("a.obj", 0, 0).to_string(), "a.obj");
+        assert_eq!(Location::new("a.obj", 3, 0).to_string(), "a.obj:3");
+        assert_eq!(Location::new("a.obj", 3, 7).to_string(), "a.obj:3:7");
+    }
+
+    #[test]
+    fn lenient_mode_only_skips_unsupported_statements() {
+        let unsupported = || Err(ParseError::new(ParseErrorKind::UnsupportedStatement, "unknown"));
+        let malformed = || Err(ParseError::new(ParseErrorKind::MalformedStatement, "malformed"));
+        let mut warnings = Vec::new();
+
+        assert!(ParseMode::Lenient.recover(unsupported(), &mut warnings).is_ok());
+        assert!(ParseMode::Lenient.recover(malformed(), &mut warnings).is_err());
+        assert!(ParseMode::Strict.recover(unsupported(), &mut warnings).is_err());
+        assert!(ParseMode::Strict.recover(Ok(()), &mut warnings).is_ok());
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn memory_resolver_opens_the_files_it_holds() {
+        let resolver = MemoryResolver::new().with_file("a.mtl", "newmtl a\n");
+
+        assert_eq!(resolver.resolve("a.mtl"), "a.mtl");
+        assert_eq!(read(resolver.open("a.mtl").unwrap()), "newmtl a\n");
+
+        let error = resolver.open("b.mtl").err().unwrap();
+        assert!(match error.kind {
+            ParseErrorKind::MissingFile => true,
+            _ => false,
+        });
+        assert_eq!(error.location, Some(Location::new("b.mtl", 0, 0)));
+    }
+
+    #[test]
+    fn file_resolver_opens_files_next_to_the_file() {
+        let root = env::temp_dir().join(format!("parser-resolver-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.mtl"), "newmtl a\n").unwrap();
+
+        let file = root.join("a.obj").to_string_lossy().into_owned();
+        let resolver = FileResolver::for_file(&file);
+        let resolved = resolver.resolve("a.mtl");
+        let contents = resolver.open("a.mtl").map(read);
+        let missing = resolver.open("b.mtl").err();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(Path::new(&resolved), root.join("a.mtl").as_path());
+        assert_eq!(contents.unwrap(), "newmtl a\n");
+
+        let missing = missing.unwrap();
+        assert!(match missing.kind {
+            ParseErrorKind::MissingFile => true,
+            _ => false,
+        });
+        let location = missing.location.unwrap();
+        assert_eq!(Path::new(&location.file), root.join("b.mtl").as_path());
+    }
+
+    #[test]
+    fn file_resolver_for_a_bare_file_uses_the_current_directory() {
+        let resolver = FileResolver::for_file("a.obj");
+        assert_eq!(Path::new(&resolver.resolve("a.mtl")), Path::new("a.mtl"));
+    }
+}
@@ -11,11 +11,15 @@ use clap::{App, Arg};
 
 use rust_game_engine::{
     graphics::MeshDescriptions,
-    parser::{FromFile, ParseError},
+    io::Vfs,
+    parser::ParseMode,
 };
 
 use sandbox::run_sandbox;
 
+use std::path::Path;
+use std::sync::Arc;
+
 fn main() {
     const TITLE: &str = "Rust Game Engine";
 
@@ -26,39 +30,84 @@ fn main() {
                 .long("scene")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("content")
+                .short("c")
+                .long("content")
+                .takes_value(true)
+                .help("The directory or archive to load content from"),
+        )
+        .arg(
+            Arg::with_name("ignore-case")
+                .long("ignore-case")
+                .help("Ignores the case of content paths"),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("Fails to load content containing unknown or unsupported statements"),
+        )
+        .arg(
+            Arg::with_name("hot-reload")
+                .long("hot-reload")
+                .help("Reloads content when it changes on disk"),
+        )
         .get_matches();
 
+    let content = matches.value_of("content").unwrap_or("./content");
+    let mut vfs = Vfs::new();
+    if Path::new(content).is_file() {
+        vfs.mount_archive("content", content)
+            .expect(format!("Failed to open archive {}", content).as_str());
+    } else {
+        vfs.mount_directory("content", content);
+    }
+    vfs.set_case_insensitive(matches.is_present("ignore-case"));
+
+    let vfs = Arc::new(vfs);
+    let hot_reload = matches.is_present("hot-reload");
+    let mode = if matches.is_present("strict") {
+        ParseMode::Strict
+    } else {
+        ParseMode::Lenient
+    };
+
     match matches.value_of("scene") {
-        None => run_scene("sandbox", TITLE),
-        Some(s) => run_scene(s, TITLE),
+        None => run_scene(vfs, "sandbox", TITLE, hot_reload, mode),
+        Some(s) => run_scene(vfs, s, TITLE, hot_reload, mode),
     }
 }
 
-fn run_scene(scene: &str, title: &str) {
+fn run_scene(vfs: Arc<Vfs>, scene: &str, title: &str, hot_reload: bool, mode: ParseMode) {
     match &scene.to_lowercase()[..] {
-        "sandbox" => run_sandbox(title),
+        "sandbox" => run_sandbox(vfs, title, hot_reload),
         "falcon" => load_mesh_scene(
-            "./content/Millenium Falcon/millenium-falcon.obj",
+            &vfs,
+            "content/Millenium Falcon/millenium-falcon.obj",
             "Millenium Falcon",
+            mode,
         ),
-        "earth" => load_mesh_scene("./content/Earth/earth.obj", "Earth"),
-        "ironman" => load_mesh_scene("./content/IronMan/IronMan.obj", "IronMan"),
+        "earth" => load_mesh_scene(&vfs, "content/Earth/earth.obj", "Earth", mode),
+        "ironman" => load_mesh_scene(&vfs, "content/IronMan/IronMan.obj", "IronMan", mode),
         _ => println!("unrecognised scene!"),
     }
 }
 
-fn load_mesh_scene(mesh_file: &str, desc: &str) {
-    let mesh = load_mesh(&mesh_file);
+fn load_mesh_scene(vfs: &Vfs, mesh_file: &str, desc: &str, mode: ParseMode) {
+    let mesh = load_mesh(vfs, &mesh_file, mode);
     println!("Loaded {}!", &desc);
     println!("{}", &mesh);
+    println!("Bounds: {}", &mesh.bounds());
 }
 
-fn load_mesh(mesh_file: &str) -> MeshDescriptions {
-    match MeshDescriptions::from_file(&mesh_file) {
-        Ok(m) => m,
-        Err(e) => match e {
-            ParseError::UnknownToken(err) => panic!("Unknown token: {}", err),
-            ParseError::GeneralError(err) => panic!("Parsing error: {}", err),
-        },
+fn load_mesh(vfs: &Vfs, mesh_file: &str, mode: ParseMode) -> MeshDescriptions {
+    match vfs.parse_with_mode::<MeshDescriptions, _>(&mesh_file, mode) {
+        Ok(parsed) => {
+            for warning in parsed.warnings.iter() {
+                eprintln!("Skipped {}", warning);
+            }
+            parsed.value
+        }
+        Err(e) => panic!("{}", e),
     }
 }
@@ -0,0 +1,89 @@
+extern crate clap;
+extern crate rust_game_engine;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use rust_game_engine::io::{pack_directory, Archive, Compression};
+
+use std::process;
+
+fn main() {
+    let matches = App::new("Rust Game Engine Packer")
+        .about("Packs content into archives and unpacks them again")
+        .subcommand(
+            SubCommand::with_name("pack")
+                .about("Packs a directory into an archive")
+                .arg(Arg::with_name("directory").required(true))
+                .arg(Arg::with_name("archive").required(true))
+                .arg(
+                    Arg::with_name("store")
+                        .long("store")
+                        .help("Stores files without compressing them"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unpack")
+                .about("Unpacks an archive into a directory")
+                .arg(Arg::with_name("archive").required(true))
+                .arg(Arg::with_name("directory").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("Lists the contents of an archive")
+                .arg(Arg::with_name("archive").required(true)),
+        )
+        .get_matches();
+
+    let result = match matches.subcommand() {
+        ("pack", Some(m)) => pack(m),
+        ("unpack", Some(m)) => unpack(m),
+        ("list", Some(m)) => list(m),
+        _ => {
+            println!("{}", matches.usage());
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn pack(matches: &ArgMatches) -> std::io::Result<()> {
+    let directory = matches.value_of("directory").unwrap();
+    let archive = matches.value_of("archive").unwrap();
+    let compression = if matches.is_present("store") {
+        Compression::None
+    } else {
+        Compression::Deflate
+    };
+
+    let count = pack_directory(directory, archive, compression)?;
+    println!("Packed {} files from {} into {}", count, directory, archive);
+    Ok(())
+}
+
+fn unpack(matches: &ArgMatches) -> std::io::Result<()> {
+    let archive = matches.value_of("archive").unwrap();
+    let directory = matches.value_of("directory").unwrap();
+
+    let count = Archive::open(archive)?.unpack(directory)?;
+    println!(
+        "Unpacked {} files from {} into {}",
+        count, archive, directory
+    );
+    Ok(())
+}
+
+fn list(matches: &ArgMatches) -> std::io::Result<()> {
+    let archive = Archive::open(matches.value_of("archive").unwrap())?;
+
+    for entry in archive.entries() {
+        println!(
+            "{}\t{} bytes\t{} stored\t{:?}",
+            entry.path, entry.size, entry.stored_size, entry.compression
+        );
+    }
+    Ok(())
+}
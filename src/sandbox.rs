@@ -6,21 +6,23 @@ use glium::{
 };
 
 use rust_game_engine::{
+    assets::{AssetManager, Handle, LoadEvent},
     engine::{create_engine, run},
     game::Game,
-    graphics::{
-        create_billboard, create_shader, load_texture, TextureConvert, VertexPositionNormalTexture,
-        FOV
-    },
-    math::{Mat4x4, Matrix, Vec3},
+    io::Vfs,
+    graphics::{create_billboard, VertexPositionNormalTangentTexture, FOV},
+    math::{Mat3, Mat4x4, Matrix, Vec3, Vector},
 };
 
+use std::sync::Arc;
+
 struct DemoGame {
-    shape: VertexBuffer<VertexPositionNormalTexture>,
-    diffuse_texture: SrgbTexture2d,
-    normal_map: Texture2d,
-    program: Program,
-    model: Mat4x4,
+    assets: AssetManager,
+    shape: VertexBuffer<VertexPositionNormalTangentTexture>,
+    diffuse_texture: Handle<SrgbTexture2d>,
+    normal_map: Handle<Texture2d>,
+    program: Handle<Program>,
+    rotation: f32,
     view: Mat4x4,
 }
 
@@ -31,7 +33,26 @@ impl Game for DemoGame {
         let perspective: Mat4x4 =
             Mat4x4::perspective(frame.get_dimensions(), FOV, (0.1f32, 1024.0f32));
 
-        let light = [1.4, 0.4, 0.7f32];
+        let model = Mat4x4::trs(
+            &Vec3::zero(),
+            &Mat4x4::rotation_y(self.rotation),
+            &Vec3::from_val(1.0),
+        );
+        let modelview = self.view * model;
+        let normal_matrix = Mat3::normal_matrix(&modelview).unwrap_or_else(Mat3::identity);
+
+        // the camera looks along positive z, so the light is in front of the billboard
+        let light = [1.4, 0.4, -0.7f32];
+
+        let (diffuse_texture, normal_map, program) = match (
+            self.assets.srgb_texture(&self.diffuse_texture),
+            self.assets.texture(&self.normal_map),
+            self.assets.program(&self.program),
+        ) {
+            (Some(d), Some(n), Some(p)) => (d, n, p),
+            // something failed to load, which has already been reported
+            _ => return self,
+        };
 
         let params = glium::DrawParameters {
             depth: glium::Depth {
@@ -46,30 +67,59 @@ impl Game for DemoGame {
             .draw(
                 &self.shape,
                 NoIndices(PrimitiveType::TriangleStrip),
-                &self.program,
-                &uniform! { model: self.model.to_array(),
-                view: self.view.to_array(),
+                program,
+                &uniform! { modelview: modelview.to_array(),
+                normal_matrix: normal_matrix.to_array(),
                 perspective: perspective.to_array(),
-                u_light: light, diffuse_tex: &self.diffuse_texture, normal_tex: &self.normal_map },
+                u_light: light, diffuse_tex: diffuse_texture, normal_tex: normal_map },
                 &params,
             )
             .unwrap();
 
-        DemoGame { ..self }
+        DemoGame {
+            rotation: self.rotation + 0.01,
+            ..self
+        }
+    }
+
+    fn assets(&mut self) -> Option<&mut AssetManager> {
+        Some(&mut self.assets)
+    }
+
+    fn on_asset_event(&mut self, event: &LoadEvent) {
+        match event.result {
+            Err(ref e) if event.reloaded => eprintln!(
+                "Failed to reload {}, keeping the last version: {}",
+                event.path, e
+            ),
+            Err(ref e) => eprintln!("Failed to load {}: {}", event.path, e),
+            Ok(()) if event.reloaded => println!("Reloaded {}", event.path),
+            Ok(()) => (),
+        }
     }
 }
 
-fn create_demo_game(display: &glium::Display) -> DemoGame {
+fn create_demo_game(vfs: Arc<Vfs>, display: &glium::Display, hot_reload: bool) -> DemoGame {
+    let mut assets = AssetManager::new(vfs);
+    assets.set_hot_reload(hot_reload);
+
+    let diffuse_texture = assets
+        .load_srgb_texture_async("content/tuto-14-diffuse.jpg")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let normal_map = assets
+        .load_texture_async("content/tuto-14-normal.png")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let program = assets
+        .load_program_async("content/vertex_shader.glsl", "content/fragment_shader.glsl")
+        .unwrap_or_else(|e| panic!("{}", e));
+
     DemoGame {
+        assets: assets,
         shape: create_billboard(&display),
-        diffuse_texture: load_texture("./content/tuto-14-diffuse.jpg").as_srgb_texture_2d(&display),
-        normal_map: load_texture("./content/tuto-14-normal.png").as_texture_2d(&display),
-        program: create_shader(
-            "./content/vertex_shader.glsl",
-            "./content/fragment_shader.glsl",
-            &display,
-        ),
-        model: Mat4x4::identity(),
+        diffuse_texture: diffuse_texture,
+        normal_map: normal_map,
+        program: program,
+        rotation: 0.0,
         view: Mat4x4::view(
             &Vec3 {
                 x: 0.5,
@@ -90,10 +140,10 @@ fn create_demo_game(display: &glium::Display) -> DemoGame {
     }
 }
 
-pub fn run_sandbox(title: &str) {
+pub fn run_sandbox(vfs: Arc<Vfs>, title: &str, hot_reload: bool) {
     let mut events_loop = glium::glutin::EventsLoop::new();
     let display = create_engine(&events_loop, title);
-    let game = create_demo_game(&display);
+    let game = create_demo_game(vfs, &display, hot_reload);
 
     run(&display, &mut events_loop, game);
 }